@@ -1,4 +1,15 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::hash::{Hash, Hasher};
+use std::io::{self, BufRead};
+
+use unicode_ident::{is_xid_continue, is_xid_start};
+use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+
+use super::source::CharSource;
+use super::span::Span;
+use crate::ch02::symbol_table::interner::{Interner, Symbol};
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub enum Word {
@@ -7,6 +18,17 @@ pub enum Word {
     False,
 }
 
+// The tags that Word tokens are classified with. A language definition
+// can bring its own tag type for its keywords; words that are not
+// keywords are tagged IDENTIFIER.
+pub trait WordTag: fmt::Debug + Hash + Eq + Copy {
+    const IDENTIFIER: Self;
+}
+
+impl WordTag for Word {
+    const IDENTIFIER: Self = Word::Identifier;
+}
+
 #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
 pub enum LogicalOperator {
     Less,
@@ -17,273 +39,1138 @@ pub enum LogicalOperator {
     GreaterOrEqual,
 }
 
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum ArithmeticOperator {
+    Plus,
+    Minus,
+    Times,
+    Divide,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum LogicalConnective {
+    And,
+    Or,
+    Not,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum BitwiseOperator {
+    And,
+    Or,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum Punctuation {
+    OpenParen,
+    CloseParen,
+    OpenBrace,
+    CloseBrace,
+    Semicolon,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum NumberValue {
+    Integer(i64),
+    Float(f64),
+}
+
+impl NumberValue {
+    pub fn as_f64(&self) -> f64 {
+        match self {
+            NumberValue::Integer(value) => *value as f64,
+            NumberValue::Float(value) => *value,
+        }
+    }
+}
+
+// Floats are compared bitwise so that tokens can keep deriving Eq and Hash;
+// the lexer never produces NaN, so this agrees with numeric equality
+impl PartialEq for NumberValue {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (NumberValue::Integer(a), NumberValue::Integer(b)) => a == b,
+            (NumberValue::Float(a), NumberValue::Float(b)) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for NumberValue {}
+
+impl Hash for NumberValue {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        match self {
+            NumberValue::Integer(value) => value.hash(state),
+            NumberValue::Float(value) => value.to_bits().hash(state),
+        }
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum LexicalError {
+    IntegerOverflow,
+    FloatOverflow,
+    MalformedNumber,
+    UnterminatedString,
+    UnterminatedChar,
+    InvalidEscape,
+    MalformedChar,
+    UnterminatedComment,
+    IllegalCharacter(char),
+}
+
+impl Display for LexicalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LexicalError::IntegerOverflow => write!(f, "integer literal does not fit in i64"),
+            LexicalError::FloatOverflow => write!(f, "float literal does not fit in f64"),
+            LexicalError::MalformedNumber => write!(f, "malformed number literal"),
+            LexicalError::UnterminatedString => write!(f, "unterminated string literal"),
+            LexicalError::UnterminatedChar => write!(f, "unterminated char literal"),
+            LexicalError::InvalidEscape => write!(f, "invalid escape sequence"),
+            LexicalError::MalformedChar => {
+                write!(f, "char literal must contain exactly one character")
+            }
+            LexicalError::UnterminatedComment => write!(f, "unterminated block comment"),
+            LexicalError::IllegalCharacter(c) => write!(f, "illegal character {:?}", c),
+        }
+    }
+}
+
+// Lexemes borrow from the input when lexing a &str, and are only copied
+// when lexing from a reader, whose text is not kept around. Decoded values,
+// e.g. the text of a string literal after its escapes, are always owned.
 #[derive(Debug, Hash, PartialEq, Eq, Clone)]
-pub enum Token {
-    Word(Word, String),
-    Number(u32, u32),
-    LogicalOperator(LogicalOperator, String),
-    Unknown(String),
+pub enum Token<'a, K = Word> {
+    Word(K, Symbol),
+    Number(NumberValue, Cow<'a, str>),
+    StringLiteral(String, Cow<'a, str>),
+    CharLiteral(char, Cow<'a, str>),
+    LogicalOperator(LogicalOperator, Cow<'a, str>),
+    ArithmeticOperator(ArithmeticOperator, Cow<'a, str>),
+    LogicalConnective(LogicalConnective, Cow<'a, str>),
+    BitwiseOperator(BitwiseOperator, Cow<'a, str>),
+    Assign(Cow<'a, str>),
+    Punctuation(Punctuation, Cow<'a, str>),
+    Comment(Cow<'a, str>),
+    DocComment(String, Cow<'a, str>),
+    Error(LexicalError, Cow<'a, str>),
+    Unknown(Cow<'a, str>),
     Epsilon,
 }
 
-type Lexeme = String;
+impl<K> Token<'_, K> {
+    // The same token with its lexeme copied, so it can outlive the input
+    pub fn into_owned(self) -> Token<'static, K> {
+        let owned = |lexeme: Cow<str>| Cow::Owned(lexeme.into_owned());
+        match self {
+            Token::Word(tag, symbol) => Token::Word(tag, symbol),
+            Token::Number(value, lexeme) => Token::Number(value, owned(lexeme)),
+            Token::StringLiteral(value, lexeme) => Token::StringLiteral(value, owned(lexeme)),
+            Token::CharLiteral(value, lexeme) => Token::CharLiteral(value, owned(lexeme)),
+            Token::LogicalOperator(tag, lexeme) => Token::LogicalOperator(tag, owned(lexeme)),
+            Token::ArithmeticOperator(tag, lexeme) => Token::ArithmeticOperator(tag, owned(lexeme)),
+            Token::LogicalConnective(tag, lexeme) => Token::LogicalConnective(tag, owned(lexeme)),
+            Token::BitwiseOperator(tag, lexeme) => Token::BitwiseOperator(tag, owned(lexeme)),
+            Token::Assign(lexeme) => Token::Assign(owned(lexeme)),
+            Token::Punctuation(tag, lexeme) => Token::Punctuation(tag, owned(lexeme)),
+            Token::Comment(lexeme) => Token::Comment(owned(lexeme)),
+            Token::DocComment(text, lexeme) => Token::DocComment(text, owned(lexeme)),
+            Token::Error(error, lexeme) => Token::Error(error, owned(lexeme)),
+            Token::Unknown(lexeme) => Token::Unknown(owned(lexeme)),
+            Token::Epsilon => Token::Epsilon,
+        }
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub struct SpannedToken<'a, K = Word> {
+    pub token: Token<'a, K>,
+    pub span: Span,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum TriviaKind {
+    Whitespace,
+    Newline,
+    Comment,
+    DocComment,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub struct Trivia<'a> {
+    pub kind: TriviaKind,
+    pub text: Cow<'a, str>,
+    pub span: Span,
+}
+
+// A token together with the exact source text around it. Trailing trivia
+// runs up to (not including) the next newline; everything else before a
+// token is its leading trivia. The last token is always Epsilon, which
+// carries whatever trivia ends the input.
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub struct LosslessToken<'a, K = Word> {
+    pub leading_trivia: Vec<Trivia<'a>>,
+    pub token: SpannedToken<'a, K>,
+    pub text: Cow<'a, str>,
+    pub trailing_trivia: Vec<Trivia<'a>>,
+}
+
+impl<K> LosslessToken<'_, K> {
+    pub fn full_text(&self) -> String {
+        let mut full_text = String::new();
+        for trivia in self.leading_trivia.iter() {
+            full_text.push_str(&trivia.text);
+        }
+        full_text.push_str(&self.text);
+        for trivia in self.trailing_trivia.iter() {
+            full_text.push_str(&trivia.text);
+        }
+        full_text
+    }
+}
+
+enum LosslessPiece<'a, K> {
+    Token(SpannedToken<'a, K>, Cow<'a, str>),
+    Trivia(Trivia<'a>),
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub struct SpannedLexicalError {
+    pub error: LexicalError,
+    pub span: Span,
+}
+
+impl Display for SpannedLexicalError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}",
+            self.span.start.line, self.span.start.column, self.error
+        )
+    }
+}
+
+// Which characters separate tokens. Both treat "\r\n" as a single line break;
+// a lone "\r" is whitespace but does not start a new line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Whitespace {
+    // Space, \t, \n, \r, vertical tab and form feed
+    Ascii,
+    // Everything with the Unicode White_Space property, e.g. U+00A0 and U+3000
+    Unicode,
+}
+
+impl Whitespace {
+    fn contains(&self, c: char) -> bool {
+        match self {
+            Whitespace::Ascii => matches!(c, ' ' | '\t' | '\n' | '\r' | '\x0B' | '\x0C'),
+            Whitespace::Unicode => c.is_whitespace(),
+        }
+    }
+}
+
+// What an operator or punctuation lexeme is lexed as
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum Operator {
+    Logical(LogicalOperator),
+    Arithmetic(ArithmeticOperator),
+    Connective(LogicalConnective),
+    Bitwise(BitwiseOperator),
+    Assign,
+    Punctuation(Punctuation),
+}
+
+impl Operator {
+    pub fn defaults() -> Vec<(&'static str, Operator)> {
+        vec![
+            ("<=", Operator::Logical(LogicalOperator::LessOrEqual)),
+            ("==", Operator::Logical(LogicalOperator::Equal)),
+            ("!=", Operator::Logical(LogicalOperator::Different)),
+            (">=", Operator::Logical(LogicalOperator::GreaterOrEqual)),
+            ("<", Operator::Logical(LogicalOperator::Less)),
+            (">", Operator::Logical(LogicalOperator::Greater)),
+            ("&&", Operator::Connective(LogicalConnective::And)),
+            ("||", Operator::Connective(LogicalConnective::Or)),
+            ("!", Operator::Connective(LogicalConnective::Not)),
+            ("&", Operator::Bitwise(BitwiseOperator::And)),
+            ("|", Operator::Bitwise(BitwiseOperator::Or)),
+            ("+", Operator::Arithmetic(ArithmeticOperator::Plus)),
+            ("-", Operator::Arithmetic(ArithmeticOperator::Minus)),
+            ("*", Operator::Arithmetic(ArithmeticOperator::Times)),
+            ("/", Operator::Arithmetic(ArithmeticOperator::Divide)),
+            ("=", Operator::Assign),
+            ("(", Operator::Punctuation(Punctuation::OpenParen)),
+            (")", Operator::Punctuation(Punctuation::CloseParen)),
+            ("{", Operator::Punctuation(Punctuation::OpenBrace)),
+            ("}", Operator::Punctuation(Punctuation::CloseBrace)),
+            (";", Operator::Punctuation(Punctuation::Semicolon)),
+        ]
+    }
+
+    pub(crate) fn token<'a, K>(self, lexeme: Cow<'a, str>) -> Token<'a, K> {
+        match self {
+            Operator::Logical(tag) => Token::LogicalOperator(tag, lexeme),
+            Operator::Arithmetic(tag) => Token::ArithmeticOperator(tag, lexeme),
+            Operator::Connective(tag) => Token::LogicalConnective(tag, lexeme),
+            Operator::Bitwise(tag) => Token::BitwiseOperator(tag, lexeme),
+            Operator::Assign => Token::Assign(lexeme),
+            Operator::Punctuation(tag) => Token::Punctuation(tag, lexeme),
+        }
+    }
+}
+
+// Comment delimiters; None disables that kind of comment. A comment whose
+// opening delimiter is followed by one more copy of its last char, e.g.
+// "///" or "/**", is a doc comment.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CommentStyle {
+    pub line: Option<String>,
+    pub block: Option<(String, String)>,
+}
+
+impl CommentStyle {
+    // "// ..." and "/* ... */"
+    pub fn c_like() -> Self {
+        CommentStyle {
+            line: Some("//".to_string()),
+            block: Some(("/*".to_string(), "*/".to_string())),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum CommentKind {
+    Line,
+    Block,
+}
+
+// Defines the language a Lexer reads: its keywords and the tags they map
+// to, its operators and its comment delimiters. Literals, numbers and
+// identifiers are lexed the same way for every language.
+pub struct LexerBuilder<K = Word> {
+    keywords: Vec<(String, K)>,
+    operators: Vec<(String, Operator)>,
+    comments: CommentStyle,
+}
+
+impl<K: WordTag> Default for LexerBuilder<K> {
+    fn default() -> Self {
+        LexerBuilder::new()
+    }
+}
+
+impl<K: WordTag> LexerBuilder<K> {
+    // No keywords, the default operators and C-like comments
+    pub fn new() -> Self {
+        LexerBuilder {
+            keywords: Vec::new(),
+            operators: Vec::new(),
+            comments: CommentStyle::c_like(),
+        }
+        .operators(Operator::defaults())
+    }
+
+    pub fn keywords<'k>(mut self, keywords: impl IntoIterator<Item = (&'k str, K)>) -> Self {
+        self.keywords.extend(
+            keywords
+                .into_iter()
+                .map(|(lexeme, tag)| (lexeme.nfc().collect(), tag)),
+        );
+        self
+    }
+
+    // Replaces the whole operator table. The lexer always takes the longest
+    // operator that matches, and when a lexeme is listed twice the last
+    // entry wins. Operators made of identifier chars are never matched,
+    // since those are lexed as words: declare them as keywords instead.
+    pub fn operators<'o>(
+        mut self,
+        operators: impl IntoIterator<Item = (&'o str, Operator)>,
+    ) -> Self {
+        self.operators.clear();
+        for (lexeme, operator) in operators.into_iter() {
+            self.operators.retain(|(existing, _)| existing != lexeme);
+            self.operators.push((lexeme.to_string(), operator));
+        }
+        self.operators
+            .sort_by_key(|(lexeme, _)| std::cmp::Reverse(lexeme.chars().count()));
+        self
+    }
+
+    pub fn comments(mut self, comments: CommentStyle) -> Self {
+        self.comments = comments;
+        self
+    }
+
+    pub fn build(self, input: &str) -> Lexer<'_, K> {
+        Lexer::from_source(CharSource::from_text(input), self)
+    }
 
-pub struct Lexer {
-    input: Vec<char>,
+    pub fn build_from_reader<'a, R: BufRead + 'a>(self, reader: R) -> Lexer<'a, K> {
+        Lexer::from_source(CharSource::from_reader(reader), self)
+    }
+}
+
+pub struct Lexer<'a, K = Word> {
+    source: CharSource<'a>,
     peek_index: usize,
-    words: HashMap<Lexeme, Token>,
+    interner: Interner,
+    keywords: HashMap<Symbol, K>,
+    operators: Vec<(String, Operator)>,
+    comments: CommentStyle,
+    token_start: usize,
+    finished: bool,
+    errors: Vec<SpannedLexicalError>,
+    recover: bool,
+    nested_comments: bool,
+    keep_comments: bool,
+    whitespace: Whitespace,
 }
 
-impl Lexer {
-    pub fn new(input: &str) -> Self {
-        let mut words = HashMap::new();
-        for reserved_kw in Lexer::get_reserved_keywords().into_iter() {
-            match &reserved_kw {
-                Token::Word(_, lexeme) => {
-                    words.insert(lexeme.clone(), reserved_kw);
+impl<'a> Lexer<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Lexer::builder().build(input)
+    }
+
+    // Lexes incrementally from any buffered reader, so arbitrarily large
+    // inputs can be processed while only holding the current token, and the
+    // whitespace and comments before it, in memory
+    pub fn from_reader<R: BufRead + 'a>(reader: R) -> Self {
+        Lexer::builder().build_from_reader(reader)
+    }
+
+    fn builder() -> LexerBuilder {
+        LexerBuilder::new().keywords(Lexer::get_reserved_keywords())
+    }
+
+    fn get_reserved_keywords() -> Vec<(&'static str, Word)> {
+        vec![("true", Word::True), ("false", Word::False)]
+    }
+}
+
+impl<'a, K: WordTag> Lexer<'a, K> {
+    fn from_source(source: CharSource<'a>, builder: LexerBuilder<K>) -> Self {
+        let mut interner = Interner::new();
+        let keywords = builder
+            .keywords
+            .iter()
+            .map(|(lexeme, tag)| (interner.intern(lexeme), *tag))
+            .collect();
+
+        Lexer {
+            source,
+            peek_index: 0,
+            interner,
+            keywords,
+            operators: builder.operators,
+            comments: builder.comments,
+            token_start: 0,
+            finished: false,
+            errors: Vec::new(),
+            recover: true,
+            nested_comments: false,
+            keep_comments: false,
+            whitespace: Whitespace::Unicode,
+        }
+    }
+
+    // With recovery on (the default) the lexer keeps going after an error so
+    // every error in the input is reported in one pass; with it off,
+    // tokenize stops right after the first error
+    pub fn with_recovery(mut self, recover: bool) -> Self {
+        self.recover = recover;
+        self
+    }
+
+    // When on, "/* /* */ */" is a single comment: every "/*" inside a block
+    // comment must be closed by its own "*/"
+    pub fn with_nested_comments(mut self, nested_comments: bool) -> Self {
+        self.nested_comments = nested_comments;
+        self
+    }
+
+    // When on, comments are returned as Comment/DocComment tokens instead of
+    // being discarded. With C-like comments, "///" and "/** */" comments are
+    // doc comments.
+    pub fn with_comments(mut self, keep_comments: bool) -> Self {
+        self.keep_comments = keep_comments;
+        self
+    }
+
+    pub fn with_whitespace(mut self, whitespace: Whitespace) -> Self {
+        self.whitespace = whitespace;
+        self
+    }
+
+    pub fn errors(&self) -> &[SpannedLexicalError] {
+        &self.errors
+    }
+
+    // The source text of a token without copying it; None when lexing
+    // from a reader, whose text is not kept around
+    pub fn lexeme(&self, span: &Span) -> Option<&'a str> {
+        self.source.source_text(span)
+    }
+
+    // The most chars of the input held in memory at once so far: the
+    // longest token with the whitespace and comments before it, however
+    // long the input
+    pub fn max_buffered(&self) -> usize {
+        self.source.max_window()
+    }
+
+    // Reading from a reader stops at the first I/O error, which is kept here
+    pub fn io_error(&self) -> Option<&io::Error> {
+        self.source.io_error()
+    }
+
+    // Word tokens carry symbols from this interner; resolve them to get the
+    // identifier back
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    pub fn into_interner(self) -> Interner {
+        self.interner
+    }
+
+    pub fn tokenize(&mut self) -> Vec<SpannedToken<'a, K>> {
+        self.by_ref().collect()
+    }
+
+    pub fn tokenize_lossless(&mut self) -> Vec<LosslessToken<'a, K>> {
+        let keep_comments = self.keep_comments;
+        self.keep_comments = true;
+
+        let mut pieces = Vec::new();
+        let mut previous_end = self.peek_index;
+        loop {
+            let spanned = self.scan_spanned();
+            self.push_whitespace_trivia(previous_end, self.token_start, &mut pieces);
+            previous_end = self.peek_index;
+
+            let text = self.lexeme_from(self.token_start);
+            match &spanned.token {
+                Token::Comment(_) => pieces.push(LosslessPiece::Trivia(Trivia {
+                    kind: TriviaKind::Comment,
+                    text,
+                    span: spanned.span,
+                })),
+                Token::DocComment(_, _) => pieces.push(LosslessPiece::Trivia(Trivia {
+                    kind: TriviaKind::DocComment,
+                    text,
+                    span: spanned.span,
+                })),
+                Token::Epsilon => {
+                    pieces.push(LosslessPiece::Token(spanned, text));
+                    break;
                 }
-                _ => (),
+                _ => pieces.push(LosslessPiece::Token(spanned, text)),
             }
         }
+        self.keep_comments = keep_comments;
 
-        Lexer {
-            input: input.chars().collect(),
-            peek_index: 0,
-            words,
+        Lexer::attach_trivia(pieces)
+    }
+
+    fn push_whitespace_trivia(
+        &mut self,
+        start: usize,
+        end: usize,
+        pieces: &mut Vec<LosslessPiece<'a, K>>,
+    ) {
+        let mut run_start = start;
+        let mut i = start;
+        while i < end {
+            let newline_length = match self.source.get(i) {
+                Some('\n') => 1,
+                Some('\r') if i + 1 < end && self.source.get(i + 1) == Some('\n') => 2,
+                _ => 0,
+            };
+            if newline_length > 0 {
+                if run_start < i {
+                    pieces.push(self.whitespace_trivia(TriviaKind::Whitespace, run_start, i));
+                }
+                let newline_end = i + newline_length;
+                pieces.push(self.whitespace_trivia(TriviaKind::Newline, i, newline_end));
+                run_start = newline_end;
+            }
+            i += newline_length.max(1);
+        }
+        if run_start < end {
+            pieces.push(self.whitespace_trivia(TriviaKind::Whitespace, run_start, end));
         }
     }
 
-    fn get_reserved_keywords() -> Vec<Token> {
-        vec![
-            Token::Word(Word::True, "true".to_string()),
-            Token::Word(Word::False, "false".to_string()),
-        ]
+    fn whitespace_trivia(
+        &mut self,
+        kind: TriviaKind,
+        start: usize,
+        end: usize,
+    ) -> LosslessPiece<'a, K> {
+        LosslessPiece::Trivia(Trivia {
+            kind,
+            text: self.source.text(start, end),
+            span: self.source.span(start, end),
+        })
     }
 
-    pub fn tokenize(&mut self) -> Vec<Token> {
-        let mut tokens = Vec::new();
-        let mut next_token = self.scan();
-        while next_token != Token::Epsilon {
-            tokens.push(next_token);
-            next_token = self.scan();
+    fn attach_trivia(pieces: Vec<LosslessPiece<'a, K>>) -> Vec<LosslessToken<'a, K>> {
+        let mut tokens: Vec<LosslessToken<'a, K>> = Vec::new();
+        let mut leading_trivia = Vec::new();
+        let mut is_trailing = false;
+
+        for piece in pieces.into_iter() {
+            match piece {
+                LosslessPiece::Token(token, text) => {
+                    tokens.push(LosslessToken {
+                        leading_trivia: std::mem::take(&mut leading_trivia),
+                        token,
+                        text,
+                        trailing_trivia: Vec::new(),
+                    });
+                    is_trailing = true;
+                }
+                LosslessPiece::Trivia(trivia) => {
+                    if trivia.kind == TriviaKind::Newline {
+                        is_trailing = false;
+                    }
+                    match tokens.last_mut() {
+                        Some(token) if is_trailing => token.trailing_trivia.push(trivia),
+                        _ => leading_trivia.push(trivia),
+                    }
+                }
+            }
         }
         tokens
     }
 
-    fn scan(&mut self) -> Token {
-        while self.peek_index < self.input.len() {
-            let peek = self.input[self.peek_index];
+    pub fn try_tokenize(&mut self) -> Result<Vec<SpannedToken<'a, K>>, Vec<SpannedLexicalError>> {
+        let tokens = self.tokenize();
+        if self.errors.is_empty() {
+            Ok(tokens)
+        } else {
+            Err(self.errors.clone())
+        }
+    }
+
+    fn scan_spanned(&mut self) -> SpannedToken<'a, K> {
+        let token = self.scan();
+        let span = self.source.span(self.token_start, self.peek_index);
+
+        let error = match &token {
+            Token::Error(error, _) => Some(*error),
+            Token::Unknown(lexeme) => Some(LexicalError::IllegalCharacter(
+                lexeme.chars().next().unwrap(),
+            )),
+            _ => None,
+        };
+        if let Some(error) = error {
+            self.errors.push(SpannedLexicalError { error, span });
+        }
+
+        SpannedToken { token, span }
+    }
+
+    fn scan(&mut self) -> Token<'a, K> {
+        // Nothing before the end of the previous token is looked at again
+        self.source.discard_before(self.peek_index);
 
+        while let Some(peek) = self.peek_char() {
             if self.is_whitespace() {
                 self.move_peek();
                 continue;
             }
-            if self.is_single_line_comment() {
-                self.handle_single_line_comment();
-                continue;
-            }
-            if self.is_multi_line_comment() {
-                self.handle_multi_line_comment();
+            self.token_start = self.peek_index;
+            if let Some(kind) = self.is_comment() {
+                match kind {
+                    CommentKind::Line => self.handle_single_line_comment(),
+                    CommentKind::Block => self.handle_multi_line_comment(),
+                }
+                if self.keep_comments {
+                    return self.comment_token(kind);
+                }
+                // An unterminated block comment is an error too, and ends
+                // the scan when recovery is off
+                if !self.recover && !self.errors.is_empty() {
+                    break;
+                }
                 continue;
             }
 
+            if self.is_raw_string() {
+                return self.handle_raw_string();
+            }
+            if peek == '"' {
+                return self.handle_string();
+            }
+            if peek == '\'' {
+                return self.handle_char();
+            }
             if self.is_number() {
                 return self.handle_number();
             }
-            if peek.is_alphabetic() {
+            if peek == '_' || is_xid_start(peek) {
                 return self.handle_word();
             }
 
-            if let Some(logical_operator) = self.is_logical_operator() {
-                return logical_operator;
+            // Comments were checked first, so "//" and "/*" are never read
+            // as divisions
+            if let Some(operator) = self.is_operator() {
+                return operator;
             }
 
             self.move_peek();
-            return Token::Unknown(peek.to_string());
+            return Token::Unknown(self.lexeme_from(self.token_start));
         }
 
+        self.token_start = self.peek_index;
         Token::Epsilon
     }
 
-    fn is_whitespace(&self) -> bool {
-        let peek = self.input[self.peek_index];
-        peek == ' ' || peek == '\t' || peek == '\n'
+    fn is_whitespace(&mut self) -> bool {
+        let whitespace = self.whitespace;
+        self.peek_char()
+            .is_some_and(|peek| whitespace.contains(peek))
+    }
+
+    fn is_crlf(&mut self) -> bool {
+        self.peek_char() == Some('\r') && self.source.get(self.peek_index + 1) == Some('\n')
+    }
+
+    // The kind of comment that starts at the peek, if any. The longer
+    // delimiter is tried first, so that a block opener that starts with the
+    // line prefix, e.g. Lua's "--[[" next to "--", opens a block comment.
+    fn is_comment(&mut self) -> Option<CommentKind> {
+        let line_length = self.comments.line.as_ref().map_or(0, String::len);
+        let block_length = self
+            .comments
+            .block
+            .as_ref()
+            .map_or(0, |(open, _)| open.len());
+        let mut kinds = [CommentKind::Line, CommentKind::Block];
+        if block_length > line_length {
+            kinds.reverse();
+        }
+        kinds.into_iter().find(|kind| match kind {
+            CommentKind::Line => self.is_single_line_comment(),
+            CommentKind::Block => self.is_multi_line_comment(),
+        })
     }
 
     fn is_single_line_comment(&mut self) -> bool {
-        self.match_sequence("//")
+        match &self.comments.line {
+            Some(prefix) => match_at(&mut self.source, &mut self.peek_index, prefix),
+            None => false,
+        }
     }
 
     fn handle_single_line_comment(&mut self) {
-        while self.move_peek() {
-            if self.input[self.peek_index] == '\n' {
-                return;
-            }
+        while self.peek_char().is_some_and(|peek| peek != '\n') && !self.is_crlf() {
+            self.move_peek();
         }
     }
     fn is_multi_line_comment(&mut self) -> bool {
-        self.match_sequence("/*")
+        match &self.comments.block {
+            Some((open, _)) => match_at(&mut self.source, &mut self.peek_index, open),
+            None => false,
+        }
     }
     fn handle_multi_line_comment(&mut self) {
-        while self.move_peek() {
-            if self.match_sequence("*/") {
-                return;
+        let (open, close) = self
+            .comments
+            .block
+            .as_ref()
+            .expect("only called after is_multi_line_comment");
+        let comment_start = self.token_start;
+        let mut depth = 1;
+        while self.source.get(self.peek_index).is_some() {
+            if match_at(&mut self.source, &mut self.peek_index, close) {
+                depth -= 1;
+                if depth == 0 {
+                    return;
+                }
+            } else if self.nested_comments && match_at(&mut self.source, &mut self.peek_index, open)
+            {
+                depth += 1;
+            } else {
+                self.peek_index += 1;
             }
         }
+        let open_end = comment_start + open.chars().count();
+        self.errors.push(SpannedLexicalError {
+            error: LexicalError::UnterminatedComment,
+            span: self.source.span(comment_start, open_end),
+        });
+    }
+
+    fn comment_token(&mut self, kind: CommentKind) -> Token<'a, K> {
+        let lexeme = self.lexeme_from(self.token_start);
+        let (open, close) = match (kind, &self.comments.line, &self.comments.block) {
+            (CommentKind::Line, Some(prefix), _) => (prefix.as_str(), ""),
+            (CommentKind::Block, _, Some((open, close))) => (open.as_str(), close.as_str()),
+            _ => unreachable!("comment_token is only called after a comment was matched"),
+        };
+
+        // The doc marker repeats the last char of the opening delimiter once
+        let repeated = open.chars().last().unwrap();
+        let marker_length = open.len() + repeated.len_utf8();
+        let is_doc = lexeme[open.len()..].starts_with(repeated)
+            && !lexeme[marker_length..].starts_with(repeated)
+            && lexeme != format!("{}{}", open, close);
+
+        if is_doc {
+            let text = &lexeme[marker_length..];
+            let text = text.strip_suffix(close).unwrap_or(text);
+            Token::DocComment(text.trim().to_string(), lexeme)
+        } else {
+            Token::Comment(lexeme)
+        }
     }
 
     fn is_number(&mut self) -> bool {
-        let mut peek = self.input[self.peek_index];
-        if peek.is_numeric() {
-            return true;
+        match self.peek_char() {
+            Some('.') => self
+                .source
+                .get(self.peek_index + 1)
+                .is_some_and(|next| next.is_ascii_digit()),
+            Some(peek) => peek.is_ascii_digit(),
+            None => false,
         }
+    }
 
-        let mut is_numeric = false;
-        if peek == '.' {
-            if self.move_peek() {
-                peek = self.input[self.peek_index];
+    fn handle_number(&mut self) -> Token<'a, K> {
+        let start = self.peek_index;
+        if let Some(radix) = self.is_radix_prefix() {
+            return self.handle_radix_number(start, radix);
+        }
 
-                if peek.is_numeric() {
-                    is_numeric = true;
-                }
+        let mut is_float = false;
+        self.consume_digits(10);
+        if self.peek_char() == Some('.') {
+            self.move_peek();
+            self.consume_digits(10);
+            is_float = true;
+        }
+        if let Some('e' | 'E') = self.peek_char() {
+            let exponent_start = self.peek_index;
+            self.move_peek();
+            if let Some('+' | '-') = self.peek_char() {
+                self.move_peek();
+            }
+            if self.peek_char().is_some_and(|c| c.is_ascii_digit()) {
+                self.consume_digits(10);
+                is_float = true;
+            } else {
+                // Not an exponent after all, e.g. the `e` in `2else`
+                self.move_peek_to(exponent_start);
             }
-            self.move_peek_reverse();
         }
-        is_numeric
-    }
 
-    fn handle_number(&mut self) -> Token {
-        let mut peek = self.input[self.peek_index];
-        let mut is_float = peek == '.';
-        let mut integer = if is_float {
-            0
+        let literal = self.lexeme_from(start);
+        let digits: String = literal.chars().filter(|c| *c != '_').collect();
+        if is_float {
+            match digits.parse::<f64>() {
+                Ok(value) if value.is_finite() => Token::Number(NumberValue::Float(value), literal),
+                _ => Token::Error(LexicalError::FloatOverflow, literal),
+            }
         } else {
-            peek.to_digit(10).unwrap()
-        };
-        let mut decimal = 0;
+            match digits.parse::<i64>() {
+                Ok(value) => Token::Number(NumberValue::Integer(value), literal),
+                Err(_) => Token::Error(LexicalError::IntegerOverflow, literal),
+            }
+        }
+    }
 
-        if self.move_peek() {
-            peek = self.input[self.peek_index];
+    fn is_radix_prefix(&mut self) -> Option<u32> {
+        [
+            ("0x", 16),
+            ("0X", 16),
+            ("0o", 8),
+            ("0O", 8),
+            ("0b", 2),
+            ("0B", 2),
+        ]
+        .into_iter()
+        .find(|(prefix, _)| self.match_sequence(prefix))
+        .map(|(_, radix)| radix)
+    }
 
-            while peek.is_numeric() || peek == '.' {
-                if peek == '.' {
-                    if is_float {
-                        break;
-                    }
-                    is_float = true;
-                } else if is_float {
-                    decimal = decimal * 10 + peek.to_digit(10).unwrap();
-                } else {
-                    integer = integer * 10 + peek.to_digit(10).unwrap();
-                }
+    fn handle_radix_number(&mut self, start: usize, radix: u32) -> Token<'a, K> {
+        let digits_start = self.peek_index;
+        self.consume_digits(radix);
+        let digits_end = self.peek_index;
+        // A decimal digit the radix has no place for, e.g. the 2 in 0b12,
+        // makes the whole literal malformed rather than starting a new one
+        self.consume_digits(10);
 
-                if !self.move_peek() {
-                    break;
-                }
-                peek = self.input[self.peek_index];
+        let literal = self.lexeme_from(start);
+        let digits: String = self
+            .source
+            .slice(digits_start, digits_end)
+            .chars()
+            .filter(|c| *c != '_')
+            .collect();
+        if digits.is_empty() || digits_end != self.peek_index {
+            return Token::Error(LexicalError::MalformedNumber, literal);
+        }
+        match i64::from_str_radix(&digits, radix) {
+            Ok(value) => Token::Number(NumberValue::Integer(value), literal),
+            Err(_) => Token::Error(LexicalError::IntegerOverflow, literal),
+        }
+    }
+
+    fn consume_digits(&mut self, radix: u32) {
+        while let Some(peek) = self.peek_char() {
+            if !peek.is_digit(radix) && peek != '_' {
+                return;
             }
+            self.move_peek();
         }
-        Token::Number(integer, decimal)
     }
 
-    fn handle_word(&mut self) -> Token {
-        let mut peek = self.input[self.peek_index];
-        let mut word = peek.to_string();
-        if self.move_peek() {
-            peek = self.input[self.peek_index];
+    fn handle_string(&mut self) -> Token<'a, K> {
+        let start = self.peek_index;
+        self.move_peek();
 
-            while peek.is_alphanumeric() || peek == '_' {
-                word.push(peek);
-                if !self.move_peek() {
+        let mut value = String::new();
+        let mut error = None;
+        loop {
+            match self.peek_char() {
+                None => {
+                    return Token::Error(LexicalError::UnterminatedString, self.lexeme_from(start))
+                }
+                Some('"') => {
+                    self.move_peek();
                     break;
                 }
-                peek = self.input[self.peek_index];
+                Some('\\') => match self.handle_escape() {
+                    Ok(c) => value.push(c),
+                    Err(escape_error) => error = error.or(Some(escape_error)),
+                },
+                // Line breaks inside strings read as "\n" whatever the file uses
+                Some('\r') if self.is_crlf() => {
+                    self.move_peek();
+                }
+                Some(c) => {
+                    value.push(c);
+                    self.move_peek();
+                }
             }
         }
-        if let Some(word_token) = self.words.get(&word) {
-            return word_token.clone();
-        }
-        let new_identifier = Token::Word(Word::Identifier, word.to_string());
-        self.words.insert(word, new_identifier.clone());
-        new_identifier
-    }
-
-    fn is_logical_operator(&mut self) -> Option<Token> {
-        if self.match_sequence("<=") {
-            Some(Token::LogicalOperator(
-                LogicalOperator::LessOrEqual,
-                "<=".to_string(),
-            ))
-        } else if self.match_sequence("==") {
-            Some(Token::LogicalOperator(
-                LogicalOperator::Equal,
-                "==".to_string(),
-            ))
-        } else if self.match_sequence("!=") {
-            Some(Token::LogicalOperator(
-                LogicalOperator::Different,
-                "!=".to_string(),
-            ))
-        } else if self.match_sequence(">=") {
-            Some(Token::LogicalOperator(
-                LogicalOperator::GreaterOrEqual,
-                ">=".to_string(),
-            ))
-        } else if self.match_sequence("<") {
-            Some(Token::LogicalOperator(
-                LogicalOperator::Less,
-                "<".to_string(),
-            ))
-        } else if self.match_sequence(">") {
-            Some(Token::LogicalOperator(
-                LogicalOperator::Greater,
-                ">".to_string(),
-            ))
-        } else {
-            None
+
+        match error {
+            Some(error) => Token::Error(error, self.lexeme_from(start)),
+            None => Token::StringLiteral(value, self.lexeme_from(start)),
         }
     }
 
-    fn match_sequence(&mut self, expected_sequence: &str) -> bool {
-        let initial_peek_index = self.peek_index;
-        let mut peek = self.input[self.peek_index];
-        let chars: Vec<char> = expected_sequence.chars().collect();
-        let mut moved = false;
-
-        for sequence_char in chars.into_iter() {
-            if peek == sequence_char {
-                if self.move_peek() {
-                    peek = self.input[self.peek_index];
-                    moved = true;
+    // r"..." or r#"..."#, with as many #s as needed to allow quotes inside
+    fn is_raw_string(&mut self) -> bool {
+        if self.peek_char() != Some('r') {
+            return false;
+        }
+        let mut after_hashes = self.peek_index + 1;
+        while self.source.get(after_hashes) == Some('#') {
+            after_hashes += 1;
+        }
+        self.source.get(after_hashes) == Some('"')
+    }
+
+    fn handle_raw_string(&mut self) -> Token<'a, K> {
+        let start = self.peek_index;
+        self.move_peek();
+
+        let mut hashes = 0;
+        while self.peek_char() == Some('#') {
+            hashes += 1;
+            self.move_peek();
+        }
+        self.move_peek();
+
+        let terminator = format!("\"{}", "#".repeat(hashes));
+        let value_start = self.peek_index;
+        while self.peek_char().is_some() {
+            let value_end = self.peek_index;
+            if self.match_sequence(&terminator) {
+                let value = self.source.slice(value_start, value_end);
+                return Token::StringLiteral(value, self.lexeme_from(start));
+            }
+            self.move_peek();
+        }
+        Token::Error(LexicalError::UnterminatedString, self.lexeme_from(start))
+    }
+
+    fn handle_char(&mut self) -> Token<'a, K> {
+        let start = self.peek_index;
+        self.move_peek();
+
+        let mut chars = Vec::new();
+        let mut error = None;
+        loop {
+            match self.peek_char() {
+                None | Some('\n') => {
+                    return Token::Error(LexicalError::UnterminatedChar, self.lexeme_from(start))
                 }
-            } else {
-                if moved {
-                    self.move_peek_to(initial_peek_index);
+                Some('\r') if self.is_crlf() => {
+                    return Token::Error(LexicalError::UnterminatedChar, self.lexeme_from(start))
+                }
+                Some('\'') => {
+                    self.move_peek();
+                    break;
+                }
+                Some('\\') => match self.handle_escape() {
+                    Ok(c) => chars.push(c),
+                    Err(escape_error) => error = error.or(Some(escape_error)),
+                },
+                Some(c) => {
+                    chars.push(c);
+                    self.move_peek();
                 }
-                return false;
             }
         }
-        true
+
+        match (error, chars.as_slice()) {
+            (Some(error), _) => Token::Error(error, self.lexeme_from(start)),
+            (None, [c]) => Token::CharLiteral(*c, self.lexeme_from(start)),
+            (None, _) => Token::Error(LexicalError::MalformedChar, self.lexeme_from(start)),
+        }
+    }
+
+    // Consumes a backslash and the escape that follows it, leaving the
+    // peek on the next character of the literal even when the escape is invalid
+    fn handle_escape(&mut self) -> Result<char, LexicalError> {
+        self.move_peek();
+        let escaped = match self.peek_char() {
+            Some(escaped) => escaped,
+            None => return Err(LexicalError::InvalidEscape),
+        };
+        self.move_peek();
+
+        match escaped {
+            'n' => Ok('\n'),
+            't' => Ok('\t'),
+            'r' => Ok('\r'),
+            '0' => Ok('\0'),
+            '\\' | '"' | '\'' => Ok(escaped),
+            'u' => self.handle_unicode_escape(),
+            _ => Err(LexicalError::InvalidEscape),
+        }
+    }
+
+    fn handle_unicode_escape(&mut self) -> Result<char, LexicalError> {
+        if !self.match_sequence("{") {
+            return Err(LexicalError::InvalidEscape);
+        }
+        let digits_start = self.peek_index;
+        while self.peek_char().is_some_and(|c| c.is_ascii_hexdigit()) {
+            self.move_peek();
+        }
+        let digits = self.lexeme_from(digits_start);
+        if !self.match_sequence("}") || digits.is_empty() || digits.len() > 6 {
+            return Err(LexicalError::InvalidEscape);
+        }
+
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .and_then(char::from_u32)
+            .ok_or(LexicalError::InvalidEscape)
+    }
+
+    // Identifiers follow UAX #31: an XID_Start char or "_" followed by
+    // XID_Continue chars. They are NFC-normalized so that canonically
+    // equivalent spellings, e.g. "é" and "e\u{301}", are the same identifier
+    fn handle_word(&mut self) -> Token<'a, K> {
+        let start = self.peek_index;
+        self.move_peek();
+        while self.peek_char().is_some_and(is_xid_continue) {
+            self.move_peek();
+        }
+        let mut word = self.lexeme_from(start);
+        if is_nfc_quick(word.chars()) != IsNormalized::Yes {
+            word = Cow::Owned(word.nfc().collect());
+        }
+
+        let symbol = self.interner.intern(&word);
+        let tag = self.keywords.get(&symbol).copied().unwrap_or(K::IDENTIFIER);
+        Token::Word(tag, symbol)
+    }
+
+    fn is_operator(&mut self) -> Option<Token<'a, K>> {
+        let operator = self
+            .operators
+            .iter()
+            .find(|(lexeme, _)| match_at(&mut self.source, &mut self.peek_index, lexeme))?
+            .1;
+        Some(operator.token(self.lexeme_from(self.token_start)))
+    }
+
+    fn match_sequence(&mut self, expected_sequence: &str) -> bool {
+        match_at(&mut self.source, &mut self.peek_index, expected_sequence)
+    }
+
+    fn lexeme_from(&mut self, start: usize) -> Cow<'a, str> {
+        self.source.text(start, self.peek_index)
+    }
+
+    fn peek_char(&mut self) -> Option<char> {
+        self.source.get(self.peek_index)
     }
 
     fn move_peek(&mut self) -> bool {
         self.peek_index += 1;
-        self.peek_index < self.input.len()
-    }
-    fn move_peek_reverse(&mut self) {
-        self.peek_index -= 1;
+        self.peek_char().is_some()
     }
     fn move_peek_to(&mut self, new_peek: usize) {
         self.peek_index = new_peek;
     }
 }
 
+// Moves index past expected_sequence if the source continues with it there.
+// A free function so callers can match sequences borrowed from other fields
+// of the Lexer.
+fn match_at(source: &mut CharSource, index: &mut usize, expected_sequence: &str) -> bool {
+    let mut peek_index = *index;
+    for sequence_char in expected_sequence.chars() {
+        if source.get(peek_index) != Some(sequence_char) {
+            return false;
+        }
+        peek_index += 1;
+    }
+    *index = peek_index;
+    true
+}
+
+impl<'a, K: WordTag> Iterator for Lexer<'a, K> {
+    type Item = SpannedToken<'a, K>;
+
+    fn next(&mut self) -> Option<SpannedToken<'a, K>> {
+        if self.finished {
+            return None;
+        }
+
+        let next_token = self.scan_spanned();
+        if next_token.token == Token::Epsilon {
+            self.finished = true;
+            return None;
+        }
+        if !self.recover && !self.errors.is_empty() {
+            self.finished = true;
+        }
+        Some(next_token)
+    }
+}
+
 #[cfg(test)]
 mod test {
+    use super::super::span::Position;
     use super::*;
     use rstest::rstest;
 
     #[rstest]
+    #[allow(clippy::approx_constant)]
     fn test_lexer() {
         let mut lexer = Lexer::new(
             "// random  comment \n\
@@ -295,41 +1182,811 @@ mod test {
              >>=<<===!=\n\
              2. 3.14 .5.",
         );
-        let tokens: Vec<Token> = lexer.tokenize();
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
 
         let expected_values = vec![
-            Token::Word(Word::Identifier, "hello".to_string()),
-            Token::Unknown("=".to_string()),
-            Token::Number(12, 0),
-            Token::Unknown("*".to_string()),
-            Token::Number(5, 0),
-            Token::Unknown("+".to_string()),
-            Token::Number(3, 0),
-            Token::Word(Word::Identifier, "boolean_variable_".to_string()),
-            Token::Unknown("=".to_string()),
-            Token::Word(Word::True, "true".to_string()),
-            Token::Unknown("|".to_string()),
-            Token::Word(Word::False, "false".to_string()),
-            Token::LogicalOperator(LogicalOperator::Greater, ">".to_string()),
-            Token::LogicalOperator(LogicalOperator::GreaterOrEqual, ">=".to_string()),
-            Token::LogicalOperator(LogicalOperator::Less, "<".to_string()),
-            Token::LogicalOperator(LogicalOperator::LessOrEqual, "<=".to_string()),
-            Token::LogicalOperator(LogicalOperator::Equal, "==".to_string()),
-            Token::LogicalOperator(LogicalOperator::Different, "!=".to_string()),
-            Token::LogicalOperator(LogicalOperator::Greater, ">".to_string()),
-            Token::LogicalOperator(LogicalOperator::GreaterOrEqual, ">=".to_string()),
-            Token::LogicalOperator(LogicalOperator::Less, "<".to_string()),
-            Token::LogicalOperator(LogicalOperator::LessOrEqual, "<=".to_string()),
-            Token::LogicalOperator(LogicalOperator::Equal, "==".to_string()),
-            Token::LogicalOperator(LogicalOperator::Different, "!=".to_string()),
-            Token::Number(2, 0),
-            Token::Number(3, 14),
-            Token::Number(0, 5),
-            Token::Unknown(".".to_string()),
+            identifier(&lexer, "hello"),
+            Token::Assign("=".into()),
+            Token::Number(NumberValue::Integer(12), "12".into()),
+            Token::ArithmeticOperator(ArithmeticOperator::Times, "*".into()),
+            Token::Number(NumberValue::Integer(5), "5".into()),
+            Token::ArithmeticOperator(ArithmeticOperator::Plus, "+".into()),
+            Token::Number(NumberValue::Integer(3), "3".into()),
+            identifier(&lexer, "boolean_variable_"),
+            Token::Assign("=".into()),
+            word(&lexer, Word::True, "true"),
+            Token::BitwiseOperator(BitwiseOperator::Or, "|".into()),
+            word(&lexer, Word::False, "false"),
+            Token::LogicalOperator(LogicalOperator::Greater, ">".into()),
+            Token::LogicalOperator(LogicalOperator::GreaterOrEqual, ">=".into()),
+            Token::LogicalOperator(LogicalOperator::Less, "<".into()),
+            Token::LogicalOperator(LogicalOperator::LessOrEqual, "<=".into()),
+            Token::LogicalOperator(LogicalOperator::Equal, "==".into()),
+            Token::LogicalOperator(LogicalOperator::Different, "!=".into()),
+            Token::LogicalOperator(LogicalOperator::Greater, ">".into()),
+            Token::LogicalOperator(LogicalOperator::GreaterOrEqual, ">=".into()),
+            Token::LogicalOperator(LogicalOperator::Less, "<".into()),
+            Token::LogicalOperator(LogicalOperator::LessOrEqual, "<=".into()),
+            Token::LogicalOperator(LogicalOperator::Equal, "==".into()),
+            Token::LogicalOperator(LogicalOperator::Different, "!=".into()),
+            Token::Number(NumberValue::Float(2.0), "2.".into()),
+            Token::Number(NumberValue::Float(3.14), "3.14".into()),
+            Token::Number(NumberValue::Float(0.5), ".5".into()),
+            Token::Unknown(".".into()),
         ];
 
         for (i, token) in tokens.into_iter().enumerate() {
             assert_eq!(token, expected_values[i]);
         }
     }
+
+    #[rstest]
+    fn test_lexer_operators() {
+        let mut lexer = Lexer::new("{ x = (a + b) * c - d / e; } !ok && (p || !q) & 1 != 2 >");
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+
+        let identifier = |name: &str| identifier(&lexer, name);
+        let punctuation = |tag, lexeme: &'static str| Token::Punctuation(tag, lexeme.into());
+        let arithmetic = |tag, lexeme: &'static str| Token::ArithmeticOperator(tag, lexeme.into());
+        let connective = |tag, lexeme: &'static str| Token::LogicalConnective(tag, lexeme.into());
+        let expected_values = vec![
+            punctuation(Punctuation::OpenBrace, "{"),
+            identifier("x"),
+            Token::Assign("=".into()),
+            punctuation(Punctuation::OpenParen, "("),
+            identifier("a"),
+            arithmetic(ArithmeticOperator::Plus, "+"),
+            identifier("b"),
+            punctuation(Punctuation::CloseParen, ")"),
+            arithmetic(ArithmeticOperator::Times, "*"),
+            identifier("c"),
+            arithmetic(ArithmeticOperator::Minus, "-"),
+            identifier("d"),
+            arithmetic(ArithmeticOperator::Divide, "/"),
+            identifier("e"),
+            punctuation(Punctuation::Semicolon, ";"),
+            punctuation(Punctuation::CloseBrace, "}"),
+            connective(LogicalConnective::Not, "!"),
+            identifier("ok"),
+            connective(LogicalConnective::And, "&&"),
+            punctuation(Punctuation::OpenParen, "("),
+            identifier("p"),
+            connective(LogicalConnective::Or, "||"),
+            connective(LogicalConnective::Not, "!"),
+            identifier("q"),
+            punctuation(Punctuation::CloseParen, ")"),
+            Token::BitwiseOperator(BitwiseOperator::And, "&".into()),
+            Token::Number(NumberValue::Integer(1), "1".into()),
+            Token::LogicalOperator(LogicalOperator::Different, "!=".into()),
+            Token::Number(NumberValue::Integer(2), "2".into()),
+            Token::LogicalOperator(LogicalOperator::Greater, ">".into()),
+        ];
+
+        assert_eq!(tokens, expected_values);
+    }
+
+    #[rstest]
+    #[case("3.05", Token::Number(NumberValue::Float(3.05), "3.05".into()))]
+    #[case("3.5", Token::Number(NumberValue::Float(3.5), "3.5".into()))]
+    #[case("007", Token::Number(NumberValue::Integer(7), "007".into()))]
+    #[case("1e10", Token::Number(NumberValue::Float(1e10), "1e10".into()))]
+    #[case("2.5E-3", Token::Number(NumberValue::Float(2.5e-3), "2.5E-3".into()))]
+    #[case("1E+3", Token::Number(NumberValue::Float(1e3), "1E+3".into()))]
+    #[case("1_000_000", Token::Number(NumberValue::Integer(1_000_000), "1_000_000".into()))]
+    #[case("0xFF", Token::Number(NumberValue::Integer(255), "0xFF".into()))]
+    #[case("0o17", Token::Number(NumberValue::Integer(15), "0o17".into()))]
+    #[case("0b1010_1010", Token::Number(NumberValue::Integer(170), "0b1010_1010".into()))]
+    #[case(
+        "9223372036854775807",
+        Token::Number(NumberValue::Integer(i64::MAX), "9223372036854775807".into())
+    )]
+    #[case(
+        "9223372036854775808",
+        Token::Error(LexicalError::IntegerOverflow, "9223372036854775808".into())
+    )]
+    #[case(
+        "0x1_0000_0000_0000_0000",
+        Token::Error(LexicalError::IntegerOverflow, "0x1_0000_0000_0000_0000".into())
+    )]
+    #[case("1e999", Token::Error(LexicalError::FloatOverflow, "1e999".into()))]
+    #[case("0x", Token::Error(LexicalError::MalformedNumber, "0x".into()))]
+    #[case("0o19", Token::Error(LexicalError::MalformedNumber, "0o19".into()))]
+    #[case("0b1_02", Token::Error(LexicalError::MalformedNumber, "0b1_02".into()))]
+    #[case("0o_8", Token::Error(LexicalError::MalformedNumber, "0o_8".into()))]
+    fn test_lexer_number(#[case] input: &str, #[case] expected: Token) {
+        let tokens: Vec<Token> = Lexer::new(input)
+            .tokenize()
+            .into_iter()
+            .map(|t| t.token)
+            .collect();
+
+        assert_eq!(tokens, vec![expected]);
+    }
+
+    #[rstest]
+    fn test_lexer_number_followed_by_word() {
+        let mut lexer = Lexer::new("2else 0b12");
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                Token::Number(NumberValue::Integer(2), "2".into()),
+                identifier(&lexer, "else"),
+                Token::Error(LexicalError::MalformedNumber, "0b12".into()),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(r#""hello""#, Token::StringLiteral("hello".to_string(), r#""hello""#.into()))]
+    #[case(r#""""#, Token::StringLiteral("".to_string(), r#""""#.into()))]
+    #[case(
+        r#""a\n\t\\\"\'\0b""#,
+        Token::StringLiteral("a\n\t\\\"'\0b".to_string(), r#""a\n\t\\\"\'\0b""#.into())
+    )]
+    #[case(
+        r#""\u{48}\u{e9}\u{1F600}""#,
+        Token::StringLiteral("Hé😀".to_string(), r#""\u{48}\u{e9}\u{1F600}""#.into())
+    )]
+    #[case("\"two\nlines\"", Token::StringLiteral("two\nlines".to_string(), "\"two\nlines\"".into()))]
+    #[case(r#"r"C:\path""#, Token::StringLiteral(r"C:\path".to_string(), r#"r"C:\path""#.into()))]
+    #[case(
+        r###"r#"say "hi""#"###,
+        Token::StringLiteral(r#"say "hi""#.to_string(), r###"r#"say "hi""#"###.into())
+    )]
+    #[case("'a'", Token::CharLiteral('a', "'a'".into()))]
+    #[case(r"'\n'", Token::CharLiteral('\n', r"'\n'".into()))]
+    #[case(r"'\''", Token::CharLiteral('\'', r"'\''".into()))]
+    #[case(r"'\u{3bb}'", Token::CharLiteral('λ', r"'\u{3bb}'".into()))]
+    #[case(r#""open"#, Token::Error(LexicalError::UnterminatedString, r#""open"#.into()))]
+    #[case(r#""ends in \""#, Token::Error(LexicalError::UnterminatedString, r#""ends in \""#.into()))]
+    #[case(r#"r#"open""#, Token::Error(LexicalError::UnterminatedString, r#"r#"open""#.into()))]
+    #[case(r#""bad \q""#, Token::Error(LexicalError::InvalidEscape, r#""bad \q""#.into()))]
+    #[case(r#""\u{110000}""#, Token::Error(LexicalError::InvalidEscape, r#""\u{110000}""#.into()))]
+    #[case(r#""\u{}""#, Token::Error(LexicalError::InvalidEscape, r#""\u{}""#.into()))]
+    #[case(r#""\u41""#, Token::Error(LexicalError::InvalidEscape, r#""\u41""#.into()))]
+    #[case("'a", Token::Error(LexicalError::UnterminatedChar, "'a".into()))]
+    #[case("''", Token::Error(LexicalError::MalformedChar, "''".into()))]
+    #[case("'ab'", Token::Error(LexicalError::MalformedChar, "'ab'".into()))]
+    fn test_lexer_literal(#[case] input: &str, #[case] expected: Token) {
+        let tokens: Vec<Token> = Lexer::new(input)
+            .tokenize()
+            .into_iter()
+            .map(|t| t.token)
+            .collect();
+
+        assert_eq!(tokens, vec![expected]);
+    }
+
+    #[rstest]
+    fn test_lexer_literal_recovery() {
+        let mut lexer = Lexer::new(r#"x = "bad \q" + 'c'; rate"#);
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                identifier(&lexer, "x"),
+                Token::Assign("=".into()),
+                Token::Error(LexicalError::InvalidEscape, r#""bad \q""#.into()),
+                Token::ArithmeticOperator(ArithmeticOperator::Plus, "+".into()),
+                Token::CharLiteral('c', "'c'".into()),
+                Token::Punctuation(Punctuation::Semicolon, ";".into()),
+                identifier(&lexer, "rate"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_lexer_errors() {
+        let mut lexer = Lexer::new(
+            "x = 0x + 9223372036854775808;\n\
+             y = @ 'ab' #\n\
+             /**/ z \"open",
+        );
+        let tokens = lexer.tokenize();
+        let errors: Vec<(LexicalError, usize, usize)> = lexer
+            .errors()
+            .iter()
+            .map(|e| (e.error, e.span.start.line, e.span.start.column))
+            .collect();
+
+        assert_eq!(
+            errors,
+            vec![
+                (LexicalError::MalformedNumber, 1, 5),
+                (LexicalError::IntegerOverflow, 1, 10),
+                (LexicalError::IllegalCharacter('@'), 2, 5),
+                (LexicalError::MalformedChar, 2, 7),
+                (LexicalError::IllegalCharacter('#'), 2, 12),
+                (LexicalError::UnterminatedString, 3, 8),
+            ]
+        );
+        assert_eq!(tokens.len(), 13);
+    }
+
+    #[rstest]
+    fn test_lexer_unterminated_comment() {
+        let mut lexer = Lexer::new("a /**/ b\n  /* c\n d");
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(
+            tokens,
+            vec![identifier(&lexer, "a"), identifier(&lexer, "b"),]
+        );
+        assert_eq!(
+            lexer.errors(),
+            &[SpannedLexicalError {
+                error: LexicalError::UnterminatedComment,
+                span: Span::new(position(11, 2, 3), position(13, 2, 5)),
+            }]
+        );
+    }
+
+    #[rstest]
+    fn test_lexer_without_recovery() {
+        let mut lexer = Lexer::new("a $ b % c").with_recovery(false);
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(
+            tokens,
+            vec![identifier(&lexer, "a"), Token::Unknown("$".into()),]
+        );
+        assert_eq!(lexer.errors().len(), 1);
+    }
+
+    #[rstest]
+    fn test_lexer_without_recovery_unterminated_comment() {
+        let mut lexer = Lexer::new("a /* b\n c").with_recovery(false);
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(tokens, vec![identifier(&lexer, "a")]);
+        assert_eq!(
+            lexer.errors(),
+            &[SpannedLexicalError {
+                error: LexicalError::UnterminatedComment,
+                span: Span::new(position(2, 1, 3), position(4, 1, 5)),
+            }]
+        );
+        assert_eq!(lexer.next(), None);
+    }
+
+    #[rstest]
+    fn test_try_tokenize() {
+        assert_eq!(Lexer::new("a + 1").try_tokenize().map(|t| t.len()), Ok(3));
+
+        let errors = Lexer::new("a $ b % c").try_tokenize().unwrap_err();
+        assert_eq!(
+            errors
+                .iter()
+                .map(|e| e.to_string())
+                .collect::<Vec<String>>(),
+            vec!["1:3: illegal character '$'", "1:7: illegal character '%'"]
+        );
+    }
+
+    #[rstest]
+    fn test_lexer_nested_comments() {
+        let input = "a /* outer /* inner */ still comment */ b";
+
+        let mut lexer = Lexer::new(input);
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+        assert_eq!(
+            tokens,
+            vec![
+                identifier(&lexer, "a"),
+                identifier(&lexer, "still"),
+                identifier(&lexer, "comment"),
+                Token::ArithmeticOperator(ArithmeticOperator::Times, "*".into()),
+                Token::ArithmeticOperator(ArithmeticOperator::Divide, "/".into()),
+                identifier(&lexer, "b"),
+            ]
+        );
+
+        let mut lexer = Lexer::new(input).with_nested_comments(true);
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+        assert_eq!(
+            tokens,
+            vec![identifier(&lexer, "a"), identifier(&lexer, "b"),]
+        );
+    }
+
+    #[rstest]
+    fn test_lexer_unterminated_nested_comment() {
+        let mut lexer = Lexer::new("/* a /* b */ c").with_nested_comments(true);
+
+        assert_eq!(lexer.tokenize(), vec![]);
+        assert_eq!(
+            lexer.errors(),
+            &[SpannedLexicalError {
+                error: LexicalError::UnterminatedComment,
+                span: Span::new(position(0, 1, 1), position(2, 1, 3)),
+            }]
+        );
+    }
+
+    #[rstest]
+    fn test_lexer_keep_comments() {
+        let mut lexer = Lexer::new(
+            "/// Adds one\n\
+             x = x + 1; // increment\n\
+             //\n\
+             /** Block\n docs */ /**/ /*** banner ***/ //// rule\n\
+             /* outer /* inner */ */",
+        )
+        .with_comments(true)
+        .with_nested_comments(true);
+        let tokens: Vec<SpannedToken> = lexer.tokenize();
+
+        let comments: Vec<Token> = tokens
+            .iter()
+            .map(|t| t.token.clone())
+            .filter(|t| matches!(t, Token::Comment(_) | Token::DocComment(_, _)))
+            .collect();
+        assert_eq!(
+            comments,
+            vec![
+                Token::DocComment("Adds one".to_string(), "/// Adds one".into()),
+                Token::Comment("// increment".into()),
+                Token::Comment("//".into()),
+                Token::DocComment("Block\n docs".to_string(), "/** Block\n docs */".into()),
+                Token::Comment("/**/".into()),
+                Token::Comment("/*** banner ***/".into()),
+                Token::Comment("//// rule".into()),
+                Token::Comment("/* outer /* inner */ */".into()),
+            ]
+        );
+        assert_eq!(tokens.len(), 14);
+        assert_eq!(
+            tokens[9].span,
+            Span::new(position(40, 4, 1), position(58, 5, 9))
+        );
+    }
+
+    #[rstest]
+    fn test_lexer_lossless_trivia() {
+        let tokens = Lexer::new("  // header\nx = 1; /* note */\n\ty\n").tokenize_lossless();
+
+        let summary: Vec<(Vec<TriviaKind>, String, Vec<TriviaKind>)> = tokens
+            .iter()
+            .map(|t| {
+                (
+                    t.leading_trivia.iter().map(|trivia| trivia.kind).collect(),
+                    t.text.to_string(),
+                    t.trailing_trivia.iter().map(|trivia| trivia.kind).collect(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    vec![
+                        TriviaKind::Whitespace,
+                        TriviaKind::Comment,
+                        TriviaKind::Newline
+                    ],
+                    "x".to_string(),
+                    vec![TriviaKind::Whitespace]
+                ),
+                (vec![], "=".to_string(), vec![TriviaKind::Whitespace]),
+                (vec![], "1".to_string(), vec![]),
+                (
+                    vec![],
+                    ";".to_string(),
+                    vec![TriviaKind::Whitespace, TriviaKind::Comment]
+                ),
+                (
+                    vec![TriviaKind::Newline, TriviaKind::Whitespace],
+                    "y".to_string(),
+                    vec![]
+                ),
+                (vec![TriviaKind::Newline], "".to_string(), vec![]),
+            ]
+        );
+        assert_eq!(tokens.last().unwrap().token.token, Token::Epsilon);
+        assert_eq!(
+            tokens[4].leading_trivia[1].span,
+            Span::new(position(30, 3, 1), position(31, 3, 2))
+        );
+    }
+
+    // Small xorshift generator so the round-trip property can be checked on
+    // many arbitrary inputs without pulling in a property testing crate
+    struct Xorshift(u64);
+
+    impl Xorshift {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    fn arbitrary_input(rng: &mut Xorshift) -> String {
+        let fragments = [
+            "a", "_b", "true", "9", "0x", "1e", ".", "e-", " ", "\t", "\n", "\r\n", "/", "*", "/*",
+            "*/", "//", "///", "/**", "\"", "'", "\\", "r#", "#", "=", "!", "<", ">", "&", "|",
+            "+", "-", "(", ")", ";", "{", "}", "@", "é", "λ", "😀", "\r", "e\u{301}", "\u{a0}",
+        ];
+        let length = rng.next() % 40;
+        (0..length)
+            .map(|_| fragments[(rng.next() % fragments.len() as u64) as usize])
+            .collect()
+    }
+
+    #[rstest]
+    #[case(false)]
+    #[case(true)]
+    fn test_lexer_lossless_round_trip(#[case] nested_comments: bool) {
+        let mut rng = Xorshift(0x2545_f491_4f6c_dd1d);
+        for _ in 0..2000 {
+            let input = arbitrary_input(&mut rng);
+            let tokens = Lexer::new(&input)
+                .with_nested_comments(nested_comments)
+                .tokenize_lossless();
+
+            let round_trip: String = tokens.iter().map(|t| t.full_text()).collect();
+            assert_eq!(round_trip, input);
+        }
+    }
+
+    fn word(lexer: &Lexer, tag: Word, name: &str) -> Token<'static> {
+        Token::Word(tag, lexer.interner().get(name).unwrap())
+    }
+
+    fn identifier(lexer: &Lexer, name: &str) -> Token<'static> {
+        word(lexer, Word::Identifier, name)
+    }
+
+    fn position(offset: usize, line: usize, column: usize) -> Position {
+        Position {
+            offset,
+            line,
+            column,
+        }
+    }
+
+    #[rstest]
+    fn test_lexer_spans() {
+        let mut lexer = Lexer::new(
+            "x /* a\n\
+             b */ >= 3.14\n\
+             é=true",
+        );
+        let spans: Vec<Span> = lexer.tokenize().into_iter().map(|t| t.span).collect();
+
+        let expected_spans = vec![
+            Span::new(position(0, 1, 1), position(1, 1, 2)),
+            Span::new(position(12, 2, 6), position(14, 2, 8)),
+            Span::new(position(15, 2, 9), position(19, 2, 13)),
+            Span::new(position(20, 3, 1), position(22, 3, 2)),
+            Span::new(position(22, 3, 2), position(23, 3, 3)),
+            Span::new(position(23, 3, 3), position(27, 3, 7)),
+        ];
+
+        assert_eq!(spans, expected_spans);
+    }
+
+    #[rstest]
+    #[case("café", "café")]
+    #[case("cafe\u{301}", "café")]
+    #[case("変数_1", "変数_1")]
+    #[case("Δx", "Δx")]
+    #[case("_private", "_private")]
+    #[case("a€b", "a")]
+    fn test_lexer_unicode_identifier(#[case] input: &str, #[case] expected: &str) {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+
+        assert_eq!(tokens[0].token, identifier(&lexer, expected));
+    }
+
+    #[rstest]
+    fn test_lexer_identifiers_are_normalized() {
+        let mut lexer = Lexer::new("naïve = nai\u{308}ve");
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(tokens[0], tokens[2]);
+        assert!(lexer.errors().is_empty());
+    }
+
+    #[rstest]
+    #[case(Whitespace::Unicode, 0)]
+    #[case(Whitespace::Ascii, 2)]
+    fn test_lexer_whitespace(#[case] whitespace: Whitespace, #[case] errors: usize) {
+        let mut lexer = Lexer::new("a\u{a0}b\u{3000}c \x0C\r\t d").with_whitespace(whitespace);
+        let words = lexer
+            .tokenize()
+            .into_iter()
+            .filter(|t| matches!(t.token, Token::Word(_, _)))
+            .count();
+
+        assert_eq!(words, 4);
+        assert_eq!(lexer.errors().len(), errors);
+    }
+
+    #[rstest]
+    fn test_lexer_crlf() {
+        let input = "x = 1 // one\r\ns = \"a\r\nb\";\r\nc = 'x\r\n";
+        let mut lexer = Lexer::new(input).with_comments(true);
+        let tokens = lexer.tokenize();
+
+        assert_eq!(tokens[3].token, Token::Comment("// one".into()));
+        assert_eq!(
+            tokens[6].token,
+            Token::StringLiteral("a\nb".to_string(), "\"a\r\nb\"".into())
+        );
+        assert_eq!(tokens[7].span.start, position(24, 3, 3));
+        assert_eq!(tokens[8].span.start, position(27, 4, 1));
+        assert_eq!(
+            lexer.errors(),
+            &[SpannedLexicalError {
+                error: LexicalError::UnterminatedChar,
+                span: Span::new(position(31, 4, 5), position(33, 4, 7)),
+            }]
+        );
+
+        let lossless = Lexer::new(input).tokenize_lossless();
+        let newlines: Vec<&str> = lossless
+            .iter()
+            .flat_map(|t| t.leading_trivia.iter().chain(t.trailing_trivia.iter()))
+            .filter(|trivia| trivia.kind == TriviaKind::Newline)
+            .map(|trivia| trivia.text.as_ref())
+            .collect();
+        assert_eq!(newlines, vec!["\r\n", "\r\n", "\r\n"]);
+    }
+
+    #[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+    enum Keyword {
+        Identifier,
+        If,
+        While,
+        Do,
+        Int,
+        Float,
+    }
+
+    impl WordTag for Keyword {
+        const IDENTIFIER: Self = Keyword::Identifier;
+    }
+
+    #[rstest]
+    fn test_lexer_builder_keywords() {
+        let mut lexer = LexerBuilder::new()
+            .keywords([
+                ("if", Keyword::If),
+                ("while", Keyword::While),
+                ("do", Keyword::Do),
+                ("int", Keyword::Int),
+                ("float", Keyword::Float),
+            ])
+            .build("int i; while (i) do i = i - 1; true");
+        let tags: Vec<Keyword> = lexer
+            .tokenize()
+            .into_iter()
+            .filter_map(|spanned| match spanned.token {
+                Token::Word(tag, _) => Some(tag),
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            tags,
+            vec![
+                Keyword::Int,
+                Keyword::Identifier,
+                Keyword::While,
+                Keyword::Identifier,
+                Keyword::Do,
+                Keyword::Identifier,
+                Keyword::Identifier,
+                Keyword::Identifier,
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_lexer_builder_language() {
+        let pascal_like = [
+            (":=", Operator::Assign),
+            ("=", Operator::Logical(LogicalOperator::Equal)),
+            ("<>", Operator::Logical(LogicalOperator::Different)),
+            ("<", Operator::Logical(LogicalOperator::Less)),
+            ("+", Operator::Arithmetic(ArithmeticOperator::Plus)),
+            ("(", Operator::Punctuation(Punctuation::OpenParen)),
+            (")", Operator::Punctuation(Punctuation::CloseParen)),
+            (";", Operator::Punctuation(Punctuation::Semicolon)),
+        ];
+        let comments = CommentStyle {
+            line: Some("--".to_string()),
+            block: Some(("(*".to_string(), "*)".to_string())),
+        };
+        let mut lexer = LexerBuilder::<Word>::new()
+            .operators(pascal_like)
+            .comments(comments)
+            .build("x := (* set *) (a + 1) <> b; --- doc\n(** block doc *) x = y // z")
+            .with_comments(true);
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+
+        let identifier = |name: &str| identifier(&lexer, name);
+        assert_eq!(
+            tokens,
+            vec![
+                identifier("x"),
+                Token::Assign(":=".into()),
+                Token::Comment("(* set *)".into()),
+                Token::Punctuation(Punctuation::OpenParen, "(".into()),
+                identifier("a"),
+                Token::ArithmeticOperator(ArithmeticOperator::Plus, "+".into()),
+                Token::Number(NumberValue::Integer(1), "1".into()),
+                Token::Punctuation(Punctuation::CloseParen, ")".into()),
+                Token::LogicalOperator(LogicalOperator::Different, "<>".into()),
+                identifier("b"),
+                Token::Punctuation(Punctuation::Semicolon, ";".into()),
+                Token::DocComment("doc".to_string(), "--- doc".into()),
+                Token::DocComment("block doc".to_string(), "(** block doc *)".into()),
+                identifier("x"),
+                Token::LogicalOperator(LogicalOperator::Equal, "=".into()),
+                identifier("y"),
+                Token::Unknown("/".into()),
+                Token::Unknown("/".into()),
+                identifier("z"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_lexer_block_opener_starting_with_line_prefix() {
+        let lua_like = CommentStyle {
+            line: Some("--".to_string()),
+            block: Some(("--[[".to_string(), "]]".to_string())),
+        };
+        let mut lexer = LexerBuilder::<Word>::new()
+            .comments(lua_like)
+            .build("a --[[ block\n still ]] b -- line ]]\n--- doc\n--[[[ block doc ]] c")
+            .with_comments(true);
+        let tokens: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(
+            tokens,
+            vec![
+                identifier(&lexer, "a"),
+                Token::Comment("--[[ block\n still ]]".into()),
+                identifier(&lexer, "b"),
+                Token::Comment("-- line ]]".into()),
+                Token::DocComment("doc".to_string(), "--- doc".into()),
+                Token::DocComment("block doc".to_string(), "--[[[ block doc ]]".into()),
+                identifier(&lexer, "c"),
+            ]
+        );
+    }
+
+    #[rstest]
+    fn test_lexer_iterator() {
+        let mut lexer = Lexer::new("a + 1; b");
+        let symbols: Vec<Symbol> = lexer
+            .by_ref()
+            .filter_map(|spanned| match spanned.token {
+                Token::Word(_, symbol) => Some(symbol),
+                _ => None,
+            })
+            .collect();
+        let words: Vec<&str> = symbols
+            .into_iter()
+            .map(|symbol| lexer.interner().resolve(symbol))
+            .collect();
+
+        assert_eq!(words, vec!["a", "b"]);
+    }
+
+    #[rstest]
+    #[case(1)]
+    #[case(3)]
+    #[case(8192)]
+    fn test_lexer_from_reader(#[case] capacity: usize) {
+        let input = "// é comment\nnaïve = r#\"raw\"# + 'λ' /* ✓ */ 0x1F;\n\"unterminated";
+        let reader = std::io::BufReader::with_capacity(capacity, input.as_bytes());
+        let mut from_reader = Lexer::from_reader(reader);
+        let mut from_text = Lexer::new(input);
+
+        assert_eq!(from_reader.tokenize(), from_text.tokenize());
+        assert_eq!(from_reader.errors(), from_text.errors());
+        assert!(from_reader.io_error().is_none());
+    }
+
+    #[rstest]
+    fn test_lexer_zero_copy_lexeme() {
+        let input = "first = \"é\" + 2.5";
+        // The lexemes of everything but words, which are interned instead
+        fn lexemes(tokens: Vec<SpannedToken>) -> Vec<Cow<str>> {
+            tokens
+                .into_iter()
+                .filter_map(|spanned| match spanned.token {
+                    Token::Assign(lexeme)
+                    | Token::StringLiteral(_, lexeme)
+                    | Token::ArithmeticOperator(_, lexeme)
+                    | Token::Number(_, lexeme) => Some(lexeme),
+                    _ => None,
+                })
+                .collect()
+        }
+
+        let borrowed = lexemes(Lexer::new(input).tokenize());
+        assert_eq!(borrowed, vec!["=", "\"é\"", "+", "2.5"]);
+        assert!(borrowed
+            .iter()
+            .all(|lexeme| matches!(lexeme, Cow::Borrowed(_))));
+
+        let owned = lexemes(Lexer::from_reader(input.as_bytes()).tokenize());
+        assert_eq!(owned, borrowed);
+        assert!(owned.iter().all(|lexeme| matches!(lexeme, Cow::Owned(_))));
+
+        let tokens = Lexer::new(input).tokenize();
+        let copied = tokens.into_iter().map(|spanned| SpannedToken {
+            token: spanned.token.into_owned(),
+            span: spanned.span,
+        });
+        assert_eq!(lexemes(copied.collect()), owned);
+
+        let mut lexer = Lexer::new(input);
+        let spans: Vec<Span> = lexer.by_ref().map(|spanned| spanned.span).collect();
+        assert_eq!(lexer.lexeme(&spans[2]), Some("\"é\""));
+        assert_eq!(Lexer::from_reader(input.as_bytes()).lexeme(&spans[0]), None);
+    }
+
+    #[rstest]
+    #[case(100)]
+    #[case(10_000)]
+    fn test_lexer_streams_long_input(#[case] lines: usize) {
+        let line = "total = total + 1; // keep counting\n";
+        let input = line.repeat(lines);
+        let mut lexer = Lexer::from_reader(input.as_bytes());
+        let mut count = 0;
+        let mut last = None;
+        for spanned in lexer.by_ref() {
+            count += 1;
+            last = Some(spanned.span);
+        }
+
+        assert_eq!(count, 6 * lines);
+        assert_eq!(last.unwrap().start, position(input.len() - 19, lines, 18));
+        // Only the chars from the end of one token to the end of the next
+        // are held, however many lines there are
+        assert_eq!(lexer.max_buffered(), " // keep counting\ntotal".len() + 1);
+    }
+
+    fn benchmark_input() -> String {
+        let line = "counter_1 = counter_1 + 0x1F * 3.25e2; /* block */ // trailing\n\
+                    if (a <= b && !done) { s = \"text \\n\"; c = 'x'; }\n";
+        line.repeat(40_000)
+    }
+
+    // cargo test --release bench_lexer_throughput -- --ignored
+    // Lexing without collecting the tokens must be at least as fast as
+    // tokenize, which materializes all of them first
+    #[rstest]
+    #[ignore]
+    fn bench_lexer_throughput() {
+        let input = benchmark_input();
+        // The best of a few runs, since a single one is easily skewed
+        let measure = |lex: &dyn Fn() -> usize| {
+            let mut count = 0;
+            let mut fastest = std::time::Duration::MAX;
+            for _ in 0..5 {
+                let start = std::time::Instant::now();
+                count = lex();
+                fastest = fastest.min(start.elapsed());
+            }
+            assert_eq!(count, 27 * 40_000);
+            fastest
+        };
+
+        let tokenize = measure(&|| Lexer::new(&input).tokenize().len());
+        let iterate = measure(&|| Lexer::new(&input).count());
+        assert!(iterate <= tokenize, "{:?} > {:?}", iterate, tokenize);
+        measure(&|| Lexer::from_reader(std::io::BufReader::new(input.as_bytes())).count());
+    }
 }
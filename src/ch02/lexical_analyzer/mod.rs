@@ -1 +1,3 @@
 pub mod ex_2_6;
+pub mod source;
+pub mod span;
@@ -0,0 +1,268 @@
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+use super::span::{Position, Span};
+
+enum Input<'a> {
+    Str { text: &'a str, cursor: usize },
+    Reader(Box<dyn BufRead + 'a>),
+}
+
+// A sliding window of chars over either a borrowed string or a reader.
+// Chars are addressed by their absolute index in the input and decoded on
+// demand; once the lexer is done with a prefix it calls discard_before, so
+// memory stays proportional to the longest token, with the whitespace and
+// comments before it, rather than the input.
+pub struct CharSource<'a> {
+    input: Input<'a>,
+    window: VecDeque<(char, Position)>,
+    window_start: usize,
+    // The most chars the window has held at once
+    max_window: usize,
+    next_position: Position,
+    exhausted: bool,
+    io_error: Option<io::Error>,
+}
+
+impl<'a> CharSource<'a> {
+    pub fn from_text(text: &'a str) -> Self {
+        CharSource::new(Input::Str { text, cursor: 0 })
+    }
+
+    pub fn from_reader<R: BufRead + 'a>(reader: R) -> Self {
+        CharSource::new(Input::Reader(Box::new(reader)))
+    }
+
+    fn new(input: Input<'a>) -> Self {
+        CharSource {
+            input,
+            window: VecDeque::new(),
+            window_start: 0,
+            max_window: 0,
+            next_position: Position {
+                offset: 0,
+                line: 1,
+                column: 1,
+            },
+            exhausted: false,
+            io_error: None,
+        }
+    }
+
+    pub fn get(&mut self, index: usize) -> Option<char> {
+        assert!(
+            index >= self.window_start,
+            "char {} was already discarded",
+            index
+        );
+        while index >= self.window_start + self.window.len() {
+            if !self.fill_one() {
+                return None;
+            }
+        }
+        self.window.get(index - self.window_start).map(|(c, _)| *c)
+    }
+
+    // Position of the char at index, or the position right after the last
+    // char when index is at (or past) the end of the input
+    pub fn position(&mut self, index: usize) -> Position {
+        match self.get(index) {
+            Some(_) => self.window[index - self.window_start].1,
+            None => self.next_position,
+        }
+    }
+
+    pub fn span(&mut self, start: usize, end: usize) -> Span {
+        Span::new(self.position(start), self.position(end))
+    }
+
+    pub fn slice(&mut self, start: usize, end: usize) -> String {
+        (start..end).filter_map(|i| self.get(i)).collect()
+    }
+
+    // Like slice, but borrowed from the input when it is a string
+    pub fn text(&mut self, start: usize, end: usize) -> Cow<'a, str> {
+        match self.input {
+            Input::Str { text, .. } => {
+                let start = self.position(start).offset;
+                let end = self.position(end).offset;
+                Cow::Borrowed(&text[start..end])
+            }
+            Input::Reader(_) => Cow::Owned(self.slice(start, end)),
+        }
+    }
+
+    pub fn discard_before(&mut self, index: usize) {
+        let discarded = index
+            .saturating_sub(self.window_start)
+            .min(self.window.len());
+        self.window.drain(..discarded);
+        self.window_start += discarded;
+    }
+
+    pub fn max_window(&self) -> usize {
+        self.max_window
+    }
+
+    // Zero-copy access to the source text of a span; only available when
+    // lexing a borrowed string
+    pub fn source_text(&self, span: &Span) -> Option<&'a str> {
+        match self.input {
+            Input::Str { text, .. } => text.get(span.start.offset..span.end.offset),
+            Input::Reader(_) => None,
+        }
+    }
+
+    pub fn io_error(&self) -> Option<&io::Error> {
+        self.io_error.as_ref()
+    }
+
+    fn fill_one(&mut self) -> bool {
+        if self.exhausted {
+            return false;
+        }
+
+        let next = match &mut self.input {
+            Input::Str { text, cursor } => text[*cursor..].chars().next().map(|c| {
+                *cursor += c.len_utf8();
+                (c, c.len_utf8())
+            }),
+            Input::Reader(reader) => match read_char(reader.as_mut()) {
+                Ok(next) => next,
+                Err(error) => {
+                    self.io_error = Some(error);
+                    None
+                }
+            },
+        };
+
+        match next {
+            Some((c, byte_length)) => {
+                let position = self.next_position;
+                self.window.push_back((c, position));
+                self.max_window = self.max_window.max(self.window.len());
+                self.next_position = if c == '\n' {
+                    Position {
+                        offset: position.offset + byte_length,
+                        line: position.line + 1,
+                        column: 1,
+                    }
+                } else {
+                    Position {
+                        offset: position.offset + byte_length,
+                        line: position.line,
+                        column: position.column + 1,
+                    }
+                };
+                true
+            }
+            None => {
+                self.exhausted = true;
+                false
+            }
+        }
+    }
+}
+
+// Decodes the next UTF-8 char from the reader, returning it along with the
+// number of bytes it took. Invalid sequences decode to U+FFFD.
+fn read_char(reader: &mut dyn BufRead) -> io::Result<Option<(char, usize)>> {
+    let first = match reader.fill_buf()?.first() {
+        Some(first) => *first,
+        None => return Ok(None),
+    };
+    let width = match first {
+        0x00..=0x7F => 1,
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => {
+            reader.consume(1);
+            return Ok(Some((char::REPLACEMENT_CHARACTER, 1)));
+        }
+    };
+
+    reader.consume(1);
+
+    // The remaining bytes may straddle the end of the reader's internal
+    // buffer, and stop early if the sequence turns out to be truncated
+    let mut bytes = [first, 0, 0, 0];
+    let mut filled = 1;
+    while filled < width {
+        match reader.fill_buf()?.first() {
+            Some(byte @ 0x80..=0xBF) => {
+                bytes[filled] = *byte;
+                reader.consume(1);
+                filled += 1;
+            }
+            _ => break,
+        }
+    }
+
+    let c = std::str::from_utf8(&bytes[..filled])
+        .ok()
+        .and_then(|decoded| decoded.chars().next())
+        .unwrap_or(char::REPLACEMENT_CHARACTER);
+    Ok(Some((c, filled)))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+    use std::io::BufReader;
+
+    #[rstest]
+    fn test_reader_matches_str() {
+        let text = "ab\né😀!\nλ";
+        // A one-byte buffer forces every multi-byte char to straddle refills
+        let mut from_reader = CharSource::from_reader(BufReader::with_capacity(1, text.as_bytes()));
+        let mut from_text = CharSource::from_text(text);
+
+        for i in 0..=text.chars().count() {
+            assert_eq!(from_reader.get(i), from_text.get(i));
+            assert_eq!(from_reader.position(i), from_text.position(i));
+        }
+        assert_eq!(
+            from_text.position(4),
+            Position {
+                offset: 5,
+                line: 2,
+                column: 2
+            }
+        );
+    }
+
+    #[rstest]
+    fn test_invalid_utf8() {
+        let bytes: &[u8] = &[b'a', 0xFF, 0xE2, 0x82, b'b'];
+        let mut source = CharSource::from_reader(bytes);
+
+        assert_eq!(source.slice(0, 4), "a\u{FFFD}\u{FFFD}b");
+        assert_eq!(source.position(4).offset, 5);
+    }
+
+    #[rstest]
+    fn test_discard_before() {
+        let mut source = CharSource::from_text("hello");
+        source.get(4);
+        source.discard_before(3);
+
+        assert_eq!(source.window.len(), 2);
+        assert_eq!(source.max_window(), 5);
+        assert_eq!(source.slice(3, 5), "lo");
+        let span = source.span(3, 5);
+        assert_eq!(source.source_text(&span), Some("lo"));
+    }
+
+    #[rstest]
+    fn test_text() {
+        let text = "aé😀b";
+        let mut from_text = CharSource::from_text(text);
+        let mut from_reader = CharSource::from_reader(text.as_bytes());
+
+        assert!(matches!(from_text.text(1, 3), Cow::Borrowed("é😀")));
+        assert!(matches!(from_reader.text(1, 3), Cow::Owned(owned) if owned == "é😀"));
+    }
+}
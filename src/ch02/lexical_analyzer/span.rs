@@ -0,0 +1,92 @@
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Span {
+    pub fn new(start: Position, end: Position) -> Self {
+        Span { start, end }
+    }
+
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+// Maps char indexes of the input (what the lexer and the parsers walk over)
+// to byte offsets, lines and columns. Lines and columns start at 1 and
+// columns are counted in chars.
+pub struct LineIndex {
+    byte_offsets: Vec<usize>,
+    line_starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(input: &[char]) -> Self {
+        let mut byte_offsets = Vec::with_capacity(input.len() + 1);
+        let mut line_starts = vec![0];
+        let mut offset = 0;
+
+        for (i, c) in input.iter().enumerate() {
+            byte_offsets.push(offset);
+            offset += c.len_utf8();
+            if *c == '\n' {
+                line_starts.push(i + 1);
+            }
+        }
+        byte_offsets.push(offset);
+
+        LineIndex {
+            byte_offsets,
+            line_starts,
+        }
+    }
+
+    pub fn position(&self, char_index: usize) -> Position {
+        let char_index = char_index.min(self.byte_offsets.len() - 1);
+        let line = match self.line_starts.binary_search(&char_index) {
+            Ok(line) => line,
+            Err(next_line) => next_line - 1,
+        };
+
+        Position {
+            offset: self.byte_offsets[char_index],
+            line: line + 1,
+            column: char_index - self.line_starts[line] + 1,
+        }
+    }
+
+    pub fn span(&self, start: usize, end: usize) -> Span {
+        Span::new(self.position(start), self.position(end))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case(0, Position { offset: 0, line: 1, column: 1 })]
+    #[case(2, Position { offset: 2, line: 1, column: 3 })]
+    #[case(3, Position { offset: 3, line: 2, column: 1 })]
+    #[case(4, Position { offset: 5, line: 2, column: 2 })]
+    #[case(5, Position { offset: 6, line: 2, column: 3 })]
+    #[case(99, Position { offset: 6, line: 2, column: 3 })]
+    fn test_line_index(#[case] char_index: usize, #[case] expected: Position) {
+        let input: Vec<char> = "ab\né!".chars().collect();
+        assert_eq!(LineIndex::new(&input).position(char_index), expected);
+    }
+}
@@ -1,2 +1,4 @@
 pub mod lexical_analyzer;
 pub mod recursive_descent_parser;
+pub mod symbol_table;
+pub mod translator;
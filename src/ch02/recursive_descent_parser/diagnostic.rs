@@ -0,0 +1,181 @@
+use std::fmt::{self, Debug, Display};
+
+use crate::ch02::lexical_analyzer::ex_2_6::LexicalError;
+use crate::ch02::lexical_analyzer::span::{LineIndex, Span};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ErrorKind {
+    InvalidCharacter(char),
+    LexicalError(LexicalError),
+    UnexpectedToken,
+    UnexpectedEndOfInput,
+    TrailingInput,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic<T> {
+    pub kind: ErrorKind,
+    pub expected: Vec<T>,
+    // None when the parser ran out of input or hit a token the lexer rejected
+    pub found: Option<T>,
+    pub span: Span,
+    pub notes: Vec<String>,
+}
+
+impl<T: Debug> Diagnostic<T> {
+    pub fn new(kind: ErrorKind, expected: Vec<T>, found: Option<T>, span: Span) -> Self {
+        Diagnostic {
+            kind,
+            expected,
+            found,
+            span,
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn with_note(mut self, note: &str) -> Self {
+        self.notes.push(note.to_string());
+        self
+    }
+
+    // Renders the diagnostic along with the offending source line,
+    // underlining the span with carets:
+    //
+    // error: expected CloseParam, found end of input
+    //  --> 1:4
+    //   |
+    // 1 | (()
+    //   |    ^
+    pub fn render(&self, source: &str) -> String {
+        let line_number = self.span.start.line.to_string();
+        let gutter = " ".repeat(line_number.len());
+        let line = source
+            .split('\n')
+            .nth(self.span.start.line - 1)
+            .unwrap_or("")
+            .trim_end_matches('\r');
+
+        let underline_length = if self.span.end.line == self.span.start.line {
+            (self.span.end.column - self.span.start.column).max(1)
+        } else {
+            (line.chars().count() + 1 - self.span.start.column).max(1)
+        };
+
+        let mut rendered = format!(
+            "error: {}\n{}--> {}:{}\n{} |\n{} | {}\n{} | {}{}",
+            self,
+            gutter,
+            self.span.start.line,
+            self.span.start.column,
+            gutter,
+            line_number,
+            line,
+            gutter,
+            " ".repeat(self.span.start.column - 1),
+            "^".repeat(underline_length),
+        );
+        for note in self.notes.iter() {
+            rendered.push_str(&format!("\n{} = note: {}", gutter, note));
+        }
+        rendered
+    }
+
+    fn format_expected(&self) -> String {
+        let expected: Vec<String> = self.expected.iter().map(|t| format!("{:?}", t)).collect();
+        match expected.len() {
+            0 => "nothing".to_string(),
+            1 => expected[0].clone(),
+            _ => format!("one of {}", expected.join(", ")),
+        }
+    }
+
+    fn format_found(&self) -> String {
+        match &self.found {
+            Some(token) => format!("{:?}", token),
+            None => "end of input".to_string(),
+        }
+    }
+}
+
+impl<T: Debug> Display for Diagnostic<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::InvalidCharacter(c) => write!(f, "invalid character {:?}", c),
+            ErrorKind::LexicalError(error) => write!(f, "{}", error),
+            ErrorKind::UnexpectedToken | ErrorKind::UnexpectedEndOfInput => write!(
+                f,
+                "expected {}, found {}",
+                self.format_expected(),
+                self.format_found()
+            ),
+            ErrorKind::TrailingInput => {
+                write!(f, "expected end of input, found {}", self.format_found())
+            }
+        }
+    }
+}
+
+pub fn char_span(input: &[char], start: usize, end: usize) -> Span {
+    LineIndex::new(input).span(start, end)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[derive(Debug)]
+    enum Token {
+        OpenParam,
+        CloseParam,
+    }
+
+    #[rstest]
+    fn test_render_end_of_input() {
+        let input: Vec<char> = "(()".chars().collect();
+        let diagnostic = Diagnostic::new(
+            ErrorKind::UnexpectedEndOfInput,
+            vec![Token::CloseParam],
+            None,
+            char_span(&input, 3, 3),
+        );
+
+        assert_eq!(
+            diagnostic.render("(()"),
+            [
+                "error: expected CloseParam, found end of input",
+                " --> 1:4",
+                "  |",
+                "1 | (()",
+                "  |    ^",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[rstest]
+    fn test_render_second_line_with_note() {
+        let source = "()\n)(x";
+        let input: Vec<char> = source.chars().collect();
+        let diagnostic = Diagnostic::new(
+            ErrorKind::UnexpectedToken,
+            vec![Token::OpenParam, Token::CloseParam],
+            Some(Token::CloseParam),
+            char_span(&input, 3, 5),
+        )
+        .with_note("parentheses must be balanced");
+
+        assert_eq!(
+            diagnostic.render(source),
+            [
+                "error: expected one of OpenParam, CloseParam, found CloseParam",
+                " --> 2:1",
+                "  |",
+                "2 | )(x",
+                "  | ^^",
+                "  = note: parentheses must be balanced",
+            ]
+            .join("\n")
+        );
+    }
+}
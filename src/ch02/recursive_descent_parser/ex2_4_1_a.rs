@@ -1,5 +1,8 @@
-#[derive(Debug, PartialEq)]
-enum Token {
+use super::diagnostic::{char_span, Diagnostic, ErrorKind};
+use super::parse_tree::{Nonterminal, ParseTree};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Token {
     A,
     Plus,
     Minus,
@@ -23,39 +26,59 @@ impl ParserA {
         }
     }
 
-    pub fn parse(&mut self) -> Result<(), String> {
-        let is_correct = self.parse_internal()?;
-        if is_correct {
-            if self.lookahead_index < self.input.len() {
-                Err("Syntax error: Invalid input".to_string())
-            } else {
-                Ok(())
-            }
-        } else {
-            Err("Syntax error: Invalid input".to_string())
+    pub fn parse(&mut self) -> Result<ParseTree<Token>, Diagnostic<Token>> {
+        let tree = self.parse_internal()?;
+
+        let start = self.lookahead_index;
+        match self.next_token() {
+            Token::Epsilon => Ok(tree),
+            Token::Invalid => Err(self.invalid_character(vec![])),
+            token => Err(Diagnostic::new(
+                ErrorKind::TrailingInput,
+                vec![],
+                Some(token),
+                char_span(&self.input, start, self.lookahead_index),
+            )),
         }
     }
 
-    fn parse_internal(&mut self) -> Result<bool, String> {
+    fn parse_internal(&mut self) -> Result<ParseTree<Token>, Diagnostic<Token>> {
         match self.next_token() {
-            Token::A => Ok(true),
-            Token::Plus | Token::Minus => {
-                if !self.parse_internal()? {
-                    return Ok(false);
-                }
-                if !self.parse_internal()? {
-                    return Ok(false);
-                }
-                Ok(true)
+            Token::A => Ok(ParseTree::Node(
+                Nonterminal::S,
+                vec![ParseTree::Leaf(Token::A)],
+            )),
+            token @ (Token::Plus | Token::Minus) => {
+                let left = self.parse_internal()?;
+                let right = self.parse_internal()?;
+                Ok(ParseTree::Node(
+                    Nonterminal::S,
+                    vec![ParseTree::Leaf(token), left, right],
+                ))
             }
-            Token::Epsilon => Ok(false),
-            Token::Invalid => Err(format!(
-                "Syntax error: Invalid token at position {}",
-                self.lookahead_index
+            Token::Epsilon => Err(Diagnostic::new(
+                ErrorKind::UnexpectedEndOfInput,
+                Self::first_of_s(),
+                None,
+                char_span(&self.input, self.lookahead_index, self.lookahead_index),
             )),
+            Token::Invalid => Err(self.invalid_character(Self::first_of_s())),
         }
     }
 
+    fn first_of_s() -> Vec<Token> {
+        vec![Token::Plus, Token::Minus, Token::A]
+    }
+
+    fn invalid_character(&self, expected: Vec<Token>) -> Diagnostic<Token> {
+        Diagnostic::new(
+            ErrorKind::InvalidCharacter(self.input[self.lookahead_index]),
+            expected,
+            None,
+            char_span(&self.input, self.lookahead_index, self.lookahead_index + 1),
+        )
+    }
+
     fn next_token(&mut self) -> Token {
         if self.lookahead_index >= self.input.len() {
             return Token::Epsilon;
@@ -91,32 +114,60 @@ mod tests {
     #[case("+-aaa")]
     #[case("+++--+-+-+-+-+++-a-aaaaaaaaaaaaaaaaaa")]
     #[case("-aa")]
-    fn test_2_4_1_a_valid(#[case] input: &str) -> Result<(), String> {
-        assert_eq!(ParserA::new(input).parse()?, ());
+    fn test_2_4_1_a_valid(#[case] input: &str) -> Result<(), Diagnostic<Token>> {
+        let tree = ParserA::new(input).parse()?;
+        assert_eq!(tree.leaves().len(), input.len());
 
         Ok(())
     }
 
     #[rstest]
-    #[case("++aa", "Syntax error: Invalid input")]
-    #[case("+a", "Syntax error: Invalid input")]
-    #[case("+ab", "Syntax error: Invalid token at position 2")]
-    #[case("+ba", "Syntax error: Invalid token at position 1")]
-    #[case("--aa", "Syntax error: Invalid input")]
-    #[case("-a", "Syntax error: Invalid input")]
-    #[case("-ab", "Syntax error: Invalid token at position 2")]
-    #[case("-ba", "Syntax error: Invalid token at position 1")]
-    #[case("aa", "Syntax error: Invalid input")]
-    #[case("b", "Syntax error: Invalid token at position 0")]
-    fn test_2_4_1_a_invalid(
-        #[case] input: &str,
-        #[case] error_message: &str,
-    ) -> Result<(), String> {
-        assert_eq!(
-            ParserA::new(input).parse().unwrap_err(),
-            error_message.to_string()
+    fn test_2_4_1_a_parse_tree() -> Result<(), Diagnostic<Token>> {
+        let a = || ParseTree::Node(Nonterminal::S, vec![ParseTree::Leaf(Token::A)]);
+        let expected = ParseTree::Node(
+            Nonterminal::S,
+            vec![
+                ParseTree::Leaf(Token::Plus),
+                ParseTree::Node(
+                    Nonterminal::S,
+                    vec![ParseTree::Leaf(Token::Minus), a(), a()],
+                ),
+                a(),
+            ],
         );
 
+        assert_eq!(ParserA::new("+-aaa").parse()?, expected);
         Ok(())
     }
+
+    #[rstest]
+    #[case("++aa", ErrorKind::UnexpectedEndOfInput, 4)]
+    #[case("+a", ErrorKind::UnexpectedEndOfInput, 2)]
+    #[case("+ab", ErrorKind::InvalidCharacter('b'), 2)]
+    #[case("+ba", ErrorKind::InvalidCharacter('b'), 1)]
+    #[case("--aa", ErrorKind::UnexpectedEndOfInput, 4)]
+    #[case("-a", ErrorKind::UnexpectedEndOfInput, 2)]
+    #[case("-ab", ErrorKind::InvalidCharacter('b'), 2)]
+    #[case("-ba", ErrorKind::InvalidCharacter('b'), 1)]
+    #[case("aa", ErrorKind::TrailingInput, 1)]
+    #[case("ab", ErrorKind::InvalidCharacter('b'), 1)]
+    #[case("b", ErrorKind::InvalidCharacter('b'), 0)]
+    fn test_2_4_1_a_invalid(#[case] input: &str, #[case] kind: ErrorKind, #[case] offset: usize) {
+        let error = ParserA::new(input).parse().unwrap_err();
+
+        assert_eq!(error.kind, kind);
+        assert_eq!(error.span.start.offset, offset);
+    }
+
+    #[rstest]
+    fn test_2_4_1_a_expected_tokens() {
+        let error = ParserA::new("+a").parse().unwrap_err();
+
+        assert_eq!(error.expected, vec![Token::Plus, Token::Minus, Token::A]);
+        assert_eq!(error.found, None);
+        assert_eq!(
+            error.to_string(),
+            "expected one of Plus, Minus, A, found end of input"
+        );
+    }
 }
@@ -0,0 +1,266 @@
+use super::diagnostic::{char_span, Diagnostic, ErrorKind};
+use super::ex2_4_1_a;
+use super::parse_tree::ParseTree;
+use crate::ch02::lexical_analyzer::ex_2_6::{self, ArithmeticOperator, Lexer, SpannedToken};
+use crate::ch02::lexical_analyzer::span::Span;
+
+// Syntax-directed translation of the parse trees built by ParserA:
+// S := +S1S2 { S.infix = "(" || S1.infix || "+" || S2.infix || ")" }
+// S := -S1S2 { S.infix = "(" || S1.infix || "-" || S2.infix || ")" }
+// S := a     { S.infix = "a" }
+pub fn to_infix(tree: &ParseTree<ex2_4_1_a::Token>) -> String {
+    match tree {
+        ParseTree::Node(_, children) => match children.as_slice() {
+            [ParseTree::Leaf(operator), left, right] => format!(
+                "({}{}{})",
+                to_infix(left),
+                symbol(operator),
+                to_infix(right)
+            ),
+            [leaf] => to_infix(leaf),
+            _ => unreachable!("ParserA only builds S nodes with one or three children"),
+        },
+        ParseTree::Leaf(token) => symbol(token).to_string(),
+    }
+}
+
+// S := +S1S2 { S.postfix = S1.postfix || S2.postfix || "+" }
+// S := -S1S2 { S.postfix = S1.postfix || S2.postfix || "-" }
+// S := a     { S.postfix = "a" }
+pub fn to_postfix(tree: &ParseTree<ex2_4_1_a::Token>) -> String {
+    match tree {
+        ParseTree::Node(_, children) => match children.as_slice() {
+            [ParseTree::Leaf(operator), left, right] => format!(
+                "{} {} {}",
+                to_postfix(left),
+                to_postfix(right),
+                symbol(operator)
+            ),
+            [leaf] => to_postfix(leaf),
+            _ => unreachable!("ParserA only builds S nodes with one or three children"),
+        },
+        ParseTree::Leaf(token) => symbol(token).to_string(),
+    }
+}
+
+fn symbol(token: &ex2_4_1_a::Token) -> &'static str {
+    match token {
+        ex2_4_1_a::Token::A => "a",
+        ex2_4_1_a::Token::Plus => "+",
+        ex2_4_1_a::Token::Minus => "-",
+        ex2_4_1_a::Token::Epsilon | ex2_4_1_a::Token::Invalid => {
+            unreachable!("ParserA never puts {:?} in a parse tree", token)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Token {
+    Plus,
+    Minus,
+    Number,
+    Word,
+    Operator,
+    Punctuation,
+    Literal,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Translation {
+    pub infix: String,
+    pub postfix: String,
+    pub value: f64,
+}
+
+pub struct PrefixTranslator<'a> {
+    tokens: Vec<SpannedToken<'a>>,
+    lookahead_index: usize,
+    end_of_input: Span,
+}
+
+impl<'a> PrefixTranslator<'a> {
+    // Translation scheme for the 2.4.1(a) grammar with `a` generalized to
+    // the number literals produced by ex_2_6::Lexer:
+    // S := +S1S2 { S.value = S1.value + S2.value }
+    // S := -S1S2 { S.value = S1.value - S2.value }
+    // S := num   { S.value = num.value }
+    // The infix and postfix attributes are computed as in to_infix/to_postfix
+
+    pub fn new(input: &'a str) -> Self {
+        let chars: Vec<char> = input.chars().collect();
+        PrefixTranslator {
+            tokens: Lexer::new(input).tokenize(),
+            lookahead_index: 0,
+            end_of_input: char_span(&chars, chars.len(), chars.len()),
+        }
+    }
+
+    pub fn translate(&mut self) -> Result<Translation, Diagnostic<Token>> {
+        let translation = self.translate_internal()?;
+
+        match self.tokens.get(self.lookahead_index) {
+            None => Ok(translation),
+            Some(spanned) => Err(self.unexpected(spanned, ErrorKind::TrailingInput, vec![])),
+        }
+    }
+
+    fn translate_internal(&mut self) -> Result<Translation, Diagnostic<Token>> {
+        let spanned = match self.tokens.get(self.lookahead_index) {
+            Some(spanned) => spanned.clone(),
+            None => {
+                return Err(Diagnostic::new(
+                    ErrorKind::UnexpectedEndOfInput,
+                    Self::first_of_s(),
+                    None,
+                    self.end_of_input,
+                ))
+            }
+        };
+        self.lookahead_index += 1;
+
+        match &spanned.token {
+            ex_2_6::Token::Number(value, literal) => Ok(Translation {
+                infix: literal.to_string(),
+                postfix: literal.to_string(),
+                value: value.as_f64(),
+            }),
+            ex_2_6::Token::ArithmeticOperator(
+                tag @ (ArithmeticOperator::Plus | ArithmeticOperator::Minus),
+                operator,
+            ) => {
+                let left = self.translate_internal()?;
+                let right = self.translate_internal()?;
+                let value = if *tag == ArithmeticOperator::Plus {
+                    left.value + right.value
+                } else {
+                    left.value - right.value
+                };
+                Ok(Translation {
+                    infix: format!("({}{}{})", left.infix, operator, right.infix),
+                    postfix: format!("{} {} {}", left.postfix, right.postfix, operator),
+                    value,
+                })
+            }
+            _ => Err(self.unexpected(&spanned, ErrorKind::UnexpectedToken, Self::first_of_s())),
+        }
+    }
+
+    fn first_of_s() -> Vec<Token> {
+        vec![Token::Plus, Token::Minus, Token::Number]
+    }
+
+    fn unexpected(
+        &self,
+        spanned: &SpannedToken<'a>,
+        kind: ErrorKind,
+        expected: Vec<Token>,
+    ) -> Diagnostic<Token> {
+        let found = match &spanned.token {
+            ex_2_6::Token::Word(_, _) => Token::Word,
+            ex_2_6::Token::Number(_, _) => Token::Number,
+            ex_2_6::Token::ArithmeticOperator(ArithmeticOperator::Plus, _) => Token::Plus,
+            ex_2_6::Token::ArithmeticOperator(ArithmeticOperator::Minus, _) => Token::Minus,
+            ex_2_6::Token::LogicalOperator(_, _)
+            | ex_2_6::Token::ArithmeticOperator(_, _)
+            | ex_2_6::Token::LogicalConnective(_, _)
+            | ex_2_6::Token::BitwiseOperator(_, _)
+            | ex_2_6::Token::Assign(_) => Token::Operator,
+            ex_2_6::Token::Punctuation(_, _) => Token::Punctuation,
+            ex_2_6::Token::StringLiteral(_, _) | ex_2_6::Token::CharLiteral(_, _) => Token::Literal,
+            ex_2_6::Token::Error(error, _) => {
+                return Diagnostic::new(
+                    ErrorKind::LexicalError(*error),
+                    expected,
+                    None,
+                    spanned.span,
+                );
+            }
+            ex_2_6::Token::Unknown(lexeme) => {
+                let c = lexeme.chars().next().unwrap();
+                return Diagnostic::new(
+                    ErrorKind::InvalidCharacter(c),
+                    expected,
+                    None,
+                    spanned.span,
+                );
+            }
+            ex_2_6::Token::Comment(_) | ex_2_6::Token::DocComment(_, _) => {
+                unreachable!("comments are discarded unless Lexer::with_comments is set")
+            }
+            ex_2_6::Token::Epsilon => unreachable!("Lexer::tokenize never yields Epsilon"),
+        };
+        Diagnostic::new(kind, expected, Some(found), spanned.span)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ex2_4_1_a::ParserA;
+    use super::*;
+    use crate::ch02::lexical_analyzer::ex_2_6::LexicalError;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("a", "a", "a")]
+    #[case("+aa", "(a+a)", "a a +")]
+    #[case("+-aaa", "((a-a)+a)", "a a - a +")]
+    #[case("-a+aa", "(a-(a+a))", "a a a + -")]
+    fn test_2_4_1_a_tree_translation(
+        #[case] input: &str,
+        #[case] infix: &str,
+        #[case] postfix: &str,
+    ) -> Result<(), Diagnostic<ex2_4_1_a::Token>> {
+        let tree = ParserA::new(input).parse()?;
+
+        assert_eq!(to_infix(&tree), infix);
+        assert_eq!(to_postfix(&tree), postfix);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("7", "7", "7", 7.0)]
+    #[case("+ 1 2", "(1+2)", "1 2 +", 3.0)]
+    #[case("- 9 + 5 2", "(9-(5+2))", "9 5 2 + -", 2.0)]
+    #[case("+ - 9 5 2", "((9-5)+2)", "9 5 - 2 +", 6.0)]
+    #[case("- 1.5 .25", "(1.5-.25)", "1.5 .25 -", 1.25)]
+    #[case("+ 3.05 1e2", "(3.05+1e2)", "3.05 1e2 +", 103.05)]
+    #[case("+ 10\n - 3 /* comment */ 12", "(10+(3-12))", "10 3 12 - +", 1.0)]
+    fn test_2_4_1_a_numeric_translation(
+        #[case] input: &str,
+        #[case] infix: &str,
+        #[case] postfix: &str,
+        #[case] value: f64,
+    ) -> Result<(), Diagnostic<Token>> {
+        let translation = PrefixTranslator::new(input).translate()?;
+
+        assert_eq!(translation.infix, infix);
+        assert_eq!(translation.postfix, postfix);
+        assert_eq!(translation.value, value);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("+ 1", ErrorKind::UnexpectedEndOfInput, 3)]
+    #[case("", ErrorKind::UnexpectedEndOfInput, 0)]
+    #[case("1 2", ErrorKind::TrailingInput, 2)]
+    #[case("+ 1 x", ErrorKind::UnexpectedToken, 4)]
+    #[case("* 1 2", ErrorKind::UnexpectedToken, 0)]
+    #[case("+ 1 (", ErrorKind::UnexpectedToken, 4)]
+    #[case("+ 1 \"2\"", ErrorKind::UnexpectedToken, 4)]
+    #[case("% 1 2", ErrorKind::InvalidCharacter('%'), 0)]
+    #[case(
+        "+ 1 99999999999999999999",
+        ErrorKind::LexicalError(LexicalError::IntegerOverflow),
+        4
+    )]
+    fn test_2_4_1_a_numeric_translation_invalid(
+        #[case] input: &str,
+        #[case] kind: ErrorKind,
+        #[case] offset: usize,
+    ) {
+        let error = PrefixTranslator::new(input).translate().unwrap_err();
+
+        assert_eq!(error.kind, kind);
+        assert_eq!(error.span.start.offset, offset);
+    }
+}
@@ -1,5 +1,8 @@
-#[derive(Debug, PartialEq)]
-enum Token {
+use super::diagnostic::{char_span, Diagnostic, ErrorKind};
+use super::parse_tree::{Nonterminal, ParseTree};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Token {
     Epsilon,
     OpenParam,
     CloseParam,
@@ -27,44 +30,81 @@ impl ParserB {
         }
     }
 
-    pub fn parse(&mut self) -> Result<(), String> {
-        self.internal_parse()?;
+    pub fn parse(&mut self) -> Result<ParseTree<Token>, Diagnostic<Token>> {
+        let tree = self.internal_parse()?;
 
-        if self.lookahead_index == self.input.len() {
-            Ok(())
-        } else {
-            Err("Syntax error: Invalid input".to_string())
+        let start = self.lookahead_index;
+        match self.next_token() {
+            Token::Epsilon => Ok(tree),
+            Token::Invalid => Err(self.invalid_character(vec![])),
+            token => Err(Diagnostic::new(
+                ErrorKind::TrailingInput,
+                vec![],
+                Some(token),
+                char_span(&self.input, start, self.lookahead_index),
+            )
+            .with_note("this CloseParam has no matching OpenParam")),
         }
     }
 
-    fn internal_parse(&mut self) -> Result<(), String> {
-        match self.next_token() {
+    fn internal_parse(&mut self) -> Result<ParseTree<Token>, Diagnostic<Token>> {
+        let r_children = match self.next_token() {
             Token::OpenParam => {
-                self.internal_parse()?;
+                let inner = self.internal_parse()?;
                 self.match_token(Token::CloseParam)?;
-                self.internal_parse()?;
-                Ok(())
+                let rest = self.internal_parse()?;
+                vec![
+                    ParseTree::Leaf(Token::OpenParam),
+                    inner,
+                    ParseTree::Leaf(Token::CloseParam),
+                    rest,
+                ]
             }
             Token::CloseParam => {
                 self.lookahead_index -= 1;
-                Ok(())
+                vec![ParseTree::Leaf(Token::Epsilon)]
             }
-            Token::Epsilon => Ok(()),
-            Token::Invalid => Err("Syntax error: Invalid char".to_string()),
-        }
+            Token::Epsilon => vec![ParseTree::Leaf(Token::Epsilon)],
+            Token::Invalid => {
+                return Err(self.invalid_character(vec![Token::OpenParam, Token::CloseParam]))
+            }
+        };
+
+        Ok(ParseTree::Node(
+            Nonterminal::S,
+            vec![ParseTree::Node(Nonterminal::R, r_children)],
+        ))
     }
 
-    fn match_token(&mut self, expected: Token) -> Result<(), String> {
-        if self.next_token() == expected {
-            Ok(())
-        } else {
-            Err(format!(
-                "Syntax error: Expected {:?} at position {}",
-                expected, self.lookahead_index
-            ))
+    fn match_token(&mut self, expected: Token) -> Result<(), Diagnostic<Token>> {
+        let start = self.lookahead_index;
+        match self.next_token() {
+            token if token == expected => Ok(()),
+            Token::Epsilon => Err(Diagnostic::new(
+                ErrorKind::UnexpectedEndOfInput,
+                vec![expected],
+                None,
+                char_span(&self.input, start, start),
+            )),
+            Token::Invalid => Err(self.invalid_character(vec![expected])),
+            token => Err(Diagnostic::new(
+                ErrorKind::UnexpectedToken,
+                vec![expected],
+                Some(token),
+                char_span(&self.input, start, self.lookahead_index),
+            )),
         }
     }
 
+    fn invalid_character(&self, expected: Vec<Token>) -> Diagnostic<Token> {
+        Diagnostic::new(
+            ErrorKind::InvalidCharacter(self.input[self.lookahead_index]),
+            expected,
+            None,
+            char_span(&self.input, self.lookahead_index, self.lookahead_index + 1),
+        )
+    }
+
     fn next_token(&mut self) -> Token {
         if self.lookahead_index >= self.input.len() {
             return Token::Epsilon;
@@ -91,29 +131,75 @@ mod tests {
     use rstest::rstest;
 
     #[rstest]
+    #[case("")]
     #[case("()")]
     #[case("()()()")]
     #[case("(()())()(())")]
     #[case("(())()")]
     #[case("(())(())(())")]
-    fn test_2_4_1_b_valid(#[case] input: &str) -> Result<(), String> {
-        assert_eq!(ParserB::new(input).parse()?, ());
+    fn test_2_4_1_b_valid(#[case] input: &str) -> Result<(), Diagnostic<Token>> {
+        let tree = ParserB::new(input).parse()?;
+        let parens = tree
+            .leaves()
+            .into_iter()
+            .filter(|token| **token != Token::Epsilon)
+            .count();
+        assert_eq!(parens, input.len());
         Ok(())
     }
 
     #[rstest]
-    #[case("a", "Syntax error: Invalid char")]
-    #[case("(", "Syntax error: Expected CloseParam at position 1")]
-    #[case(")", "Syntax error: Invalid input")]
-    #[case(")(", "Syntax error: Invalid input")]
-    #[case("())", "Syntax error: Invalid input")]
-    #[case("(()", "Syntax error: Expected CloseParam at position 3")]
-    // #[case("()())", "Syntax error: Invalid input")]
-    fn test_2_4_1_invalid(#[case] input: &str, #[case] error_message: &str) -> Result<(), String> {
-        assert_eq!(
-            ParserB::new(input).parse().unwrap_err(),
-            error_message.to_string()
+    fn test_2_4_1_b_parse_tree() -> Result<(), Diagnostic<Token>> {
+        let epsilon = || {
+            ParseTree::Node(
+                Nonterminal::S,
+                vec![ParseTree::Node(
+                    Nonterminal::R,
+                    vec![ParseTree::Leaf(Token::Epsilon)],
+                )],
+            )
+        };
+        let expected = ParseTree::Node(
+            Nonterminal::S,
+            vec![ParseTree::Node(
+                Nonterminal::R,
+                vec![
+                    ParseTree::Leaf(Token::OpenParam),
+                    epsilon(),
+                    ParseTree::Leaf(Token::CloseParam),
+                    epsilon(),
+                ],
+            )],
         );
+
+        assert_eq!(ParserB::new("()").parse()?, expected);
         Ok(())
     }
+
+    #[rstest]
+    #[case("a", ErrorKind::InvalidCharacter('a'), 0)]
+    #[case("(", ErrorKind::UnexpectedEndOfInput, 1)]
+    #[case(")", ErrorKind::TrailingInput, 0)]
+    #[case(")(", ErrorKind::TrailingInput, 0)]
+    #[case("())", ErrorKind::TrailingInput, 2)]
+    #[case("(()", ErrorKind::UnexpectedEndOfInput, 3)]
+    #[case("()())", ErrorKind::TrailingInput, 4)]
+    #[case("(a)", ErrorKind::InvalidCharacter('a'), 1)]
+    fn test_2_4_1_invalid(#[case] input: &str, #[case] kind: ErrorKind, #[case] offset: usize) {
+        let error = ParserB::new(input).parse().unwrap_err();
+
+        assert_eq!(error.kind, kind);
+        assert_eq!(error.span.start.offset, offset);
+    }
+
+    #[rstest]
+    fn test_2_4_1_b_render() {
+        let error = ParserB::new("(()").parse().unwrap_err();
+
+        assert_eq!(error.expected, vec![Token::CloseParam]);
+        assert_eq!(
+            error.render("(()"),
+            "error: expected CloseParam, found end of input\n --> 1:4\n  |\n1 | (()\n  |    ^"
+        );
+    }
 }
@@ -1,5 +1,8 @@
-#[derive(Debug, PartialEq)]
-enum Token {
+use super::diagnostic::{char_span, Diagnostic, ErrorKind};
+use super::parse_tree::{Nonterminal, ParseTree};
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Token {
     Zero,
     One,
     Epsilon,
@@ -22,47 +25,90 @@ impl ParserC {
         }
     }
 
-    pub fn parse(&mut self) -> Result<(), String> {
-        self.parse_internal()?;
+    pub fn parse(&mut self) -> Result<ParseTree<Token>, Diagnostic<Token>> {
+        let tree = self.parse_internal()?;
 
-        if self.lookahead_index < self.input.len() {
-            Err("Syntax error: Invalid input".to_string())
-        } else {
-            Ok(())
+        let start = self.lookahead_index;
+        match self.next_token() {
+            Token::Epsilon => Ok(tree),
+            Token::Invalid => Err(self.invalid_character(vec![])),
+            token => Err(Diagnostic::new(
+                ErrorKind::TrailingInput,
+                vec![],
+                Some(token),
+                char_span(&self.input, start, self.lookahead_index),
+            )),
         }
     }
 
-    fn parse_internal(&mut self) -> Result<(), String> {
+    // Every string S derives starts with 0, so "" and an S that starts with
+    // 1 are errors
+    fn parse_internal(&mut self) -> Result<ParseTree<Token>, Diagnostic<Token>> {
+        let start = self.lookahead_index;
         match self.next_token() {
             Token::Zero => {
-                self.parse_internal()?;
+                let mut children = vec![ParseTree::Leaf(Token::Zero)];
+                // A lookahead of 1 selects S := 01, anything else S := 0S1
+                if self.peek_token() != Token::One {
+                    children.push(self.parse_internal()?);
+                }
                 self.match_token(Token::One)?;
+                children.push(ParseTree::Leaf(Token::One));
 
-                Ok(())
+                Ok(ParseTree::Node(Nonterminal::S, children))
             }
-            Token::One => {
-                self.lookahead_index -= 1;
-                Ok(())
-            }
-            Token::Epsilon => Ok(()),
-            Token::Invalid => Err(format!(
-                "Syntax error: Invalid token at position {}",
-                self.lookahead_index
+            Token::One => Err(Diagnostic::new(
+                ErrorKind::UnexpectedToken,
+                vec![Token::Zero],
+                Some(Token::One),
+                char_span(&self.input, start, self.lookahead_index),
+            )),
+            Token::Epsilon => Err(Diagnostic::new(
+                ErrorKind::UnexpectedEndOfInput,
+                vec![Token::Zero],
+                None,
+                char_span(&self.input, start, start),
             )),
+            Token::Invalid => Err(self.invalid_character(vec![Token::Zero])),
         }
     }
 
-    fn match_token(&mut self, expected: Token) -> Result<(), String> {
-        if self.next_token() == expected {
-            Ok(())
-        } else {
-            Err(format!(
-                "Syntax error: Expected {:?} at position {}",
-                expected, self.lookahead_index
-            ))
+    fn match_token(&mut self, expected: Token) -> Result<(), Diagnostic<Token>> {
+        let start = self.lookahead_index;
+        match self.next_token() {
+            token if token == expected => Ok(()),
+            Token::Epsilon => Err(Diagnostic::new(
+                ErrorKind::UnexpectedEndOfInput,
+                vec![expected],
+                None,
+                char_span(&self.input, start, start),
+            )),
+            Token::Invalid => Err(self.invalid_character(vec![expected])),
+            token => Err(Diagnostic::new(
+                ErrorKind::UnexpectedToken,
+                vec![expected],
+                Some(token),
+                char_span(&self.input, start, self.lookahead_index),
+            )),
         }
     }
 
+    fn invalid_character(&self, expected: Vec<Token>) -> Diagnostic<Token> {
+        Diagnostic::new(
+            ErrorKind::InvalidCharacter(self.input[self.lookahead_index]),
+            expected,
+            None,
+            char_span(&self.input, self.lookahead_index, self.lookahead_index + 1),
+        )
+    }
+
+    fn peek_token(&mut self) -> Token {
+        let lookahead_index = self.lookahead_index;
+        let token = self.next_token();
+        self.lookahead_index = lookahead_index;
+        token
+    }
+
     fn next_token(&mut self) -> Token {
         if self.lookahead_index >= self.input.len() {
             return Token::Epsilon;
@@ -91,25 +137,44 @@ mod tests {
     #[case("01")]
     #[case("0011")]
     #[case("00000000001111111111")]
-    fn test_2_4_1_b_valid(#[case] input: &str) -> Result<(), String> {
-        assert_eq!(ParserC::new(input).parse()?, ());
+    fn test_2_4_1_b_valid(#[case] input: &str) -> Result<(), Diagnostic<Token>> {
+        let tree = ParserC::new(input).parse()?;
+        assert_eq!(tree.leaves().len(), input.len());
         Ok(())
     }
 
     #[rstest]
-    #[case("0", "Syntax error: Expected One at position 1")]
-    #[case("1", "Syntax error: Invalid input")]
-    #[case("10", "Syntax error: Invalid input")]
-    #[case("010", "Syntax error: Invalid input")]
-    #[case("001", "Syntax error: Expected One at position 3")]
-    #[case("00110", "Syntax error: Invalid input")]
-    #[case("a", "Syntax error: Invalid token at position 0")]
-    // #[case("()())", "Syntax error: Invalid input")]
-    fn test_2_4_1_invalid(#[case] input: &str, #[case] error_message: &str) -> Result<(), String> {
-        assert_eq!(
-            ParserC::new(input).parse().unwrap_err(),
-            error_message.to_string()
+    fn test_2_4_1_c_parse_tree() -> Result<(), Diagnostic<Token>> {
+        let expected = ParseTree::Node(
+            Nonterminal::S,
+            vec![
+                ParseTree::Leaf(Token::Zero),
+                ParseTree::Node(
+                    Nonterminal::S,
+                    vec![ParseTree::Leaf(Token::Zero), ParseTree::Leaf(Token::One)],
+                ),
+                ParseTree::Leaf(Token::One),
+            ],
         );
+
+        assert_eq!(ParserC::new("0011").parse()?, expected);
         Ok(())
     }
+
+    #[rstest]
+    #[case("0", ErrorKind::UnexpectedEndOfInput, 1)]
+    #[case("", ErrorKind::UnexpectedEndOfInput, 0)]
+    #[case("1", ErrorKind::UnexpectedToken, 0)]
+    #[case("10", ErrorKind::UnexpectedToken, 0)]
+    #[case("010", ErrorKind::TrailingInput, 2)]
+    #[case("001", ErrorKind::UnexpectedEndOfInput, 3)]
+    #[case("00110", ErrorKind::TrailingInput, 4)]
+    #[case("a", ErrorKind::InvalidCharacter('a'), 0)]
+    #[case("01a", ErrorKind::InvalidCharacter('a'), 2)]
+    fn test_2_4_1_invalid(#[case] input: &str, #[case] kind: ErrorKind, #[case] offset: usize) {
+        let error = ParserC::new(input).parse().unwrap_err();
+
+        assert_eq!(error.kind, kind);
+        assert_eq!(error.span.start.offset, offset);
+    }
 }
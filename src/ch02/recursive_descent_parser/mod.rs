@@ -1,3 +1,6 @@
+pub mod diagnostic;
 pub mod ex2_4_1_a;
+pub mod ex2_4_1_a_translator;
 pub mod ex2_4_1_b;
 pub mod ex2_4_1_c;
+pub mod parse_tree;
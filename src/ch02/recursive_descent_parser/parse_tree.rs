@@ -0,0 +1,176 @@
+use std::fmt::{self, Debug, Display, Write};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Nonterminal {
+    S,
+    R,
+}
+
+// Interior nodes are labelled by N, which defaults to the nonterminals of
+// the hand-written parsers; parsers generated from a grammar use its names
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseTree<T, N = Nonterminal> {
+    Node(N, Vec<ParseTree<T, N>>),
+    Leaf(T),
+}
+
+impl<T: Debug, N: Debug> ParseTree<T, N> {
+    pub fn leaves(&self) -> Vec<&T> {
+        match self {
+            ParseTree::Node(_, children) => children.iter().flat_map(|c| c.leaves()).collect(),
+            ParseTree::Leaf(token) => vec![token],
+        }
+    }
+
+    // Prints the tree one symbol per line, e.g. for "+aa":
+    //
+    // S
+    // ├── Plus
+    // ├── S
+    // │   └── A
+    // └── S
+    //     └── A
+    pub fn pretty_print(&self) -> String {
+        let mut output = String::new();
+        self.pretty_print_internal("", "", &mut output);
+        output
+    }
+
+    fn pretty_print_internal(&self, prefix: &str, child_prefix: &str, output: &mut String) {
+        output.push_str(prefix);
+        output.push_str(&self.label());
+        output.push('\n');
+
+        if let ParseTree::Node(_, children) = self {
+            for (i, child) in children.iter().enumerate() {
+                if i + 1 == children.len() {
+                    child.pretty_print_internal(
+                        &format!("{}└── ", child_prefix),
+                        &format!("{}    ", child_prefix),
+                        output,
+                    );
+                } else {
+                    child.pretty_print_internal(
+                        &format!("{}├── ", child_prefix),
+                        &format!("{}│   ", child_prefix),
+                        output,
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn to_dot(&self) -> String {
+        let mut output = String::from("digraph ParseTree {\n");
+        let mut next_id = 0;
+        self.to_dot_internal(&mut next_id, &mut output);
+        output.push_str("}\n");
+        output
+    }
+
+    fn to_dot_internal(&self, next_id: &mut usize, output: &mut String) -> usize {
+        let id = *next_id;
+        *next_id += 1;
+
+        match self {
+            ParseTree::Node(_, children) => {
+                writeln!(output, "    n{} [label=\"{}\"];", id, self.label()).unwrap();
+                for child in children.iter() {
+                    let child_id = child.to_dot_internal(next_id, output);
+                    writeln!(output, "    n{} -> n{};", id, child_id).unwrap();
+                }
+            }
+            ParseTree::Leaf(_) => {
+                writeln!(
+                    output,
+                    "    n{} [label=\"{}\", shape=plaintext];",
+                    id,
+                    self.label()
+                )
+                .unwrap();
+            }
+        }
+        id
+    }
+
+    fn label(&self) -> String {
+        match self {
+            ParseTree::Node(nonterminal, _) => format!("{:?}", nonterminal),
+            ParseTree::Leaf(token) => format!("{:?}", token),
+        }
+    }
+}
+
+impl<T: Debug, N: Debug> Display for ParseTree<T, N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.pretty_print())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[derive(Debug, PartialEq)]
+    enum Token {
+        A,
+        Plus,
+    }
+
+    fn plus_a_a() -> ParseTree<Token> {
+        ParseTree::Node(
+            Nonterminal::S,
+            vec![
+                ParseTree::Leaf(Token::Plus),
+                ParseTree::Node(Nonterminal::S, vec![ParseTree::Leaf(Token::A)]),
+                ParseTree::Node(Nonterminal::S, vec![ParseTree::Leaf(Token::A)]),
+            ],
+        )
+    }
+
+    #[rstest]
+    fn test_leaves() {
+        assert_eq!(
+            plus_a_a().leaves(),
+            vec![&Token::Plus, &Token::A, &Token::A]
+        );
+    }
+
+    #[rstest]
+    fn test_pretty_print() {
+        assert_eq!(
+            plus_a_a().pretty_print(),
+            [
+                "S",
+                "├── Plus",
+                "├── S",
+                "│   └── A",
+                "└── S",
+                "    └── A",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[rstest]
+    fn test_to_dot() {
+        assert_eq!(
+            plus_a_a().to_dot(),
+            "digraph ParseTree {\n    \
+             n0 [label=\"S\"];\n    \
+             n1 [label=\"Plus\", shape=plaintext];\n    \
+             n0 -> n1;\n    \
+             n2 [label=\"S\"];\n    \
+             n3 [label=\"A\", shape=plaintext];\n    \
+             n2 -> n3;\n    \
+             n0 -> n2;\n    \
+             n4 [label=\"S\"];\n    \
+             n5 [label=\"A\", shape=plaintext];\n    \
+             n4 -> n5;\n    \
+             n0 -> n4;\n\
+             }\n"
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+
+use super::interner::Symbol;
+use crate::ch02::lexical_analyzer::span::Span;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Type {
+    Int,
+    Float,
+    Char,
+    Bool,
+    String,
+}
+
+impl Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Type::Int => "int",
+            Type::Float => "float",
+            Type::Char => "char",
+            Type::Bool => "bool",
+            Type::String => "string",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SymbolKind {
+    Variable,
+    Constant,
+    Parameter,
+    Function,
+}
+
+// What the symbol table records about a declared name
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct SymbolInfo {
+    pub kind: SymbolKind,
+    pub ty: Type,
+    pub declared_at: Span,
+}
+
+// The chained symbol tables of section 2.7: one table per block, each
+// pointing to the table of the enclosing block. Looking a name up walks the
+// chain outwards, so the innermost declaration wins.
+#[derive(Debug, Default)]
+pub struct Env {
+    table: HashMap<Symbol, SymbolInfo>,
+    prev: Option<Box<Env>>,
+}
+
+impl Env {
+    pub fn new() -> Self {
+        Env::default()
+    }
+
+    // Declares symbol in the innermost scope. Returns the previous
+    // declaration from that same scope, if any, so callers can report
+    // redeclarations; declarations in enclosing scopes are only shadowed.
+    pub fn put(&mut self, symbol: Symbol, info: SymbolInfo) -> Option<SymbolInfo> {
+        self.table.insert(symbol, info)
+    }
+
+    pub fn get(&self, symbol: Symbol) -> Option<&SymbolInfo> {
+        let mut env = Some(self);
+        while let Some(current) = env {
+            if let Some(info) = current.table.get(&symbol) {
+                return Some(info);
+            }
+            env = current.prev.as_deref();
+        }
+        None
+    }
+
+    pub fn get_local(&self, symbol: Symbol) -> Option<&SymbolInfo> {
+        self.table.get(&symbol)
+    }
+
+    // Number of scopes enclosing the innermost one
+    pub fn depth(&self) -> usize {
+        let mut depth = 0;
+        let mut env = self.prev.as_deref();
+        while let Some(current) = env {
+            depth += 1;
+            env = current.prev.as_deref();
+        }
+        depth
+    }
+
+    pub fn enter_scope(&mut self) {
+        let enclosing = std::mem::take(self);
+        self.prev = Some(Box::new(enclosing));
+    }
+
+    // Closes the innermost scope and returns its declarations, or None when
+    // it is the outermost scope, which is never closed
+    pub fn exit_scope(&mut self) -> Option<HashMap<Symbol, SymbolInfo>> {
+        let enclosing = self.prev.take()?;
+        let closed = std::mem::replace(self, *enclosing);
+        Some(closed.table)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::super::interner::Interner;
+    use super::*;
+    use crate::ch02::lexical_analyzer::ex_2_6::{Lexer, Punctuation, SpannedToken, Token};
+    use rstest::rstest;
+
+    fn info(ty: Type) -> SymbolInfo {
+        SymbolInfo {
+            kind: SymbolKind::Variable,
+            ty,
+            declared_at: Span::default(),
+        }
+    }
+
+    #[rstest]
+    fn test_env_scopes() {
+        let mut interner = Interner::new();
+        let x = interner.intern("x");
+        let y = interner.intern("y");
+        let mut env = Env::new();
+
+        assert_eq!(env.put(x, info(Type::Int)), None);
+        assert_eq!(env.put(x, info(Type::Float)), Some(info(Type::Int)));
+
+        env.enter_scope();
+        assert_eq!(env.depth(), 1);
+        assert_eq!(env.put(x, info(Type::Bool)), None);
+        env.put(y, info(Type::Char));
+        assert_eq!(env.get(x), Some(&info(Type::Bool)));
+        assert_eq!(env.get_local(x), Some(&info(Type::Bool)));
+
+        let closed = env.exit_scope().unwrap();
+        assert_eq!(closed.len(), 2);
+        assert_eq!(env.get(x), Some(&info(Type::Float)));
+        assert_eq!(env.get(y), None);
+        assert_eq!(env.exit_scope(), None);
+        assert_eq!(env.depth(), 0);
+    }
+
+    // The translation of section 2.7.1, which drops declarations and
+    // annotates every use of a name with the type it was declared with:
+    // block := '{' decls stmts '}'
+    // decls := decls decl | ε
+    // decl := type id ;
+    // stmts := stmts stmt | ε
+    // stmt := block | id ;
+    fn annotate_types(input: &str) -> String {
+        let mut lexer = Lexer::new(input);
+        let tokens = lexer.tokenize();
+        let interner = lexer.interner();
+        let mut env = Env::new();
+        let mut output = Vec::new();
+
+        let mut i = 0;
+        while i < tokens.len() {
+            match &tokens[i].token {
+                Token::Punctuation(Punctuation::OpenBrace, _) => {
+                    env.enter_scope();
+                    output.push("{".to_string());
+                }
+                Token::Punctuation(Punctuation::CloseBrace, _) => {
+                    env.exit_scope().unwrap();
+                    output.push("}".to_string());
+                }
+                Token::Word(_, symbol) => {
+                    let ty = match interner.resolve(*symbol) {
+                        "int" => Some(Type::Int),
+                        "char" => Some(Type::Char),
+                        "bool" => Some(Type::Bool),
+                        _ => None,
+                    };
+                    match (ty, &tokens[i + 1]) {
+                        (
+                            Some(ty),
+                            SpannedToken {
+                                token: Token::Word(_, id),
+                                span,
+                            },
+                        ) => {
+                            let declaration = SymbolInfo {
+                                kind: SymbolKind::Variable,
+                                ty,
+                                declared_at: *span,
+                            };
+                            env.put(*id, declaration);
+                            i += 2;
+                        }
+                        _ => {
+                            let ty = env.get(*symbol).unwrap().ty;
+                            output.push(format!("{}:{};", interner.resolve(*symbol), ty));
+                            i += 1;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            i += 1;
+        }
+        output.join(" ")
+    }
+
+    #[rstest]
+    #[case(
+        "{ int x; char y; { bool y; x; y; } x; y; }",
+        "{ { x:int; y:bool; } x:int; y:char; }"
+    )]
+    #[case(
+        "{ int x; { { x; } char x; x; } x; }",
+        "{ { { x:int; } x:char; } x:int; }"
+    )]
+    fn test_env_translation(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(annotate_types(input), expected);
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::HashMap;
+use std::fmt::{self, Display};
+use std::rc::Rc;
+
+// A compact id for an interned string. Two symbols from the same Interner
+// are equal exactly when their strings are.
+#[derive(Debug, Hash, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn index(&self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+// Stores every distinct string once and hands out Symbols in the order the
+// strings were first seen. Each string is shared between the map that finds
+// its symbol and the list that resolves the symbol back.
+#[derive(Debug, Default, Clone)]
+pub struct Interner {
+    symbols: HashMap<Rc<str>, Symbol>,
+    strings: Vec<Rc<str>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    pub fn intern(&mut self, string: &str) -> Symbol {
+        if let Some(symbol) = self.get(string) {
+            return symbol;
+        }
+        let symbol = Symbol(u32::try_from(self.strings.len()).expect("too many symbols"));
+        let string: Rc<str> = string.into();
+        self.strings.push(Rc::clone(&string));
+        self.symbols.insert(string, symbol);
+        symbol
+    }
+
+    pub fn get(&self, string: &str) -> Option<Symbol> {
+        self.symbols.get(string).copied()
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        &self.strings[symbol.index()]
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_interner() {
+        let mut interner = Interner::new();
+        let x = interner.intern("x");
+        let y = interner.intern("y");
+
+        assert_ne!(x, y);
+        assert_eq!(interner.intern("x"), x);
+        assert_eq!(interner.get("y"), Some(y));
+        assert_eq!(interner.get("z"), None);
+        assert_eq!(interner.resolve(y), "y");
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[rstest]
+    fn test_interner_many_strings() {
+        let mut interner = Interner::new();
+        let names: Vec<String> = (0..10_000).map(|i| format!("name_{}", i)).collect();
+        let symbols: Vec<Symbol> = names.iter().map(|name| interner.intern(name)).collect();
+
+        for (name, symbol) in names.iter().zip(symbols) {
+            assert_eq!(interner.intern(name), symbol);
+            assert_eq!(interner.resolve(symbol), name);
+        }
+        assert_eq!(interner.len(), 10_000);
+    }
+
+    // Strings that differ only slightly, or are empty, get their own symbols
+    #[rstest]
+    fn test_interner_similar_strings() {
+        let mut interner = Interner::new();
+        let strings = ["", "a", "ab", "ba", "A", "a "];
+        let symbols: Vec<Symbol> = strings.iter().map(|s| interner.intern(s)).collect();
+
+        for (string, symbol) in strings.iter().zip(symbols) {
+            assert_eq!(interner.get(string), Some(symbol));
+            assert_eq!(interner.resolve(symbol), *string);
+        }
+        assert_eq!(interner.len(), strings.len());
+        assert_eq!(interner.get("b"), None);
+    }
+}
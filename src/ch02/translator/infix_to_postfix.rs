@@ -0,0 +1,349 @@
+use std::fmt::{self, Display};
+
+use crate::ch02::lexical_analyzer::ex_2_6::{
+    self, ArithmeticOperator, Lexer, Punctuation, SpannedToken,
+};
+use crate::ch02::lexical_analyzer::span::Span;
+use crate::ch02::recursive_descent_parser::diagnostic::{char_span, Diagnostic, ErrorKind};
+use crate::ch02::symbol_table::interner::Interner;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Token {
+    Plus,
+    Minus,
+    Times,
+    Divide,
+    OpenParen,
+    CloseParen,
+    Number,
+    Identifier,
+    Keyword,
+    Operator,
+    Punctuation,
+    Literal,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Operator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+}
+
+impl Operator {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Operator::Add => "+",
+            Operator::Subtract => "-",
+            Operator::Multiply => "*",
+            Operator::Divide => "/",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum SyntaxTree {
+    Number(String),
+    Identifier(String),
+    BinaryOperation(Operator, Box<SyntaxTree>, Box<SyntaxTree>),
+}
+
+impl Display for SyntaxTree {
+    // Fully parenthesized infix form, which makes the tree shape visible
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyntaxTree::Number(literal) => write!(f, "{}", literal),
+            SyntaxTree::Identifier(name) => write!(f, "{}", name),
+            SyntaxTree::BinaryOperation(operator, left, right) => {
+                write!(f, "({}{}{})", left, operator.symbol(), right)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Translation {
+    pub postfix: String,
+    pub syntax_tree: SyntaxTree,
+}
+
+pub struct Translator<'a> {
+    tokens: Vec<SpannedToken<'a>>,
+    interner: Interner,
+    lookahead_index: usize,
+    end_of_input: Span,
+    postfix: Vec<String>,
+}
+
+impl<'a> Translator<'a> {
+    // Translation scheme for the following grammar:
+    // expr   := expr + term { print('+') } | expr - term { print('-') } | term
+    // term   := term * factor { print('*') } | term / factor { print('/') } | factor
+    // factor := num { print(num) } | id { print(id) } | ( expr )
+    // Since expr and term are left-recursive, they are converted to:
+    // expr   := term rest
+    // rest   := + term { print('+') } rest | - term { print('-') } rest | ε
+    // term   := factor rest_t
+    // rest_t := * factor { print('*') } rest_t | / factor { print('/') } rest_t | ε
+    // The left operand is passed down to rest/rest_t as an inherited attribute
+    // so the syntax tree stays left-associative.
+
+    pub fn new(input: &'a str) -> Self {
+        let chars: Vec<char> = input.chars().collect();
+        let mut lexer = Lexer::new(input);
+        Translator {
+            tokens: lexer.tokenize(),
+            interner: lexer.into_interner(),
+            lookahead_index: 0,
+            end_of_input: char_span(&chars, chars.len(), chars.len()),
+            postfix: Vec::new(),
+        }
+    }
+
+    pub fn translate(&mut self) -> Result<Translation, Diagnostic<Token>> {
+        let syntax_tree = self.expr()?;
+
+        if let Some(spanned) = self.tokens.get(self.lookahead_index) {
+            return Err(self.unexpected(spanned, ErrorKind::TrailingInput, vec![]));
+        }
+
+        Ok(Translation {
+            postfix: self.postfix.join(" "),
+            syntax_tree,
+        })
+    }
+
+    fn expr(&mut self) -> Result<SyntaxTree, Diagnostic<Token>> {
+        let left = self.term()?;
+        self.rest(left)
+    }
+
+    // The tail recursion of rest is turned into a loop (section 2.5.4)
+    fn rest(&mut self, mut left: SyntaxTree) -> Result<SyntaxTree, Diagnostic<Token>> {
+        loop {
+            let operator = match self.peek_token() {
+                Some(Ok(Token::Plus)) => Operator::Add,
+                Some(Ok(Token::Minus)) => Operator::Subtract,
+                _ => return Ok(left),
+            };
+            self.lookahead_index += 1;
+
+            let right = self.term()?;
+            self.postfix.push(operator.symbol().to_string());
+            left = SyntaxTree::BinaryOperation(operator, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn term(&mut self) -> Result<SyntaxTree, Diagnostic<Token>> {
+        let left = self.factor()?;
+        self.rest_t(left)
+    }
+
+    fn rest_t(&mut self, mut left: SyntaxTree) -> Result<SyntaxTree, Diagnostic<Token>> {
+        loop {
+            let operator = match self.peek_token() {
+                Some(Ok(Token::Times)) => Operator::Multiply,
+                Some(Ok(Token::Divide)) => Operator::Divide,
+                _ => return Ok(left),
+            };
+            self.lookahead_index += 1;
+
+            let right = self.factor()?;
+            self.postfix.push(operator.symbol().to_string());
+            left = SyntaxTree::BinaryOperation(operator, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn factor(&mut self) -> Result<SyntaxTree, Diagnostic<Token>> {
+        let first_of_factor = vec![Token::Number, Token::Identifier, Token::OpenParen];
+        let spanned = match self.tokens.get(self.lookahead_index) {
+            Some(spanned) => spanned.clone(),
+            None => {
+                return Err(Diagnostic::new(
+                    ErrorKind::UnexpectedEndOfInput,
+                    first_of_factor,
+                    None,
+                    self.end_of_input,
+                ))
+            }
+        };
+
+        match &spanned.token {
+            ex_2_6::Token::Number(_, literal) => {
+                self.lookahead_index += 1;
+                self.postfix.push(literal.to_string());
+                Ok(SyntaxTree::Number(literal.to_string()))
+            }
+            ex_2_6::Token::Word(ex_2_6::Word::Identifier, symbol) => {
+                self.lookahead_index += 1;
+                let name = self.interner.resolve(*symbol).to_string();
+                self.postfix.push(name.clone());
+                Ok(SyntaxTree::Identifier(name))
+            }
+            ex_2_6::Token::Punctuation(Punctuation::OpenParen, _) => {
+                self.lookahead_index += 1;
+                let inner = self.expr()?;
+                self.match_token(Token::CloseParen)?;
+                Ok(inner)
+            }
+            _ => Err(self.unexpected(&spanned, ErrorKind::UnexpectedToken, first_of_factor)),
+        }
+    }
+
+    fn match_token(&mut self, expected: Token) -> Result<(), Diagnostic<Token>> {
+        match self.tokens.get(self.lookahead_index) {
+            Some(spanned) if Self::classify(&spanned.token) == Ok(expected) => {
+                self.lookahead_index += 1;
+                Ok(())
+            }
+            Some(spanned) => {
+                Err(self.unexpected(spanned, ErrorKind::UnexpectedToken, vec![expected]))
+            }
+            None => Err(Diagnostic::new(
+                ErrorKind::UnexpectedEndOfInput,
+                vec![expected],
+                None,
+                self.end_of_input,
+            )),
+        }
+    }
+
+    fn peek_token(&self) -> Option<Result<Token, ErrorKind>> {
+        self.tokens
+            .get(self.lookahead_index)
+            .map(|spanned| Self::classify(&spanned.token))
+    }
+
+    // Maps a lexer token to the terminal it stands for in this grammar, or to
+    // the error kind to report when the lexer could not recognize it
+    fn classify(token: &ex_2_6::Token) -> Result<Token, ErrorKind> {
+        match token {
+            ex_2_6::Token::Number(_, _) => Ok(Token::Number),
+            ex_2_6::Token::Word(ex_2_6::Word::Identifier, _) => Ok(Token::Identifier),
+            ex_2_6::Token::Word(_, _) => Ok(Token::Keyword),
+            ex_2_6::Token::ArithmeticOperator(tag, _) => Ok(match tag {
+                ArithmeticOperator::Plus => Token::Plus,
+                ArithmeticOperator::Minus => Token::Minus,
+                ArithmeticOperator::Times => Token::Times,
+                ArithmeticOperator::Divide => Token::Divide,
+            }),
+            ex_2_6::Token::Punctuation(Punctuation::OpenParen, _) => Ok(Token::OpenParen),
+            ex_2_6::Token::Punctuation(Punctuation::CloseParen, _) => Ok(Token::CloseParen),
+            ex_2_6::Token::Punctuation(_, _) => Ok(Token::Punctuation),
+            ex_2_6::Token::StringLiteral(_, _) | ex_2_6::Token::CharLiteral(_, _) => {
+                Ok(Token::Literal)
+            }
+            ex_2_6::Token::LogicalOperator(_, _)
+            | ex_2_6::Token::LogicalConnective(_, _)
+            | ex_2_6::Token::BitwiseOperator(_, _)
+            | ex_2_6::Token::Assign(_) => Ok(Token::Operator),
+            ex_2_6::Token::Error(error, _) => Err(ErrorKind::LexicalError(*error)),
+            ex_2_6::Token::Unknown(lexeme) => {
+                Err(ErrorKind::InvalidCharacter(lexeme.chars().next().unwrap()))
+            }
+            ex_2_6::Token::Comment(_) | ex_2_6::Token::DocComment(_, _) => {
+                unreachable!("comments are discarded unless Lexer::with_comments is set")
+            }
+            ex_2_6::Token::Epsilon => unreachable!("Lexer::tokenize never yields Epsilon"),
+        }
+    }
+
+    fn unexpected(
+        &self,
+        spanned: &SpannedToken<'a>,
+        kind: ErrorKind,
+        expected: Vec<Token>,
+    ) -> Diagnostic<Token> {
+        match Self::classify(&spanned.token) {
+            Ok(found) => Diagnostic::new(kind, expected, Some(found), spanned.span),
+            Err(error_kind) => Diagnostic::new(error_kind, expected, None, spanned.span),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ch02::lexical_analyzer::ex_2_6::LexicalError;
+    use rstest::rstest;
+
+    #[rstest]
+    #[case("9", "9", "9")]
+    #[case("9-5+2", "9 5 - 2 +", "((9-5)+2)")]
+    #[case("9-5-2", "9 5 - 2 -", "((9-5)-2)")]
+    #[case("9+5*2", "9 5 2 * +", "(9+(5*2))")]
+    #[case("9*5+2", "9 5 * 2 +", "((9*5)+2)")]
+    #[case("8/4/2", "8 4 / 2 /", "((8/4)/2)")]
+    #[case("(9-5)*2", "9 5 - 2 *", "((9-5)*2)")]
+    #[case("9-(5-2)", "9 5 2 - -", "(9-(5-2))")]
+    #[case("count + 12 * rate", "count 12 rate * +", "(count+(12*rate))")]
+    #[case("0x1F * 1.5e3", "0x1F 1.5e3 *", "(0x1F*1.5e3)")]
+    #[case("a - b / c + d", "a b c / - d +", "((a-(b/c))+d)")]
+    fn test_translate(#[case] input: &str, #[case] postfix: &str, #[case] infix: &str) {
+        let translation = Translator::new(input).translate().unwrap();
+
+        assert_eq!(translation.postfix, postfix);
+        assert_eq!(translation.syntax_tree.to_string(), infix);
+    }
+
+    #[rstest]
+    fn test_syntax_tree_is_left_associative() {
+        let translation = Translator::new("1-2-3").translate().unwrap();
+
+        let number = |literal: &str| Box::new(SyntaxTree::Number(literal.to_string()));
+        assert_eq!(
+            translation.syntax_tree,
+            SyntaxTree::BinaryOperation(
+                Operator::Subtract,
+                Box::new(SyntaxTree::BinaryOperation(
+                    Operator::Subtract,
+                    number("1"),
+                    number("2")
+                )),
+                number("3"),
+            )
+        );
+    }
+
+    #[rstest]
+    #[case("", ErrorKind::UnexpectedEndOfInput, 0)]
+    #[case("9-", ErrorKind::UnexpectedEndOfInput, 2)]
+    #[case("(9-5", ErrorKind::UnexpectedEndOfInput, 4)]
+    #[case("9 5", ErrorKind::TrailingInput, 2)]
+    #[case("9)", ErrorKind::TrailingInput, 1)]
+    #[case("9+*2", ErrorKind::UnexpectedToken, 2)]
+    #[case("9 < 2", ErrorKind::TrailingInput, 2)]
+    #[case("9 % 2", ErrorKind::InvalidCharacter('%'), 2)]
+    #[case("(9;", ErrorKind::UnexpectedToken, 2)]
+    #[case("9 * 'x'", ErrorKind::UnexpectedToken, 4)]
+    #[case("9 + 0x", ErrorKind::LexicalError(LexicalError::MalformedNumber), 4)]
+    #[case("true", ErrorKind::UnexpectedToken, 0)]
+    #[case("1 + false", ErrorKind::UnexpectedToken, 4)]
+    fn test_translate_invalid(#[case] input: &str, #[case] kind: ErrorKind, #[case] offset: usize) {
+        let error = Translator::new(input).translate().unwrap_err();
+
+        assert_eq!(error.kind, kind);
+        assert_eq!(error.span.start.offset, offset);
+    }
+
+    #[rstest]
+    fn test_keyword_is_not_an_identifier() {
+        let error = Translator::new("true").translate().unwrap_err();
+
+        assert_eq!(error.found, Some(Token::Keyword));
+        assert_eq!(
+            error.expected,
+            vec![Token::Number, Token::Identifier, Token::OpenParen]
+        );
+    }
+
+    // rest and rest_t loop rather than recurse once per operator
+    #[rstest]
+    fn test_translate_long_chain() {
+        let input = vec!["a"; 20_000].join("+");
+
+        let translation = Translator::new(&input).translate().unwrap();
+        assert_eq!(translation.postfix.len(), 4 * 20_000 - 3);
+    }
+}
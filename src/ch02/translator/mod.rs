@@ -0,0 +1 @@
+pub mod infix_to_postfix;
@@ -0,0 +1,513 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, VecDeque};
+use std::fmt::{self, Display};
+
+use super::nfa::{Label, Nfa, StateId};
+
+// A state of each DFA when walking two of them in lockstep, None being the
+// dead state
+type StatePair = (Option<StateId>, Option<StateId>);
+
+// A DFA over chars. Each state's transitions are sorted, disjoint, inclusive
+// char ranges; a char no range covers leads to the implicit dead state.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Dfa {
+    transitions: Vec<Vec<(char, char, StateId)>>,
+    start: StateId,
+    accepting: BTreeSet<StateId>,
+}
+
+impl Dfa {
+    pub fn from_nfa(nfa: &Nfa) -> Self {
+        subset_construction(nfa).0
+    }
+
+    pub fn start(&self) -> StateId {
+        self.start
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn is_accepting(&self, state: StateId) -> bool {
+        self.accepting.contains(&state)
+    }
+
+    pub fn transitions(&self, state: StateId) -> &[(char, char, StateId)] {
+        &self.transitions[state]
+    }
+
+    pub fn next_state(&self, state: StateId, c: char) -> Option<StateId> {
+        self.transitions[state]
+            .iter()
+            .find(|(start, end, _)| *start <= c && c <= *end)
+            .map(|(_, _, to)| *to)
+    }
+
+    // Algorithm 3.18
+    pub fn matches(&self, input: &str) -> bool {
+        let mut state = self.start;
+        for c in input.chars() {
+            match self.next_state(state, c) {
+                Some(next) => state = next,
+                None => return false,
+            }
+        }
+        self.is_accepting(state)
+    }
+
+    // Algorithm 3.39: starts from the partition {accepting, non-accepting}
+    // and splits groups whose states disagree on the group some input leads
+    // to, until no group splits. The dead state takes part like any other so
+    // that a missing transition is told apart from one into a live state, and
+    // its group is dropped afterwards.
+    pub fn minimize(&self) -> Dfa {
+        let dead = self.state_count();
+        let alphabet = self.alphabet();
+        let target = |state: StateId, c: char| {
+            if state == dead {
+                dead
+            } else {
+                self.next_state(state, c).unwrap_or(dead)
+            }
+        };
+
+        let mut group: Vec<usize> = (0..=dead)
+            .map(|state| usize::from(self.is_accepting(state)))
+            .collect();
+        let mut group_count = 0;
+        loop {
+            let mut signatures: HashMap<Vec<usize>, usize> = HashMap::new();
+            let next_group: Vec<usize> = (0..=dead)
+                .map(|state| {
+                    let mut signature = vec![group[state]];
+                    signature.extend(alphabet.iter().map(|(c, _)| group[target(state, *c)]));
+                    let next_id = signatures.len();
+                    *signatures.entry(signature).or_insert(next_id)
+                })
+                .collect();
+            group = next_group;
+            if signatures.len() == group_count {
+                break;
+            }
+            group_count = signatures.len();
+        }
+
+        // One representative per group, numbered in breadth-first order from
+        // the start state
+        let dead_group = group[dead];
+        let mut representatives: Vec<StateId> = Vec::new();
+        let mut new_ids: HashMap<usize, StateId> = HashMap::new();
+        let mut queue = VecDeque::from([self.start]);
+        new_ids.insert(group[self.start], 0);
+        representatives.push(self.start);
+        let mut transitions = Vec::new();
+        while let Some(state) = queue.pop_front() {
+            let mut state_transitions = Vec::new();
+            for (start, end, to) in self.transitions[state].iter() {
+                if group[*to] == dead_group {
+                    continue;
+                }
+                let id = *new_ids.entry(group[*to]).or_insert_with(|| {
+                    representatives.push(*to);
+                    queue.push_back(*to);
+                    representatives.len() - 1
+                });
+                push_transition(&mut state_transitions, *start, *end, id);
+            }
+            transitions.push(state_transitions);
+        }
+
+        let accepting = representatives
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| self.is_accepting(**state))
+            .map(|(id, _)| id)
+            .collect();
+        Dfa {
+            transitions,
+            start: 0,
+            accepting,
+        }
+    }
+
+    // Walks both DFAs in lockstep and returns the shortest input, if any,
+    // that one of them accepts and the other rejects
+    pub fn counterexample(&self, other: &Dfa) -> Option<String> {
+        let ranges = self.ranges().chain(other.ranges());
+        let alphabet = elementary_intervals(ranges);
+
+        let start = (Some(self.start), Some(other.start));
+        let mut parents: HashMap<StatePair, Option<(StatePair, char)>> =
+            HashMap::from([(start, None)]);
+        let mut queue = VecDeque::from([start]);
+        while let Some(pair @ (left, right)) = queue.pop_front() {
+            let left_accepts = left.is_some_and(|state| self.is_accepting(state));
+            let right_accepts = right.is_some_and(|state| other.is_accepting(state));
+            if left_accepts != right_accepts {
+                let mut input = Vec::new();
+                let mut current = pair;
+                while let Some(Some((parent, c))) = parents.get(&current) {
+                    input.push(*c);
+                    current = *parent;
+                }
+                return Some(input.into_iter().rev().collect());
+            }
+
+            for (c, _) in alphabet.iter() {
+                let next = (
+                    left.and_then(|state| self.next_state(state, *c)),
+                    right.and_then(|state| other.next_state(state, *c)),
+                );
+                if next != (None, None) && !parents.contains_key(&next) {
+                    parents.insert(next, Some((pair, *c)));
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    pub fn is_equivalent(&self, other: &Dfa) -> bool {
+        self.counterexample(other).is_none()
+    }
+
+    fn ranges(&self) -> impl Iterator<Item = (char, char)> + '_ {
+        self.transitions
+            .iter()
+            .flat_map(|transitions| transitions.iter().map(|(start, end, _)| (*start, *end)))
+    }
+
+    // The input columns of the transition table: the char ranges on which
+    // every state behaves uniformly
+    pub fn alphabet(&self) -> Vec<(char, char)> {
+        elementary_intervals(self.ranges())
+            .into_iter()
+            .filter(|(c, _)| (0..self.state_count()).any(|s| self.next_state(s, *c).is_some()))
+            .collect()
+    }
+}
+
+// Prints the transition table, marking the start state with '>' and
+// accepting states with '*', e.g. for (a|b)*abb:
+//       a  b
+// >  0  1  0
+//    1  1  2
+//    2  1  3
+// *  3  1  0
+impl Display for Dfa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let alphabet = self.alphabet();
+        let mut rows = vec![[" ".to_string(), String::new()]
+            .into_iter()
+            .chain(
+                alphabet
+                    .iter()
+                    .map(|(start, end)| describe_range(*start, *end)),
+            )
+            .collect::<Vec<String>>()];
+        for state in 0..self.state_count() {
+            let marker = match (state == self.start, self.is_accepting(state)) {
+                (true, true) => ">*",
+                (true, false) => ">",
+                (false, true) => "*",
+                (false, false) => " ",
+            };
+            let mut row = vec![marker.to_string(), state.to_string()];
+            row.extend(
+                alphabet
+                    .iter()
+                    .map(|(c, _)| match self.next_state(state, *c) {
+                        Some(to) => to.to_string(),
+                        None => "-".to_string(),
+                    }),
+            );
+            rows.push(row);
+        }
+
+        let columns = rows[0].len();
+        let widths: Vec<usize> = (0..columns)
+            .map(|column| {
+                rows.iter()
+                    .map(|row| row[column].chars().count())
+                    .max()
+                    .unwrap()
+            })
+            .collect();
+        for row in rows.iter() {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(widths.iter())
+                .map(|(cell, width)| format!("{:>width$}", cell, width = width))
+                .collect();
+            writeln!(f, "{}", cells.join("  ").trim_end())?;
+        }
+        Ok(())
+    }
+}
+
+// Figure 3.32. Also returns the set of NFA states each DFA state stands for.
+pub fn subset_construction(nfa: &Nfa) -> (Dfa, Vec<BTreeSet<StateId>>) {
+    let ranges = (0..nfa.state_count())
+        .flat_map(|state| nfa.transitions(state).iter())
+        .flat_map(|(label, _)| label_ranges(label));
+
+    determinize(
+        nfa.start_closure(),
+        ranges,
+        |states, c| nfa.epsilon_closure(&nfa.move_on(states, c)),
+        |states| states.contains(&nfa.accept()),
+    )
+}
+
+// The loop the subset construction and the direct method of section 3.9.5
+// share: each DFA state is a set of NFA states or positions, and next gives
+// the set reached on a char. ranges must contain every range the sets'
+// transitions distinguish; the empty set is the dead state and left out.
+// Returns the DFA along with the set behind each of its states.
+pub fn determinize(
+    start: BTreeSet<usize>,
+    ranges: impl Iterator<Item = (char, char)>,
+    next: impl Fn(&BTreeSet<usize>, char) -> BTreeSet<usize>,
+    is_accepting: impl Fn(&BTreeSet<usize>) -> bool,
+) -> (Dfa, Vec<BTreeSet<usize>>) {
+    let alphabet = elementary_intervals(ranges);
+
+    let mut dstates = vec![start];
+    let mut ids: BTreeMap<BTreeSet<usize>, StateId> = BTreeMap::from([(dstates[0].clone(), 0)]);
+    let mut transitions = Vec::new();
+    let mut unmarked = 0;
+    while unmarked < dstates.len() {
+        let mut state_transitions = Vec::new();
+        for (start, end) in alphabet.iter() {
+            let next = next(&dstates[unmarked], *start);
+            if next.is_empty() {
+                continue;
+            }
+            let id = *ids.entry(next.clone()).or_insert_with(|| {
+                dstates.push(next);
+                dstates.len() - 1
+            });
+            push_transition(&mut state_transitions, *start, *end, id);
+        }
+        transitions.push(state_transitions);
+        unmarked += 1;
+    }
+
+    let accepting = dstates
+        .iter()
+        .enumerate()
+        .filter(|(_, states)| is_accepting(states))
+        .map(|(id, _)| id)
+        .collect();
+    let dfa = Dfa {
+        transitions,
+        start: 0,
+        accepting,
+    };
+    (dfa, dstates)
+}
+
+pub fn label_ranges(label: &Label) -> Vec<(char, char)> {
+    match label {
+        Label::Epsilon => vec![],
+        Label::Char(c) => vec![(*c, *c)],
+        Label::Class(class) => class.ranges.clone(),
+    }
+}
+
+// Appends a transition, extending the previous range when it continues it
+// into the same state
+fn push_transition(
+    transitions: &mut Vec<(char, char, StateId)>,
+    start: char,
+    end: char,
+    to: StateId,
+) {
+    if let Some((_, previous_end, previous_to)) = transitions.last_mut() {
+        if *previous_to == to && next_char(*previous_end) == Some(start) {
+            *previous_end = end;
+            return;
+        }
+    }
+    transitions.push((start, end, to));
+}
+
+// Splits all chars into the intervals that no given range starts or ends
+// inside of, so each range covers either all of an interval or none of it
+fn elementary_intervals(ranges: impl Iterator<Item = (char, char)>) -> Vec<(char, char)> {
+    let mut boundaries = BTreeSet::from(['\0']);
+    for (start, end) in ranges {
+        boundaries.insert(start);
+        if let Some(next) = next_char(end) {
+            boundaries.insert(next);
+        }
+    }
+
+    let boundaries: Vec<char> = boundaries.into_iter().collect();
+    boundaries
+        .iter()
+        .enumerate()
+        .map(|(i, start)| {
+            let end = match boundaries.get(i + 1) {
+                Some(next) => previous_char(*next),
+                None => char::MAX,
+            };
+            (*start, end)
+        })
+        .collect()
+}
+
+fn next_char(c: char) -> Option<char> {
+    match c {
+        '\u{D7FF}' => Some('\u{E000}'),
+        char::MAX => None,
+        c => char::from_u32(c as u32 + 1),
+    }
+}
+
+fn previous_char(c: char) -> char {
+    match c {
+        '\u{E000}' => '\u{D7FF}',
+        c => char::from_u32(c as u32 - 1).unwrap(),
+    }
+}
+
+fn describe_range(start: char, end: char) -> String {
+    if start == end {
+        start.escape_debug().to_string()
+    } else {
+        format!("{}-{}", start.escape_debug(), end.escape_debug())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ch02::lexical_analyzer::ex_2_6::Operator;
+    use crate::ch03::regex::parser::RegexParser;
+    use rstest::rstest;
+
+    fn dfa(pattern: &str) -> Dfa {
+        Dfa::from_nfa(&Nfa::from_regex(
+            &RegexParser::new(pattern).parse().unwrap(),
+        ))
+    }
+
+    #[rstest]
+    fn test_subset_construction_figure_3_36() {
+        let nfa = Nfa::from_regex(&RegexParser::new("(a|b)*abb").parse().unwrap());
+        let (dfa, dstates) = subset_construction(&nfa);
+
+        let sets: Vec<Vec<StateId>> = dstates
+            .into_iter()
+            .map(|s| s.into_iter().collect())
+            .collect();
+        assert_eq!(
+            sets,
+            vec![
+                vec![0, 1, 2, 4, 7],
+                vec![1, 2, 3, 4, 6, 7, 8],
+                vec![1, 2, 4, 5, 6, 7],
+                vec![1, 2, 4, 5, 6, 7, 9],
+                vec![1, 2, 4, 5, 6, 7, 10],
+            ]
+        );
+        assert_eq!(
+            dfa.to_string(),
+            "      a  b\n\
+             >  0  1  2\n   \
+                1  1  3\n   \
+                2  1  2\n   \
+                3  1  4\n\
+             *  4  1  2\n"
+        );
+    }
+
+    #[rstest]
+    fn test_minimize_example_3_40() {
+        let minimal = dfa("(a|b)*abb").minimize();
+
+        assert_eq!(
+            minimal.to_string(),
+            "      a  b\n\
+             >  0  1  0\n   \
+                1  1  2\n   \
+                2  1  3\n\
+             *  3  1  0\n"
+        );
+    }
+
+    #[rstest]
+    #[case("(a|b)*abb", 4)]
+    #[case("a*", 1)]
+    #[case("(a|b)*", 1)]
+    #[case("a|b|c", 2)]
+    #[case("[a-z_][a-z0-9_]*", 2)]
+    #[case("(ab|ac)*", 2)]
+    #[case("x*y|xy*", 5)]
+    fn test_minimize_state_count(#[case] pattern: &str, #[case] states: usize) {
+        let original = dfa(pattern);
+        let minimal = original.minimize();
+
+        assert_eq!(minimal.state_count(), states);
+        assert!(minimal.is_equivalent(&original));
+        assert_eq!(minimal.minimize(), minimal);
+    }
+
+    #[rstest]
+    #[case("(a|b)*", "(a*b*)*", None)]
+    #[case("a(b|c)", "ab|ac", None)]
+    #[case("[0-9]+", "[0-4]+|[5-9][0-9]*|[0-4]+[5-9][0-9]*", None)]
+    #[case("(a|b)*abb", "(a|b)*ab", Some("ab"))]
+    #[case("a*", "a+", Some(""))]
+    #[case("[a-z]", "[a-y]", Some("z"))]
+    fn test_counterexample(
+        #[case] left: &str,
+        #[case] right: &str,
+        #[case] expected: Option<&str>,
+    ) {
+        let counterexample = dfa(left).counterexample(&dfa(right));
+
+        assert_eq!(counterexample.as_deref(), expected);
+        if let Some(input) = counterexample {
+            assert_ne!(dfa(left).matches(&input), dfa(right).matches(&input));
+        }
+    }
+
+    #[rstest]
+    #[case("abb", true)]
+    #[case("aabb", true)]
+    #[case("abba", false)]
+    #[case("", false)]
+    fn test_dfa_matches(#[case] input: &str, #[case] expected: bool) {
+        assert_eq!(dfa("(a|b)*abb").matches(input), expected);
+        assert_eq!(dfa("(a|b)*abb").minimize().matches(input), expected);
+    }
+
+    // The lexer tries its relational operators longest first. As a language
+    // they form a 4-state minimal DFA: "<" and ">" land in the same
+    // accepting state, and so do "=" and "!" in the same non-accepting one.
+    // Telling the operators apart takes one accepting state per token, which
+    // is what a lexer generator's DFA adds on top.
+    #[rstest]
+    fn test_relational_operators_minimal_dfa() {
+        let relops: Vec<&str> = Operator::defaults()
+            .into_iter()
+            .filter(|(_, operator)| matches!(operator, Operator::Logical(_)))
+            .map(|(lexeme, _)| lexeme)
+            .collect();
+        let pattern = relops.join("|");
+        let minimal = dfa(&pattern).minimize();
+
+        assert!(minimal.is_equivalent(&dfa("<|<=|==|!=|>|>=")));
+        assert_eq!(
+            minimal.to_string(),
+            "      !  <  =  >\n\
+             >  0  1  2  1  2\n   \
+                1  -  -  3  -\n\
+             *  2  -  -  3  -\n\
+             *  3  -  -  -  -\n"
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+
+use super::dfa::{determinize, label_ranges, Dfa};
+use super::nfa::Label;
+use crate::ch03::regex::ast::Regex;
+
+// A position is the number of a leaf of the syntax tree, counting from 1 in
+// left-to-right order, as in section 3.9.1
+pub type Position = usize;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Node {
+    Empty,
+    Leaf(Position),
+    Concat(Box<AnnotatedNode>, Box<AnnotatedNode>),
+    Union(Box<AnnotatedNode>, Box<AnnotatedNode>),
+    Star(Box<AnnotatedNode>),
+    Plus(Box<AnnotatedNode>),
+    Optional(Box<AnnotatedNode>),
+}
+
+// A syntax tree node along with the functions of figure 3.58
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AnnotatedNode {
+    pub node: Node,
+    pub nullable: bool,
+    pub firstpos: BTreeSet<Position>,
+    pub lastpos: BTreeSet<Position>,
+}
+
+// The augmented regex (r)# of section 3.9.5, annotated with nullable,
+// firstpos and lastpos on every node and with followpos for every position.
+// The endmarker # is the last position.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct AugmentedRegex {
+    pub root: AnnotatedNode,
+    symbols: Vec<Label>,
+    followpos: Vec<BTreeSet<Position>>,
+}
+
+impl AugmentedRegex {
+    pub fn new(regex: &Regex) -> Self {
+        let mut augmented = AugmentedRegex {
+            root: AnnotatedNode {
+                node: Node::Empty,
+                nullable: true,
+                firstpos: BTreeSet::new(),
+                lastpos: BTreeSet::new(),
+            },
+            symbols: Vec::new(),
+            followpos: Vec::new(),
+        };
+        let tree = augmented.annotate(regex);
+        let end_marker = augmented.add_position(None);
+        let end_marker = AnnotatedNode {
+            node: Node::Leaf(end_marker),
+            nullable: false,
+            firstpos: BTreeSet::from([end_marker]),
+            lastpos: BTreeSet::from([end_marker]),
+        };
+        augmented.root = augmented.concat(tree, end_marker);
+        augmented
+    }
+
+    pub fn end_marker(&self) -> Position {
+        self.followpos.len()
+    }
+
+    // The char or class at a position; None for the endmarker
+    pub fn symbol(&self, position: Position) -> Option<&Label> {
+        self.symbols.get(position - 1)
+    }
+
+    pub fn followpos(&self, position: Position) -> &BTreeSet<Position> {
+        &self.followpos[position - 1]
+    }
+
+    // Algorithm 3.36: the start state is firstpos of the root and the state
+    // reached from S on a is the union of followpos(p) over the positions p
+    // in S whose symbol matches a. States containing the endmarker accept.
+    pub fn to_dfa(&self) -> Dfa {
+        self.to_dfa_with_states().0
+    }
+
+    // Also returns the set of positions behind each DFA state
+    pub fn to_dfa_with_states(&self) -> (Dfa, Vec<BTreeSet<Position>>) {
+        let end_marker = self.end_marker();
+        determinize(
+            self.root.firstpos.clone(),
+            self.symbols.iter().flat_map(label_ranges),
+            |positions, c| {
+                positions
+                    .iter()
+                    .filter(|p| self.symbol(**p).is_some_and(|label| label.matches(c)))
+                    .flat_map(|p| self.followpos(*p).iter().copied())
+                    .collect()
+            },
+            |positions| positions.contains(&end_marker),
+        )
+    }
+
+    fn add_position(&mut self, symbol: Option<Label>) -> Position {
+        if let Some(symbol) = symbol {
+            self.symbols.push(symbol);
+        }
+        self.followpos.push(BTreeSet::new());
+        self.followpos.len()
+    }
+
+    // Figure 3.58, filling in followpos by the two rules of section 3.9.4
+    // as each concatenation and star node is built
+    fn annotate(&mut self, regex: &Regex) -> AnnotatedNode {
+        match regex {
+            Regex::Empty => AnnotatedNode {
+                node: Node::Empty,
+                nullable: true,
+                firstpos: BTreeSet::new(),
+                lastpos: BTreeSet::new(),
+            },
+            Regex::Char(_) | Regex::Class(_) => {
+                let label = match regex {
+                    Regex::Char(c) => Label::Char(*c),
+                    Regex::Class(class) => Label::Class(class.clone()),
+                    _ => unreachable!(),
+                };
+                let position = self.add_position(Some(label));
+                AnnotatedNode {
+                    node: Node::Leaf(position),
+                    nullable: false,
+                    firstpos: BTreeSet::from([position]),
+                    lastpos: BTreeSet::from([position]),
+                }
+            }
+            Regex::Concat(left, right) => {
+                let left = self.annotate(left);
+                let right = self.annotate(right);
+                self.concat(left, right)
+            }
+            Regex::Union(left, right) => {
+                let left = self.annotate(left);
+                let right = self.annotate(right);
+                AnnotatedNode {
+                    nullable: left.nullable || right.nullable,
+                    firstpos: &left.firstpos | &right.firstpos,
+                    lastpos: &left.lastpos | &right.lastpos,
+                    node: Node::Union(Box::new(left), Box::new(right)),
+                }
+            }
+            Regex::Star(inner) | Regex::Plus(inner) => {
+                let inner = self.annotate(inner);
+                // Rule 2: whatever ends an iteration can be followed by
+                // whatever starts the next one
+                for position in inner.lastpos.iter() {
+                    self.followpos[position - 1].extend(inner.firstpos.iter().copied());
+                }
+                let is_star = matches!(regex, Regex::Star(_));
+                AnnotatedNode {
+                    nullable: is_star || inner.nullable,
+                    firstpos: inner.firstpos.clone(),
+                    lastpos: inner.lastpos.clone(),
+                    node: if is_star {
+                        Node::Star(Box::new(inner))
+                    } else {
+                        Node::Plus(Box::new(inner))
+                    },
+                }
+            }
+            Regex::Optional(inner) => {
+                let inner = self.annotate(inner);
+                AnnotatedNode {
+                    nullable: true,
+                    firstpos: inner.firstpos.clone(),
+                    lastpos: inner.lastpos.clone(),
+                    node: Node::Optional(Box::new(inner)),
+                }
+            }
+        }
+    }
+
+    fn concat(&mut self, left: AnnotatedNode, right: AnnotatedNode) -> AnnotatedNode {
+        // Rule 1: whatever ends left can be followed by whatever starts right
+        for position in left.lastpos.iter() {
+            self.followpos[position - 1].extend(right.firstpos.iter().copied());
+        }
+        let firstpos = if left.nullable {
+            &left.firstpos | &right.firstpos
+        } else {
+            left.firstpos.clone()
+        };
+        let lastpos = if right.nullable {
+            &left.lastpos | &right.lastpos
+        } else {
+            right.lastpos.clone()
+        };
+        AnnotatedNode {
+            nullable: left.nullable && right.nullable,
+            firstpos,
+            lastpos,
+            node: Node::Concat(Box::new(left), Box::new(right)),
+        }
+    }
+}
+
+// Prints the followpos table in the layout of figure 3.60
+impl Display for AugmentedRegex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "position  symbol  followpos")?;
+        for position in 1..=self.end_marker() {
+            let symbol = match self.symbol(position) {
+                Some(Label::Char(c)) => c.escape_debug().to_string(),
+                Some(label) => label.to_string(),
+                None => "#".to_string(),
+            };
+            let followpos: Vec<String> = self
+                .followpos(position)
+                .iter()
+                .map(|p| p.to_string())
+                .collect();
+            writeln!(
+                f,
+                "{:<8}  {:<6}  {{{}}}",
+                position,
+                symbol,
+                followpos.join(", ")
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::nfa::Nfa;
+    use super::*;
+    use crate::ch03::regex::parser::RegexParser;
+    use rstest::rstest;
+
+    fn augmented(pattern: &str) -> AugmentedRegex {
+        AugmentedRegex::new(&RegexParser::new(pattern).parse().unwrap())
+    }
+
+    #[rstest]
+    fn test_followpos_figure_3_60() {
+        let augmented = augmented("(a|b)*abb");
+
+        assert_eq!(augmented.root.firstpos, BTreeSet::from([1, 2, 3]));
+        assert_eq!(augmented.root.lastpos, BTreeSet::from([6]));
+        assert!(!augmented.root.nullable);
+        assert_eq!(
+            augmented.to_string(),
+            "position  symbol  followpos\n\
+             1         a       {1, 2, 3}\n\
+             2         b       {1, 2, 3}\n\
+             3         a       {4}\n\
+             4         b       {5}\n\
+             5         b       {6}\n\
+             6         #       {}\n"
+        );
+    }
+
+    #[rstest]
+    fn test_annotated_star_node() {
+        let augmented = augmented("(a|b)*abb");
+
+        // The root is ((((a|b)*a)b)b)#, so its leftmost descendant that is not
+        // a concatenation is the star node of figure 3.59
+        let mut node = &augmented.root;
+        while let Node::Concat(left, _) = &node.node {
+            node = left;
+        }
+        assert!(matches!(node.node, Node::Star(_)));
+        assert!(node.nullable);
+        assert_eq!(node.firstpos, BTreeSet::from([1, 2]));
+        assert_eq!(node.lastpos, BTreeSet::from([1, 2]));
+    }
+
+    #[rstest]
+    fn test_direct_dfa_figure_3_63() {
+        let (dfa, states) = augmented("(a|b)*abb").to_dfa_with_states();
+
+        let states: Vec<Vec<Position>> = states
+            .into_iter()
+            .map(|s| s.into_iter().collect())
+            .collect();
+        assert_eq!(
+            states,
+            vec![
+                vec![1, 2, 3],
+                vec![1, 2, 3, 4],
+                vec![1, 2, 3, 5],
+                vec![1, 2, 3, 6]
+            ]
+        );
+        assert_eq!(dfa.state_count(), 4);
+        assert!(dfa.is_accepting(3));
+    }
+
+    #[rstest]
+    #[case("a?b+", 2, vec![(1, vec![2]), (2, vec![2, 3])])]
+    #[case("()", 0, vec![(1, vec![])])]
+    #[case("(a|())c", 2, vec![(1, vec![2]), (2, vec![3])])]
+    fn test_followpos_shorthands(
+        #[case] pattern: &str,
+        #[case] positions: usize,
+        #[case] followpos: Vec<(Position, Vec<Position>)>,
+    ) {
+        let augmented = augmented(pattern);
+
+        assert_eq!(augmented.end_marker(), positions + 1);
+        for (position, expected) in followpos {
+            let expected: BTreeSet<Position> = expected.into_iter().collect();
+            assert_eq!(augmented.followpos(position), &expected);
+        }
+    }
+
+    #[rstest]
+    #[case("[A-Za-z_][A-Za-z0-9_]*")]
+    #[case(
+        "[0-9][0-9_]*(\\.[0-9_]*)?([eE][+\\-]?[0-9][0-9_]*)?\
+         |\\.[0-9][0-9_]*([eE][+\\-]?[0-9][0-9_]*)?"
+    )]
+    #[case("<|<=|==|!=|>|>=")]
+    #[case("0[xX][0-9a-fA-F_]+|0[oO][0-7_]+|0[bB][01_]+")]
+    #[case("\"([^\"\\\\\\n]|\\\\.)*\"")]
+    #[case("//[^\\n]*|/\\*([^*]|\\*+[^*/])*\\*+/")]
+    #[case("(a|b)*abb")]
+    #[case("(a*)*b?|()")]
+    fn test_direct_dfa_equivalent_to_subset_construction(#[case] pattern: &str) {
+        let regex = RegexParser::new(pattern).parse().unwrap();
+        let direct = AugmentedRegex::new(&regex).to_dfa();
+        let subset = Dfa::from_nfa(&Nfa::from_regex(&regex));
+
+        assert_eq!(direct.counterexample(&subset), None);
+        assert_eq!(direct.minimize(), subset.minimize());
+        assert!(direct.state_count() <= subset.state_count());
+    }
+}
@@ -0,0 +1,3 @@
+pub mod dfa;
+pub mod direct;
+pub mod nfa;
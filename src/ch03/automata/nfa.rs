@@ -0,0 +1,338 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+
+use crate::ch03::regex::ast::{CharClass, Regex};
+
+pub type StateId = usize;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub enum Label {
+    Epsilon,
+    Char(char),
+    Class(CharClass),
+}
+
+impl Label {
+    pub fn matches(&self, c: char) -> bool {
+        match self {
+            Label::Epsilon => false,
+            Label::Char(expected) => *expected == c,
+            Label::Class(class) => class.contains(c),
+        }
+    }
+}
+
+impl Display for Label {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Label::Epsilon => write!(f, "ε"),
+            Label::Char(c) => write!(f, "{:?}", c),
+            Label::Class(class) => write!(f, "{}", class),
+        }
+    }
+}
+
+// An ε-NFA with a single start and a single accepting state, as built by
+// Thompson's construction. States are numbered in the order they are created.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Nfa {
+    transitions: Vec<Vec<(Label, StateId)>>,
+    start: StateId,
+    accept: StateId,
+}
+
+impl Nfa {
+    // Algorithm 3.23 (McNaughton-Yamada-Thompson). Each case adds the states
+    // and edges of figures 3.40-3.43; concatenation reuses the accepting
+    // state of N(s) as the start of N(t) rather than adding an ε edge:
+    // ε     : start -ε-> f
+    // a     : start -a-> f
+    // s|t   : start -ε-> N(s) -ε-> f, start -ε-> N(t) -ε-> f
+    // st    : start N(s) N(t)
+    // s*    : start -ε-> N(s) -ε-> f, start -ε-> f, N(s).accept -ε-> N(s).start
+    // s+ and s? are s* without the start -ε-> f and the loop-back edges
+    // respectively
+    pub fn from_regex(regex: &Regex) -> Self {
+        let mut nfa = Nfa {
+            transitions: vec![Vec::new()],
+            start: 0,
+            accept: 0,
+        };
+        nfa.accept = nfa.compile(regex, 0);
+        nfa
+    }
+
+    fn compile(&mut self, regex: &Regex, start: StateId) -> StateId {
+        match regex {
+            Regex::Empty => {
+                let accept = self.add_state();
+                self.add_transition(start, Label::Epsilon, accept);
+                accept
+            }
+            Regex::Char(c) => {
+                let accept = self.add_state();
+                self.add_transition(start, Label::Char(*c), accept);
+                accept
+            }
+            Regex::Class(class) => {
+                let accept = self.add_state();
+                self.add_transition(start, Label::Class(class.clone()), accept);
+                accept
+            }
+            Regex::Concat(left, right) => {
+                let middle = self.compile(left, start);
+                self.compile(right, middle)
+            }
+            Regex::Union(left, right) => {
+                let left_start = self.add_state();
+                let left_accept = self.compile(left, left_start);
+                let right_start = self.add_state();
+                let right_accept = self.compile(right, right_start);
+                let accept = self.add_state();
+                self.add_transition(start, Label::Epsilon, left_start);
+                self.add_transition(start, Label::Epsilon, right_start);
+                self.add_transition(left_accept, Label::Epsilon, accept);
+                self.add_transition(right_accept, Label::Epsilon, accept);
+                accept
+            }
+            Regex::Star(inner) | Regex::Plus(inner) | Regex::Optional(inner) => {
+                let inner_start = self.add_state();
+                let inner_accept = self.compile(inner, inner_start);
+                let accept = self.add_state();
+                self.add_transition(start, Label::Epsilon, inner_start);
+                if !matches!(regex, Regex::Plus(_)) {
+                    self.add_transition(start, Label::Epsilon, accept);
+                }
+                if !matches!(regex, Regex::Optional(_)) {
+                    self.add_transition(inner_accept, Label::Epsilon, inner_start);
+                }
+                self.add_transition(inner_accept, Label::Epsilon, accept);
+                accept
+            }
+        }
+    }
+
+    fn add_state(&mut self) -> StateId {
+        self.transitions.push(Vec::new());
+        self.transitions.len() - 1
+    }
+
+    fn add_transition(&mut self, from: StateId, label: Label, to: StateId) {
+        self.transitions[from].push((label, to));
+    }
+
+    pub fn start(&self) -> StateId {
+        self.start
+    }
+
+    pub fn accept(&self) -> StateId {
+        self.accept
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn transitions(&self, state: StateId) -> &[(Label, StateId)] {
+        &self.transitions[state]
+    }
+
+    // Every state reachable from states through ε edges alone, states included
+    pub fn epsilon_closure(&self, states: &BTreeSet<StateId>) -> BTreeSet<StateId> {
+        let mut closure = states.clone();
+        let mut stack: Vec<StateId> = states.iter().copied().collect();
+        while let Some(state) = stack.pop() {
+            for (label, to) in self.transitions[state].iter() {
+                if *label == Label::Epsilon && closure.insert(*to) {
+                    stack.push(*to);
+                }
+            }
+        }
+        closure
+    }
+
+    // The states reachable from states through one edge matching c
+    pub fn move_on(&self, states: &BTreeSet<StateId>, c: char) -> BTreeSet<StateId> {
+        states
+            .iter()
+            .flat_map(|state| self.transitions[*state].iter())
+            .filter(|(label, _)| label.matches(c))
+            .map(|(_, to)| *to)
+            .collect()
+    }
+
+    pub fn start_closure(&self) -> BTreeSet<StateId> {
+        self.epsilon_closure(&BTreeSet::from([self.start]))
+    }
+
+    // Algorithm 3.22: simulates the NFA on the whole input
+    pub fn matches(&self, input: &str) -> bool {
+        let mut states = self.start_closure();
+        for c in input.chars() {
+            states = self.epsilon_closure(&self.move_on(&states, c));
+            if states.is_empty() {
+                return false;
+            }
+        }
+        states.contains(&self.accept)
+    }
+
+    // Length in bytes of the longest prefix of input the NFA accepts, which
+    // is what a lexer reading input would take as the next lexeme
+    pub fn longest_match(&self, input: &str) -> Option<usize> {
+        let mut states = self.start_closure();
+        let mut longest = states.contains(&self.accept).then_some(0);
+        for (offset, c) in input.char_indices() {
+            states = self.epsilon_closure(&self.move_on(&states, c));
+            if states.is_empty() {
+                break;
+            }
+            if states.contains(&self.accept) {
+                longest = Some(offset + c.len_utf8());
+            }
+        }
+        longest
+    }
+}
+
+impl Display for Nfa {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "start: {}, accept: {}", self.start, self.accept)?;
+        for (from, transitions) in self.transitions.iter().enumerate() {
+            for (label, to) in transitions.iter() {
+                writeln!(f, "{} -{}-> {}", from, label, to)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ch02::lexical_analyzer::ex_2_6::{Lexer, LogicalOperator, Token};
+    use crate::ch03::regex::parser::RegexParser;
+    use rstest::rstest;
+
+    fn nfa(pattern: &str) -> Nfa {
+        Nfa::from_regex(&RegexParser::new(pattern).parse().unwrap())
+    }
+
+    #[rstest]
+    fn test_thompson_figure_3_34() {
+        let nfa = nfa("(a|b)*abb");
+        let mut edges = Vec::new();
+        for from in 0..nfa.state_count() {
+            for (label, to) in nfa.transitions(from) {
+                edges.push((from, label.to_string(), *to));
+            }
+        }
+
+        let epsilon = |from, to| (from, "ε".to_string(), to);
+        let char = |from, c: char, to| (from, format!("{:?}", c), to);
+        assert_eq!(nfa.state_count(), 11);
+        assert_eq!((nfa.start(), nfa.accept()), (0, 10));
+        assert_eq!(
+            edges,
+            vec![
+                epsilon(0, 1),
+                epsilon(0, 7),
+                epsilon(1, 2),
+                epsilon(1, 4),
+                char(2, 'a', 3),
+                epsilon(3, 6),
+                char(4, 'b', 5),
+                epsilon(5, 6),
+                epsilon(6, 1),
+                epsilon(6, 7),
+                char(7, 'a', 8),
+                char(8, 'b', 9),
+                char(9, 'b', 10),
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case("(a|b)*abb", "abb", true)]
+    #[case("(a|b)*abb", "babaabb", true)]
+    #[case("(a|b)*abb", "abab", false)]
+    #[case("a+", "", false)]
+    #[case("a+", "aaa", true)]
+    #[case("ab?c", "ac", true)]
+    #[case("ab?c", "abbc", false)]
+    #[case("()", "", true)]
+    #[case("[^0-9]x", "ax", true)]
+    #[case("[^0-9]x", "5x", false)]
+    #[case(".*", "any\u{e9}thing", true)]
+    #[case(".", "\n", false)]
+    #[case("(a*)*", "aa", true)]
+    fn test_nfa_matches(#[case] pattern: &str, #[case] input: &str, #[case] expected: bool) {
+        assert_eq!(nfa(pattern).matches(input), expected);
+    }
+
+    // The token patterns ex_2_6::Lexer recognizes, restricted to ASCII
+    // identifiers and decimal numbers
+    const IDENTIFIER: &str = "[A-Za-z_][A-Za-z0-9_]*";
+    const NUMBER: &str = "[0-9][0-9_]*(\\.[0-9_]*)?([eE][+\\-]?[0-9][0-9_]*)?\
+                          |\\.[0-9][0-9_]*([eE][+\\-]?[0-9][0-9_]*)?";
+    const RELOP: &str = "<|<=|==|!=|>|>=";
+
+    fn first_lexeme_length(input: &str) -> (Token<'_>, usize) {
+        let spanned = Lexer::new(input).next().unwrap();
+        (spanned.token, spanned.span.end.offset)
+    }
+
+    #[rstest]
+    #[case("counter_1 + 2")]
+    #[case("_x9")]
+    #[case("true")]
+    #[case("x.y")]
+    fn test_nfa_identifier_matches_lexer(#[case] input: &str) {
+        let (token, length) = first_lexeme_length(input);
+
+        assert!(matches!(token, Token::Word(_, _)));
+        assert_eq!(nfa(IDENTIFIER).longest_match(input), Some(length));
+    }
+
+    #[rstest]
+    #[case("42;")]
+    #[case("3.14159")]
+    #[case("2.")]
+    #[case(".5.")]
+    #[case("1e10")]
+    #[case("6.02E+23x")]
+    #[case("1e")]
+    #[case("2else")]
+    #[case("1_000.5_5e-1_0")]
+    #[case("1.2.3")]
+    fn test_nfa_number_matches_lexer(#[case] input: &str) {
+        let (token, length) = first_lexeme_length(input);
+
+        assert!(matches!(token, Token::Number(_, _)));
+        assert_eq!(nfa(NUMBER).longest_match(input), Some(length));
+    }
+
+    #[rstest]
+    #[case("<", LogicalOperator::Less)]
+    #[case("<=5", LogicalOperator::LessOrEqual)]
+    #[case("==", LogicalOperator::Equal)]
+    #[case("!=x", LogicalOperator::Different)]
+    #[case("> =", LogicalOperator::Greater)]
+    #[case(">==", LogicalOperator::GreaterOrEqual)]
+    fn test_nfa_relop_matches_lexer(#[case] input: &str, #[case] expected: LogicalOperator) {
+        let (token, length) = first_lexeme_length(input);
+
+        assert!(matches!(token, Token::LogicalOperator(tag, _) if tag == expected));
+        assert_eq!(nfa(RELOP).longest_match(input), Some(length));
+    }
+
+    #[rstest]
+    #[case(IDENTIFIER, "9lives")]
+    #[case(NUMBER, "e5")]
+    #[case(NUMBER, ".e5")]
+    #[case(RELOP, "=")]
+    #[case(RELOP, "!")]
+    fn test_nfa_rejects_what_lexer_rejects(#[case] pattern: &str, #[case] input: &str) {
+        assert_eq!(nfa(pattern).longest_match(input), None);
+    }
+}
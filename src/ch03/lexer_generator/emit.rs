@@ -0,0 +1,337 @@
+use std::fmt::Write;
+
+use super::scanner::ScannerTable;
+use crate::ch02::recursive_descent_parser::diagnostic::Diagnostic;
+use crate::ch03::regex::ast::Regex;
+
+// Writes the scanner for a list of rules as a standalone Rust module, for
+// instance from a build.rs into OUT_DIR. Where LexerSpec runs an action on
+// each lexeme, here every rule names the token kind it produces, and the
+// module only depends on std:
+// - pub enum TokenKind, one variant per kind in order of first use
+// - pub struct Lexeme<'a> { kind, text, offset, value }, offset being in
+//   bytes and value being what the action of the rule made of the text
+// - pub struct Scanner<'a>, an iterator over Result<Lexeme<'a>, usize>
+//   whose errors are the offsets of chars no rule matches
+// - the code added with code(), like the user subroutines of a lex program
+// The DFA is emitted as tables, so rule priority and longest match work
+// the same as with a Scanner.
+pub struct ScannerEmitter {
+    rules: Vec<EmittedRule>,
+    kinds: Vec<String>,
+    code: String,
+}
+
+struct EmittedRule {
+    pattern: String,
+    kind: Option<String>,
+    action: Option<String>,
+}
+
+impl Default for ScannerEmitter {
+    fn default() -> Self {
+        ScannerEmitter::new()
+    }
+}
+
+impl ScannerEmitter {
+    pub fn new() -> Self {
+        ScannerEmitter {
+            rules: Vec::new(),
+            kinds: Vec::new(),
+            code: String::new(),
+        }
+    }
+
+    pub fn rule(self, pattern: &str, kind: &str) -> Self {
+        self.push_rule(pattern, Some(kind), None)
+    }
+
+    // A rule whose lexemes get their value from action, a function
+    // fn(&str) -> Result<Value, E> in the module's code, where TokenKind:
+    // From<E>. A lexeme the action fails on has no value and takes the kind
+    // its error converts to, so that malformed literals get kinds of their
+    // own. The code must then define Value; without actions it is empty.
+    pub fn rule_with(self, pattern: &str, kind: &str, action: &str) -> Self {
+        self.push_rule(pattern, Some(kind), Some(action))
+    }
+
+    pub fn literal(self, lexeme: &str, kind: &str) -> Self {
+        self.rule(&Regex::literal(lexeme).to_string(), kind)
+    }
+
+    pub fn skip(self, pattern: &str) -> Self {
+        self.push_rule(pattern, None, None)
+    }
+
+    // A kind no rule produces, such as one the errors of actions convert to
+    pub fn kind(mut self, kind: &str) -> Self {
+        self.add_kind(kind);
+        self
+    }
+
+    // Rust items copied at the end of the module, such as actions
+    pub fn code(mut self, code: &str) -> Self {
+        self.code.push('\n');
+        self.code.push_str(code);
+        self
+    }
+
+    pub fn kinds(&self) -> Vec<&str> {
+        self.kinds.iter().map(String::as_str).collect()
+    }
+
+    fn push_rule(mut self, pattern: &str, kind: Option<&str>, action: Option<&str>) -> Self {
+        if let Some(kind) = kind {
+            self.add_kind(kind);
+        }
+        self.rules.push(EmittedRule {
+            pattern: pattern.to_string(),
+            kind: kind.map(str::to_string),
+            action: action.map(str::to_string),
+        });
+        self
+    }
+
+    fn add_kind(&mut self, kind: &str) {
+        if !self.kinds.iter().any(|known| known == kind) {
+            self.kinds.push(kind.to_string());
+        }
+    }
+
+    pub fn emit(&self) -> Result<String, Diagnostic<char>> {
+        let table = ScannerTable::parse(self.rules.iter().map(|rule| rule.pattern.as_str()))?;
+        let dfa = table.dfa();
+
+        let mut source = String::new();
+        source.push_str(HEADER);
+
+        source
+            .push_str("#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]\npub enum TokenKind {\n");
+        for kind in self.kinds() {
+            writeln!(source, "    {},", kind).unwrap();
+        }
+        source.push_str("}\n\n");
+        if self.rules.iter().all(|rule| rule.action.is_none()) {
+            source.push_str(NO_VALUE);
+        }
+        source.push_str(TYPES);
+
+        writeln!(
+            source,
+            "static TRANSITIONS: [&[(char, char, usize)]; {}] = [",
+            dfa.state_count()
+        )
+        .unwrap();
+        for state in 0..dfa.state_count() {
+            let ranges: Vec<String> = dfa
+                .transitions(state)
+                .iter()
+                .map(|(start, end, to)| format!("({:?}, {:?}, {})", start, end, to))
+                .collect();
+            writeln!(source, "    &[{}],", ranges.join(", ")).unwrap();
+        }
+        source.push_str("];\n\n");
+
+        writeln!(source, "static ACCEPT: [Accept; {}] = [", dfa.state_count()).unwrap();
+        for state in 0..dfa.state_count() {
+            let accept = match table.rule(state).map(|rule| &self.rules[rule]) {
+                None => "Accept::No".to_string(),
+                Some(EmittedRule { kind: None, .. }) => "Accept::Skip".to_string(),
+                Some(EmittedRule {
+                    kind: Some(kind),
+                    action: None,
+                    ..
+                }) => format!("Accept::Token(TokenKind::{}, None)", kind),
+                Some(EmittedRule {
+                    kind: Some(kind),
+                    action: Some(action),
+                    ..
+                }) => format!(
+                    "Accept::Token(TokenKind::{}, Some(|text: &str| {}(text).map_err(TokenKind::from)))",
+                    kind, action
+                ),
+            };
+            writeln!(source, "    {},", accept).unwrap();
+        }
+        source.push_str("];\n\n");
+
+        writeln!(source, "const START: usize = {};\n", dfa.start()).unwrap();
+        source.push_str(SCANNER);
+        source.push_str(&self.code);
+        Ok(source)
+    }
+}
+
+const HEADER: &str = "\
+// @generated by ch03::lexer_generator::emit::ScannerEmitter; do not edit.
+
+use std::cmp::Ordering;
+
+";
+
+const NO_VALUE: &str = "\
+// No rule has an action, so no lexeme has a value
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Value {}
+
+";
+
+const TYPES: &str = "\
+#[derive(Debug, PartialEq, Clone)]
+pub struct Lexeme<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub offset: usize,
+    pub value: Option<Value>,
+}
+
+type Action = fn(&str) -> Result<Value, TokenKind>;
+
+#[derive(Debug, Clone, Copy)]
+enum Accept {
+    No,
+    Skip,
+    Token(TokenKind, Option<Action>),
+}
+
+// Sorted, disjoint char ranges leaving each state; a char none of them
+// covers leads to the dead state
+";
+
+const SCANNER: &str = "\
+pub struct Scanner<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Scanner { input, offset: 0 }
+    }
+
+    // Where the longest nonempty lexeme at the current offset ends and what
+    // the state it ends in accepts
+    fn longest_match(&self) -> Option<(usize, Accept)> {
+        let mut state = START;
+        let mut longest = None;
+        for (index, c) in self.input[self.offset..].char_indices() {
+            match next_state(state, c) {
+                Some(next) => state = next,
+                None => break,
+            }
+            if !matches!(ACCEPT[state], Accept::No) {
+                longest = Some((self.offset + index + c.len_utf8(), ACCEPT[state]));
+            }
+        }
+        longest
+    }
+}
+
+impl<'a> Iterator for Scanner<'a> {
+    type Item = Result<Lexeme<'a>, usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.offset < self.input.len() {
+            let start = self.offset;
+            let Some((end, accept)) = self.longest_match() else {
+                let c = self.input[start..].chars().next().unwrap();
+                self.offset += c.len_utf8();
+                return Some(Err(start));
+            };
+            self.offset = end;
+            if let Accept::Token(kind, action) = accept {
+                let text = &self.input[start..end];
+                let (kind, value) = match action.map(|action| action(text)) {
+                    None => (kind, None),
+                    Some(Ok(value)) => (kind, Some(value)),
+                    Some(Err(kind)) => (kind, None),
+                };
+                return Some(Ok(Lexeme {
+                    kind,
+                    text,
+                    offset: start,
+                    value,
+                }));
+            }
+        }
+        None
+    }
+}
+
+fn next_state(state: usize, c: char) -> Option<usize> {
+    let transitions = TRANSITIONS[state];
+    transitions
+        .binary_search_by(|(start, end, _)| {
+            if *end < c {
+                Ordering::Less
+            } else if *start > c {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .ok()
+        .map(|index| transitions[index].2)
+}
+";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_emit_figure_3_51() {
+        let source = ScannerEmitter::new()
+            .rule("a", "A")
+            .rule("abb", "Abb")
+            .rule("a*b+", "AStarBPlus")
+            .emit()
+            .unwrap();
+
+        assert!(source.contains("pub enum TokenKind {\n    A,\n    Abb,\n    AStarBPlus,\n}\n"));
+        assert!(source.contains(
+            "static ACCEPT: [Accept; 6] = [\n    \
+             Accept::No,\n    \
+             Accept::Token(TokenKind::A, None),\n    \
+             Accept::Token(TokenKind::AStarBPlus, None),\n    \
+             Accept::No,\n    \
+             Accept::Token(TokenKind::AStarBPlus, None),\n    \
+             Accept::Token(TokenKind::Abb, None),\n];\n"
+        ));
+        assert!(source.contains("    &[('a', 'a', 1), ('b', 'b', 2)],\n"));
+        assert!(source.contains("pub enum Value {}\n"));
+    }
+
+    #[rstest]
+    fn test_emit_actions_and_code() {
+        let code = "fn number(text: &str) -> Result<Value, TokenKind> {\n    todo!()\n}\n";
+        let source = ScannerEmitter::new()
+            .rule_with("[0-9]+", "Number", "number")
+            .kind("Overflow")
+            .code(code)
+            .emit()
+            .unwrap();
+
+        assert!(source.contains("pub enum TokenKind {\n    Number,\n    Overflow,\n}\n"));
+        assert!(source.contains(
+            "Accept::Token(TokenKind::Number, Some(|text: &str| number(text).map_err(TokenKind::from)))"
+        ));
+        assert!(!source.contains("pub enum Value"));
+        assert!(source.ends_with(code));
+    }
+
+    #[rstest]
+    fn test_kinds_in_order_of_first_use() {
+        let emitter = ScannerEmitter::new()
+            .skip(" ")
+            .rule("[0-9]+", "Number")
+            .rule("[a-z]+", "Word")
+            .rule("0x[0-9a-f]+", "Number")
+            .kind("Overflow")
+            .kind("Word");
+
+        assert_eq!(emitter.kinds(), vec!["Number", "Word", "Overflow"]);
+    }
+}
@@ -0,0 +1,366 @@
+use crate::ch02::lexical_analyzer::ex_2_6::{LexicalError, NumberValue, Operator, Token, Word};
+
+use super::emit::ScannerEmitter;
+use super::ex_2_6_literals::{
+    binary_number, char_literal, decimal_number, hexadecimal_number, octal_number, string_literal,
+    LiteralError, Value,
+};
+use super::spec::LexerSpec;
+
+// The token set of ch02's ex_2_6::Lexer as lex patterns. This is a parallel
+// implementation: ex_2_6::Lexer::scan still scans by hand, and these
+// patterns are checked against it rather than used by it. They cover less:
+// - identifiers are ASCII only, where the Lexer takes Unicode XID
+// - raw strings are missing, since matching their number of #s is not
+//   regular
+// - comment delimiters and keywords are fixed, where LexerBuilder lets a
+//   language choose them
+// Malformed literals are matched by looser patterns listed after the
+// well-formed ones, so that they only win when nothing well-formed is as
+// long; the last pattern takes any other char.
+const WHITESPACE: &str = "\\s+";
+const LINE_COMMENT: &str = "//[^\\n]*";
+const BLOCK_COMMENT: &str = "/\\*([^*]|\\*+[^*/])*\\*+/";
+const IDENTIFIER: &str = "[A-Za-z_][A-Za-z0-9_]*";
+const DECIMAL: &str = "[0-9][0-9_]*(\\.[0-9_]*)?([eE][+\\-]?[0-9][0-9_]*)?\
+                       |\\.[0-9][0-9_]*([eE][+\\-]?[0-9][0-9_]*)?";
+const HEXADECIMAL: &str = "0[xX][0-9a-fA-F_]+";
+const OCTAL: &str = "0[oO][0-7_]+";
+const BINARY: &str = "0[bB][01_]+";
+// A radix prefix without digits, or with decimal digits the radix has no
+// place for, e.g. 0b12
+const MALFORMED_RADIX: &str = "0[xXoObB][0-9_]*";
+const STRING: &str = "\"([^\"\\\\]|\\\\(.|\\n))*\"";
+const UNTERMINATED_STRING: &str = "\"([^\"\\\\]|\\\\(.|\\n))*\\\\?";
+const CHAR: &str = "'([^'\\\\\\n]|\\\\.)*'";
+const UNTERMINATED_CHAR: &str = "'([^'\\\\\\n]|\\\\.)*\\\\?";
+const UNKNOWN: &str = ".";
+
+// Comments are skipped and words are interned
+pub fn token_spec() -> LexerSpec<Token<'static>> {
+    let mut spec = LexerSpec::new()
+        .skip(WHITESPACE)
+        .skip(LINE_COMMENT)
+        .skip(BLOCK_COMMENT)
+        .literal("true", |lexeme, interner| {
+            Token::Word(Word::True, interner.intern(lexeme))
+        })
+        .literal("false", |lexeme, interner| {
+            Token::Word(Word::False, interner.intern(lexeme))
+        })
+        .rule(IDENTIFIER, |lexeme, interner| {
+            Token::Word(Word::Identifier, interner.intern(lexeme))
+        })
+        .rule(DECIMAL, |lexeme, _| literal(lexeme, decimal_number(lexeme)))
+        .rule(HEXADECIMAL, |lexeme, _| {
+            literal(lexeme, hexadecimal_number(lexeme))
+        })
+        .rule(OCTAL, |lexeme, _| literal(lexeme, octal_number(lexeme)))
+        .rule(BINARY, |lexeme, _| literal(lexeme, binary_number(lexeme)))
+        .rule(MALFORMED_RADIX, |lexeme, _| {
+            Token::Error(LexicalError::MalformedNumber, lexeme.to_string().into())
+        })
+        .rule(STRING, |lexeme, _| literal(lexeme, string_literal(lexeme)))
+        .rule(UNTERMINATED_STRING, |lexeme, _| {
+            Token::Error(LexicalError::UnterminatedString, lexeme.to_string().into())
+        })
+        .rule(CHAR, |lexeme, _| literal(lexeme, char_literal(lexeme)))
+        .rule(UNTERMINATED_CHAR, |lexeme, _| {
+            Token::Error(LexicalError::UnterminatedChar, lexeme.to_string().into())
+        });
+
+    for (lexeme, operator) in Operator::defaults() {
+        spec = spec.literal(lexeme, move |lexeme, _| {
+            operator.token(lexeme.to_string().into())
+        });
+    }
+
+    spec.rule(UNKNOWN, |lexeme, _| {
+        Token::Unknown(lexeme.to_string().into())
+    })
+}
+
+// The same patterns for ScannerEmitter. Literals are decoded by the same
+// functions as in token_spec, which the emitted module gets a copy of, and
+// each way a literal can be malformed has a kind of its own.
+pub fn token_emitter() -> ScannerEmitter {
+    let mut emitter = ScannerEmitter::new()
+        .skip(WHITESPACE)
+        .skip(LINE_COMMENT)
+        .skip(BLOCK_COMMENT)
+        .literal("true", "True")
+        .literal("false", "False")
+        .rule(IDENTIFIER, "Identifier")
+        .rule_with(DECIMAL, "Number", "decimal_number")
+        .rule_with(HEXADECIMAL, "Number", "hexadecimal_number")
+        .rule_with(OCTAL, "Number", "octal_number")
+        .rule_with(BINARY, "Number", "binary_number")
+        .rule(MALFORMED_RADIX, "MalformedNumber")
+        .rule_with(STRING, "String", "string_literal")
+        .rule(UNTERMINATED_STRING, "UnterminatedString")
+        .rule_with(CHAR, "Char", "char_literal")
+        .rule(UNTERMINATED_CHAR, "UnterminatedChar")
+        .kind("IntegerOverflow")
+        .kind("FloatOverflow")
+        .kind("InvalidEscape")
+        .kind("MalformedChar");
+
+    for (lexeme, operator) in Operator::defaults() {
+        emitter = emitter.literal(lexeme, &operator_kind(operator));
+    }
+
+    emitter
+        .rule(UNKNOWN, "Unknown")
+        .code(include_str!("ex_2_6_literals.rs"))
+        .code(ERROR_KINDS)
+}
+
+// The TokenKind variant of an operator, e.g. LessOrEqual or BitwiseAnd
+pub fn operator_kind(operator: Operator) -> String {
+    match operator {
+        Operator::Logical(tag) => format!("{:?}", tag),
+        Operator::Arithmetic(tag) => format!("{:?}", tag),
+        Operator::Connective(tag) => format!("Logical{:?}", tag),
+        Operator::Bitwise(tag) => format!("Bitwise{:?}", tag),
+        Operator::Assign => "Assign".to_string(),
+        Operator::Punctuation(tag) => format!("{:?}", tag),
+    }
+}
+
+const ERROR_KINDS: &str = "\
+impl From<LiteralError> for TokenKind {
+    fn from(error: LiteralError) -> Self {
+        match error {
+            LiteralError::IntegerOverflow => TokenKind::IntegerOverflow,
+            LiteralError::FloatOverflow => TokenKind::FloatOverflow,
+            LiteralError::MalformedNumber => TokenKind::MalformedNumber,
+            LiteralError::InvalidEscape => TokenKind::InvalidEscape,
+            LiteralError::MalformedChar => TokenKind::MalformedChar,
+        }
+    }
+}
+";
+
+fn literal(lexeme: &str, value: Result<Value, LiteralError>) -> Token<'static> {
+    let lexeme = lexeme.to_string().into();
+    match value {
+        Ok(Value::Integer(value)) => Token::Number(NumberValue::Integer(value), lexeme),
+        Ok(Value::Float(value)) => Token::Number(NumberValue::Float(value), lexeme),
+        Ok(Value::String(value)) => Token::StringLiteral(value, lexeme),
+        Ok(Value::Char(value)) => Token::CharLiteral(value, lexeme),
+        Err(error) => Token::Error(lexical_error(error), lexeme),
+    }
+}
+
+fn lexical_error(error: LiteralError) -> LexicalError {
+    match error {
+        LiteralError::IntegerOverflow => LexicalError::IntegerOverflow,
+        LiteralError::FloatOverflow => LexicalError::FloatOverflow,
+        LiteralError::MalformedNumber => LexicalError::MalformedNumber,
+        LiteralError::InvalidEscape => LexicalError::InvalidEscape,
+        LiteralError::MalformedChar => LexicalError::MalformedChar,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::ex_2_6_scanner::{Lexeme, Scanner, TokenKind, Value};
+    use super::super::spec::GeneratedLexer;
+    use super::*;
+    use crate::ch02::lexical_analyzer::ex_2_6::{
+        ArithmeticOperator, BitwiseOperator, Lexer, LogicalOperator,
+    };
+    use crate::ch02::symbol_table::interner::Interner;
+    use rstest::rstest;
+    use std::borrow::Cow;
+
+    fn generated() -> GeneratedLexer<Token<'static>> {
+        token_spec().build().unwrap()
+    }
+
+    fn word(interner: &Interner, tag: Word, name: &str) -> Token<'static> {
+        Token::Word(tag, interner.get(name).unwrap())
+    }
+
+    fn identifier(interner: &Interner, name: &str) -> Token<'static> {
+        word(interner, Word::Identifier, name)
+    }
+
+    // The same input and expectations as ex_2_6's test_lexer
+    #[rstest]
+    #[allow(clippy::approx_constant)]
+    fn test_generated_lexer() {
+        let generated = generated();
+        let mut scanner = generated.scanner(
+            "// random  comment \n\
+             hello = 12    * 5\t + 3\n\
+             boolean_variable_=true | false //comment at the /* end\n\
+             /* test multiline comment\n\
+             commented_variable = 3 */\n\
+             > >= < <= == !=\n\
+             >>=<<===!=\n\
+             2. 3.14 .5.",
+        );
+        let tokens: Vec<Token> = scanner.tokenize().into_iter().map(|t| t.token).collect();
+        let interner = scanner.interner();
+
+        let expected_values = vec![
+            identifier(interner, "hello"),
+            Token::Assign("=".into()),
+            Token::Number(NumberValue::Integer(12), "12".into()),
+            Token::ArithmeticOperator(ArithmeticOperator::Times, "*".into()),
+            Token::Number(NumberValue::Integer(5), "5".into()),
+            Token::ArithmeticOperator(ArithmeticOperator::Plus, "+".into()),
+            Token::Number(NumberValue::Integer(3), "3".into()),
+            identifier(interner, "boolean_variable_"),
+            Token::Assign("=".into()),
+            word(interner, Word::True, "true"),
+            Token::BitwiseOperator(BitwiseOperator::Or, "|".into()),
+            word(interner, Word::False, "false"),
+            Token::LogicalOperator(LogicalOperator::Greater, ">".into()),
+            Token::LogicalOperator(LogicalOperator::GreaterOrEqual, ">=".into()),
+            Token::LogicalOperator(LogicalOperator::Less, "<".into()),
+            Token::LogicalOperator(LogicalOperator::LessOrEqual, "<=".into()),
+            Token::LogicalOperator(LogicalOperator::Equal, "==".into()),
+            Token::LogicalOperator(LogicalOperator::Different, "!=".into()),
+            Token::LogicalOperator(LogicalOperator::Greater, ">".into()),
+            Token::LogicalOperator(LogicalOperator::GreaterOrEqual, ">=".into()),
+            Token::LogicalOperator(LogicalOperator::Less, "<".into()),
+            Token::LogicalOperator(LogicalOperator::LessOrEqual, "<=".into()),
+            Token::LogicalOperator(LogicalOperator::Equal, "==".into()),
+            Token::LogicalOperator(LogicalOperator::Different, "!=".into()),
+            Token::Number(NumberValue::Float(2.0), "2.".into()),
+            Token::Number(NumberValue::Float(3.14), "3.14".into()),
+            Token::Number(NumberValue::Float(0.5), ".5".into()),
+            Token::Unknown(".".into()),
+        ];
+
+        assert_eq!(tokens, expected_values);
+    }
+
+    // Words are compared by name since each lexer has its own interner
+    fn resolved(tokens: Vec<Token>, interner: &Interner) -> Vec<String> {
+        tokens
+            .into_iter()
+            .map(|token| match token {
+                Token::Word(tag, symbol) => format!("{:?}({})", tag, interner.resolve(symbol)),
+                token => format!("{:?}", token),
+            })
+            .collect()
+    }
+
+    #[rstest]
+    #[case("{ x = (a + b) * c - d / e; } !ok && (p || !q) & 1 != 2 >")]
+    #[case("2else 0b12 0o19 0b1_02 0x 0xFF 0o17 1_000_000 1e10 2.5E-3 1E+3 007")]
+    #[case("9223372036854775807 9223372036854775808 0x1_0000_0000_0000_0000 1e999")]
+    #[case(r#""hello" "" "a\n\t\\\"\'\0b" "\u{48}\u{e9}\u{1F600}" "\q" "\u{110000}""#)]
+    #[case("'a' '\\n' '\\u{e9}' 'ab' '' '\\q'")]
+    #[case("x /* a ** b */ y // z\n w")]
+    #[case("\"multi\r\nline\" 'a\n' \"open")]
+    #[case("a $ b @")]
+    fn test_generated_lexer_matches_lexer(#[case] input: &str) {
+        let generated = generated();
+        let mut scanner = generated.scanner(input);
+        let scanned: Vec<Token> = scanner.tokenize().into_iter().map(|t| t.token).collect();
+        let mut lexer = Lexer::new(input).with_recovery(true);
+        let lexed: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(
+            resolved(scanned, scanner.interner()),
+            resolved(lexed, lexer.interner())
+        );
+    }
+
+    const EMITTED_SCANNER: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/src/ch03/lexer_generator/ex_2_6_scanner.rs"
+    );
+
+    // ex_2_6_scanner.rs is checked in as token_emitter emits it. After
+    // changing the patterns, regenerate it with
+    // cargo test regenerate_emitted_scanner -- --ignored
+    #[rstest]
+    #[ignore]
+    fn regenerate_emitted_scanner() {
+        std::fs::write(EMITTED_SCANNER, token_emitter().emit().unwrap()).unwrap();
+    }
+
+    #[rstest]
+    fn test_emitted_scanner_is_up_to_date() {
+        let emitted = token_emitter().emit().unwrap();
+
+        assert!(emitted == std::fs::read_to_string(EMITTED_SCANNER).unwrap());
+    }
+
+    // The Lexer token of a lexeme of the emitted scanner, its kind being
+    // checked against the operator it names when it is an operator
+    fn emitted_token(lexeme: Lexeme, interner: &mut Interner) -> Token<'static> {
+        let text: Cow<'static, str> = lexeme.text.to_string().into();
+        match (lexeme.kind, lexeme.value) {
+            (TokenKind::True, None) => Token::Word(Word::True, interner.intern(&text)),
+            (TokenKind::False, None) => Token::Word(Word::False, interner.intern(&text)),
+            (TokenKind::Identifier, None) => Token::Word(Word::Identifier, interner.intern(&text)),
+            (TokenKind::Number, Some(Value::Integer(value))) => {
+                Token::Number(NumberValue::Integer(value), text)
+            }
+            (TokenKind::Number, Some(Value::Float(value))) => {
+                Token::Number(NumberValue::Float(value), text)
+            }
+            (TokenKind::String, Some(Value::String(value))) => Token::StringLiteral(value, text),
+            (TokenKind::Char, Some(Value::Char(value))) => Token::CharLiteral(value, text),
+            (TokenKind::IntegerOverflow, None) => Token::Error(LexicalError::IntegerOverflow, text),
+            (TokenKind::FloatOverflow, None) => Token::Error(LexicalError::FloatOverflow, text),
+            (TokenKind::MalformedNumber, None) => Token::Error(LexicalError::MalformedNumber, text),
+            (TokenKind::InvalidEscape, None) => Token::Error(LexicalError::InvalidEscape, text),
+            (TokenKind::MalformedChar, None) => Token::Error(LexicalError::MalformedChar, text),
+            (TokenKind::UnterminatedString, None) => {
+                Token::Error(LexicalError::UnterminatedString, text)
+            }
+            (TokenKind::UnterminatedChar, None) => {
+                Token::Error(LexicalError::UnterminatedChar, text)
+            }
+            (TokenKind::Unknown, None) => Token::Unknown(text),
+            (kind, None) => {
+                let (_, operator) = Operator::defaults()
+                    .into_iter()
+                    .find(|(operator, _)| *operator == text)
+                    .unwrap();
+                assert_eq!(format!("{:?}", kind), operator_kind(operator));
+                operator.token(text)
+            }
+            (kind, Some(value)) => panic!("{:?} has the value {:?}", kind, value),
+        }
+    }
+
+    #[rstest]
+    #[case(
+        "// random  comment \n\
+         hello = 12    * 5\t + 3\n\
+         boolean_variable_=true | false //comment at the /* end\n\
+         /* test multiline comment\n\
+         commented_variable = 3 */\n\
+         > >= < <= == !=\n\
+         >>=<<===!=\n\
+         2. 3.14 .5."
+    )]
+    #[case("{ x = (a + b) * c - d / e; } !ok && (p || !q) & 1 != 2 >")]
+    #[case("2else 0b12 0o19 0x 0xFF 0o17 1_000_000 1e10 2.5E-3 1E+3 007 1e999")]
+    #[case("9223372036854775807 9223372036854775808 0x1_0000_0000_0000_0000 0x_")]
+    #[case(r#""hello" "a\n\"" "\q" "\u{48}\u{e9}\u{1F600}" "\u{110000}""#)]
+    #[case(r#"'\u{e9}' 'ab' '' '\q' '\n'"#)]
+    #[case("\"multi\r\nline\" 'a\n' \"open")]
+    #[case("a $ b @ #")]
+    fn test_emitted_scanner_matches_lexer(#[case] input: &str) {
+        let mut interner = Interner::new();
+        let emitted: Vec<Token> = Scanner::new(input)
+            .map(|lexeme| emitted_token(lexeme.unwrap(), &mut interner))
+            .collect();
+        let mut lexer = Lexer::new(input).with_recovery(true);
+        let lexed: Vec<Token> = lexer.tokenize().into_iter().map(|t| t.token).collect();
+
+        assert_eq!(
+            resolved(emitted, &interner),
+            resolved(lexed, lexer.interner())
+        );
+    }
+}
@@ -0,0 +1,117 @@
+// The values of ex_2_6's number, string and char literals, decoded the way
+// ex_2_6::Lexer decodes them. This module only depends on std: token_emitter
+// copies it into the emitted scanner, so that both generated scanners
+// decode literals with the same code.
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Char(char),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LiteralError {
+    IntegerOverflow,
+    FloatOverflow,
+    MalformedNumber,
+    InvalidEscape,
+    MalformedChar,
+}
+
+pub fn decimal_number(lexeme: &str) -> Result<Value, LiteralError> {
+    let digits: String = lexeme.chars().filter(|c| *c != '_').collect();
+    if digits.contains(['.', 'e', 'E']) {
+        match digits.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(Value::Float(value)),
+            _ => Err(LiteralError::FloatOverflow),
+        }
+    } else {
+        digits
+            .parse::<i64>()
+            .map(Value::Integer)
+            .map_err(|_| LiteralError::IntegerOverflow)
+    }
+}
+
+pub fn hexadecimal_number(lexeme: &str) -> Result<Value, LiteralError> {
+    radix_number(lexeme, 16)
+}
+
+pub fn octal_number(lexeme: &str) -> Result<Value, LiteralError> {
+    radix_number(lexeme, 8)
+}
+
+pub fn binary_number(lexeme: &str) -> Result<Value, LiteralError> {
+    radix_number(lexeme, 2)
+}
+
+// A number after a two char prefix such as 0x
+fn radix_number(lexeme: &str, radix: u32) -> Result<Value, LiteralError> {
+    let digits: String = lexeme[2..].chars().filter(|c| *c != '_').collect();
+    if digits.is_empty() {
+        return Err(LiteralError::MalformedNumber);
+    }
+    i64::from_str_radix(&digits, radix)
+        .map(Value::Integer)
+        .map_err(|_| LiteralError::IntegerOverflow)
+}
+
+pub fn string_literal(lexeme: &str) -> Result<Value, LiteralError> {
+    unescape(&lexeme[1..lexeme.len() - 1]).map(Value::String)
+}
+
+pub fn char_literal(lexeme: &str) -> Result<Value, LiteralError> {
+    let value = unescape(&lexeme[1..lexeme.len() - 1])?;
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(Value::Char(c)),
+        _ => Err(LiteralError::MalformedChar),
+    }
+}
+
+// The escapes ex_2_6::Lexer accepts inside string and char literals, with
+// "\r\n" read as "\n"
+fn unescape(body: &str) -> Result<String, LiteralError> {
+    let mut value = String::new();
+    let mut error = None;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\r' && chars.peek() == Some(&'\n') {
+            continue;
+        }
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('r') => Some('\r'),
+            Some('0') => Some('\0'),
+            Some(escaped @ ('\\' | '"' | '\'')) => Some(escaped),
+            Some('u') if chars.peek() == Some(&'{') => {
+                chars.next();
+                let mut digits = String::new();
+                while let Some(digit) = chars.next_if(|c| c.is_ascii_hexdigit()) {
+                    digits.push(digit);
+                }
+                let closed = chars.next_if_eq(&'}').is_some();
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| closed && digits.len() <= 6)
+                    .and_then(char::from_u32)
+            }
+            _ => None,
+        };
+        match escaped {
+            Some(escaped) => value.push(escaped),
+            None => error = error.or(Some(LiteralError::InvalidEscape)),
+        }
+    }
+    match error {
+        Some(error) => Err(error),
+        None => Ok(value),
+    }
+}
@@ -0,0 +1,430 @@
+// @generated by ch03::lexer_generator::emit::ScannerEmitter; do not edit.
+
+use std::cmp::Ordering;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy)]
+pub enum TokenKind {
+    True,
+    False,
+    Identifier,
+    Number,
+    MalformedNumber,
+    String,
+    UnterminatedString,
+    Char,
+    UnterminatedChar,
+    IntegerOverflow,
+    FloatOverflow,
+    InvalidEscape,
+    MalformedChar,
+    LessOrEqual,
+    Equal,
+    Different,
+    GreaterOrEqual,
+    Less,
+    Greater,
+    LogicalAnd,
+    LogicalOr,
+    LogicalNot,
+    BitwiseAnd,
+    BitwiseOr,
+    Plus,
+    Minus,
+    Times,
+    Divide,
+    Assign,
+    OpenParen,
+    CloseParen,
+    OpenBrace,
+    CloseBrace,
+    Semicolon,
+    Unknown,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Lexeme<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub offset: usize,
+    pub value: Option<Value>,
+}
+
+type Action = fn(&str) -> Result<Value, TokenKind>;
+
+#[derive(Debug, Clone, Copy)]
+enum Accept {
+    No,
+    Skip,
+    Token(TokenKind, Option<Action>),
+}
+
+// Sorted, disjoint char ranges leaving each state; a char none of them
+// covers leads to the dead state
+static TRANSITIONS: [&[(char, char, usize)]; 78] = [
+    &[('\0', '\u{8}', 1), ('\t', '\t', 2), ('\n', '\n', 3), ('\u{b}', '\r', 2), ('\u{e}', '\u{1f}', 1), (' ', ' ', 2), ('!', '!', 4), ('"', '"', 5), ('#', '%', 1), ('&', '&', 6), ('\'', '\'', 7), ('(', '(', 8), (')', ')', 9), ('*', '*', 10), ('+', '+', 11), (',', ',', 1), ('-', '-', 12), ('.', '.', 13), ('/', '/', 14), ('0', '0', 15), ('1', '9', 16), (':', ':', 1), (';', ';', 17), ('<', '<', 18), ('=', '=', 19), ('>', '>', 20), ('?', '@', 1), ('A', 'Z', 21), ('[', '^', 1), ('_', '_', 21), ('`', '`', 1), ('a', 'e', 21), ('f', 'f', 22), ('g', 's', 21), ('t', 't', 23), ('u', 'z', 21), ('{', '{', 24), ('|', '|', 25), ('}', '}', 26), ('~', '\u{10ffff}', 1)],
+    &[],
+    &[('\t', '\r', 3), (' ', ' ', 3)],
+    &[('\t', '\r', 3), (' ', ' ', 3)],
+    &[('=', '=', 27)],
+    &[('\0', '!', 28), ('"', '"', 29), ('#', '[', 28), ('\\', '\\', 30), (']', '\u{10ffff}', 28)],
+    &[('&', '&', 31)],
+    &[('\0', '\t', 32), ('\u{b}', '&', 32), ('\'', '\'', 33), ('(', '[', 32), ('\\', '\\', 34), (']', '\u{10ffff}', 32)],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[],
+    &[('0', '9', 35)],
+    &[('*', '*', 36), ('/', '/', 37)],
+    &[('.', '.', 38), ('0', '9', 39), ('B', 'B', 40), ('E', 'E', 41), ('O', 'O', 42), ('X', 'X', 43), ('_', '_', 39), ('b', 'b', 40), ('e', 'e', 41), ('o', 'o', 42), ('x', 'x', 43)],
+    &[('.', '.', 38), ('0', '9', 39), ('E', 'E', 41), ('_', '_', 39), ('e', 'e', 41)],
+    &[],
+    &[('=', '=', 44)],
+    &[('=', '=', 45)],
+    &[('=', '=', 46)],
+    &[('0', '9', 47), ('A', 'Z', 47), ('_', '_', 47), ('a', 'z', 47)],
+    &[('0', '9', 47), ('A', 'Z', 47), ('_', '_', 47), ('a', 'a', 48), ('b', 'z', 47)],
+    &[('0', '9', 47), ('A', 'Z', 47), ('_', '_', 47), ('a', 'q', 47), ('r', 'r', 49), ('s', 'z', 47)],
+    &[],
+    &[('|', '|', 50)],
+    &[],
+    &[],
+    &[('\0', '!', 28), ('"', '"', 29), ('#', '[', 28), ('\\', '\\', 30), (']', '\u{10ffff}', 28)],
+    &[],
+    &[('\0', '\t', 51), ('\n', '\n', 52), ('\u{b}', '\u{10ffff}', 51)],
+    &[],
+    &[('\0', '\t', 32), ('\u{b}', '&', 32), ('\'', '\'', 33), ('(', '[', 32), ('\\', '\\', 34), (']', '\u{10ffff}', 32)],
+    &[],
+    &[('\0', '\t', 53), ('\u{b}', '\u{10ffff}', 53)],
+    &[('0', '9', 54), ('E', 'E', 55), ('_', '_', 54), ('e', 'e', 55)],
+    &[('\0', ')', 56), ('*', '*', 57), ('+', '\u{10ffff}', 56)],
+    &[('\0', '\t', 58), ('\u{b}', '\u{10ffff}', 58)],
+    &[('0', '9', 59), ('E', 'E', 41), ('_', '_', 59), ('e', 'e', 41)],
+    &[('.', '.', 38), ('0', '9', 39), ('E', 'E', 41), ('_', '_', 39), ('e', 'e', 41)],
+    &[('0', '1', 60), ('2', '9', 61), ('_', '_', 60)],
+    &[('+', '+', 62), ('-', '-', 62), ('0', '9', 63)],
+    &[('0', '7', 64), ('8', '9', 61), ('_', '_', 64)],
+    &[('0', '9', 65), ('A', 'F', 66), ('_', '_', 65), ('a', 'f', 66)],
+    &[],
+    &[],
+    &[],
+    &[('0', '9', 47), ('A', 'Z', 47), ('_', '_', 47), ('a', 'z', 47)],
+    &[('0', '9', 47), ('A', 'Z', 47), ('_', '_', 47), ('a', 'k', 47), ('l', 'l', 67), ('m', 'z', 47)],
+    &[('0', '9', 47), ('A', 'Z', 47), ('_', '_', 47), ('a', 't', 47), ('u', 'u', 68), ('v', 'z', 47)],
+    &[],
+    &[('\0', '!', 28), ('"', '"', 29), ('#', '[', 28), ('\\', '\\', 30), (']', '\u{10ffff}', 28)],
+    &[('\0', '!', 28), ('"', '"', 29), ('#', '[', 28), ('\\', '\\', 30), (']', '\u{10ffff}', 28)],
+    &[('\0', '\t', 32), ('\u{b}', '&', 32), ('\'', '\'', 33), ('(', '[', 32), ('\\', '\\', 34), (']', '\u{10ffff}', 32)],
+    &[('0', '9', 54), ('E', 'E', 55), ('_', '_', 54), ('e', 'e', 55)],
+    &[('+', '+', 69), ('-', '-', 69), ('0', '9', 70)],
+    &[('\0', ')', 56), ('*', '*', 57), ('+', '\u{10ffff}', 56)],
+    &[('\0', ')', 71), ('*', '*', 57), ('+', '.', 71), ('/', '/', 72), ('0', '\u{10ffff}', 71)],
+    &[('\0', '\t', 58), ('\u{b}', '\u{10ffff}', 58)],
+    &[('0', '9', 59), ('E', 'E', 41), ('_', '_', 59), ('e', 'e', 41)],
+    &[('0', '1', 60), ('2', '9', 61), ('_', '_', 60)],
+    &[('0', '9', 61), ('_', '_', 61)],
+    &[('0', '9', 63)],
+    &[('0', '9', 73), ('_', '_', 73)],
+    &[('0', '7', 64), ('8', '9', 61), ('_', '_', 64)],
+    &[('0', '9', 65), ('A', 'F', 66), ('_', '_', 65), ('a', 'f', 66)],
+    &[('0', '9', 66), ('A', 'F', 66), ('_', '_', 66), ('a', 'f', 66)],
+    &[('0', '9', 47), ('A', 'Z', 47), ('_', '_', 47), ('a', 'r', 47), ('s', 's', 74), ('t', 'z', 47)],
+    &[('0', '9', 47), ('A', 'Z', 47), ('_', '_', 47), ('a', 'd', 47), ('e', 'e', 75), ('f', 'z', 47)],
+    &[('0', '9', 70)],
+    &[('0', '9', 76), ('_', '_', 76)],
+    &[('\0', ')', 56), ('*', '*', 57), ('+', '\u{10ffff}', 56)],
+    &[],
+    &[('0', '9', 73), ('_', '_', 73)],
+    &[('0', '9', 47), ('A', 'Z', 47), ('_', '_', 47), ('a', 'd', 47), ('e', 'e', 77), ('f', 'z', 47)],
+    &[('0', '9', 47), ('A', 'Z', 47), ('_', '_', 47), ('a', 'z', 47)],
+    &[('0', '9', 76), ('_', '_', 76)],
+    &[('0', '9', 47), ('A', 'Z', 47), ('_', '_', 47), ('a', 'z', 47)],
+];
+
+static ACCEPT: [Accept; 78] = [
+    Accept::No,
+    Accept::Token(TokenKind::Unknown, None),
+    Accept::Skip,
+    Accept::Skip,
+    Accept::Token(TokenKind::LogicalNot, None),
+    Accept::Token(TokenKind::UnterminatedString, None),
+    Accept::Token(TokenKind::BitwiseAnd, None),
+    Accept::Token(TokenKind::UnterminatedChar, None),
+    Accept::Token(TokenKind::OpenParen, None),
+    Accept::Token(TokenKind::CloseParen, None),
+    Accept::Token(TokenKind::Times, None),
+    Accept::Token(TokenKind::Plus, None),
+    Accept::Token(TokenKind::Minus, None),
+    Accept::Token(TokenKind::Unknown, None),
+    Accept::Token(TokenKind::Divide, None),
+    Accept::Token(TokenKind::Number, Some(|text: &str| decimal_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::Number, Some(|text: &str| decimal_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::Semicolon, None),
+    Accept::Token(TokenKind::Less, None),
+    Accept::Token(TokenKind::Assign, None),
+    Accept::Token(TokenKind::Greater, None),
+    Accept::Token(TokenKind::Identifier, None),
+    Accept::Token(TokenKind::Identifier, None),
+    Accept::Token(TokenKind::Identifier, None),
+    Accept::Token(TokenKind::OpenBrace, None),
+    Accept::Token(TokenKind::BitwiseOr, None),
+    Accept::Token(TokenKind::CloseBrace, None),
+    Accept::Token(TokenKind::Different, None),
+    Accept::Token(TokenKind::UnterminatedString, None),
+    Accept::Token(TokenKind::String, Some(|text: &str| string_literal(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::UnterminatedString, None),
+    Accept::Token(TokenKind::LogicalAnd, None),
+    Accept::Token(TokenKind::UnterminatedChar, None),
+    Accept::Token(TokenKind::Char, Some(|text: &str| char_literal(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::UnterminatedChar, None),
+    Accept::Token(TokenKind::Number, Some(|text: &str| decimal_number(text).map_err(TokenKind::from))),
+    Accept::No,
+    Accept::Skip,
+    Accept::Token(TokenKind::Number, Some(|text: &str| decimal_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::Number, Some(|text: &str| decimal_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::MalformedNumber, None),
+    Accept::No,
+    Accept::Token(TokenKind::MalformedNumber, None),
+    Accept::Token(TokenKind::MalformedNumber, None),
+    Accept::Token(TokenKind::LessOrEqual, None),
+    Accept::Token(TokenKind::Equal, None),
+    Accept::Token(TokenKind::GreaterOrEqual, None),
+    Accept::Token(TokenKind::Identifier, None),
+    Accept::Token(TokenKind::Identifier, None),
+    Accept::Token(TokenKind::Identifier, None),
+    Accept::Token(TokenKind::LogicalOr, None),
+    Accept::Token(TokenKind::UnterminatedString, None),
+    Accept::Token(TokenKind::UnterminatedString, None),
+    Accept::Token(TokenKind::UnterminatedChar, None),
+    Accept::Token(TokenKind::Number, Some(|text: &str| decimal_number(text).map_err(TokenKind::from))),
+    Accept::No,
+    Accept::No,
+    Accept::No,
+    Accept::Skip,
+    Accept::Token(TokenKind::Number, Some(|text: &str| decimal_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::Number, Some(|text: &str| binary_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::MalformedNumber, None),
+    Accept::No,
+    Accept::Token(TokenKind::Number, Some(|text: &str| decimal_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::Number, Some(|text: &str| octal_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::Number, Some(|text: &str| hexadecimal_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::Number, Some(|text: &str| hexadecimal_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::Identifier, None),
+    Accept::Token(TokenKind::Identifier, None),
+    Accept::No,
+    Accept::Token(TokenKind::Number, Some(|text: &str| decimal_number(text).map_err(TokenKind::from))),
+    Accept::No,
+    Accept::Skip,
+    Accept::Token(TokenKind::Number, Some(|text: &str| decimal_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::Identifier, None),
+    Accept::Token(TokenKind::True, None),
+    Accept::Token(TokenKind::Number, Some(|text: &str| decimal_number(text).map_err(TokenKind::from))),
+    Accept::Token(TokenKind::False, None),
+];
+
+const START: usize = 0;
+
+pub struct Scanner<'a> {
+    input: &'a str,
+    offset: usize,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Scanner { input, offset: 0 }
+    }
+
+    // Where the longest nonempty lexeme at the current offset ends and what
+    // the state it ends in accepts
+    fn longest_match(&self) -> Option<(usize, Accept)> {
+        let mut state = START;
+        let mut longest = None;
+        for (index, c) in self.input[self.offset..].char_indices() {
+            match next_state(state, c) {
+                Some(next) => state = next,
+                None => break,
+            }
+            if !matches!(ACCEPT[state], Accept::No) {
+                longest = Some((self.offset + index + c.len_utf8(), ACCEPT[state]));
+            }
+        }
+        longest
+    }
+}
+
+impl<'a> Iterator for Scanner<'a> {
+    type Item = Result<Lexeme<'a>, usize>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.offset < self.input.len() {
+            let start = self.offset;
+            let Some((end, accept)) = self.longest_match() else {
+                let c = self.input[start..].chars().next().unwrap();
+                self.offset += c.len_utf8();
+                return Some(Err(start));
+            };
+            self.offset = end;
+            if let Accept::Token(kind, action) = accept {
+                let text = &self.input[start..end];
+                let (kind, value) = match action.map(|action| action(text)) {
+                    None => (kind, None),
+                    Some(Ok(value)) => (kind, Some(value)),
+                    Some(Err(kind)) => (kind, None),
+                };
+                return Some(Ok(Lexeme {
+                    kind,
+                    text,
+                    offset: start,
+                    value,
+                }));
+            }
+        }
+        None
+    }
+}
+
+fn next_state(state: usize, c: char) -> Option<usize> {
+    let transitions = TRANSITIONS[state];
+    transitions
+        .binary_search_by(|(start, end, _)| {
+            if *end < c {
+                Ordering::Less
+            } else if *start > c {
+                Ordering::Greater
+            } else {
+                Ordering::Equal
+            }
+        })
+        .ok()
+        .map(|index| transitions[index].2)
+}
+
+// The values of ex_2_6's number, string and char literals, decoded the way
+// ex_2_6::Lexer decodes them. This module only depends on std: token_emitter
+// copies it into the emitted scanner, so that both generated scanners
+// decode literals with the same code.
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Integer(i64),
+    Float(f64),
+    String(String),
+    Char(char),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LiteralError {
+    IntegerOverflow,
+    FloatOverflow,
+    MalformedNumber,
+    InvalidEscape,
+    MalformedChar,
+}
+
+pub fn decimal_number(lexeme: &str) -> Result<Value, LiteralError> {
+    let digits: String = lexeme.chars().filter(|c| *c != '_').collect();
+    if digits.contains(['.', 'e', 'E']) {
+        match digits.parse::<f64>() {
+            Ok(value) if value.is_finite() => Ok(Value::Float(value)),
+            _ => Err(LiteralError::FloatOverflow),
+        }
+    } else {
+        digits
+            .parse::<i64>()
+            .map(Value::Integer)
+            .map_err(|_| LiteralError::IntegerOverflow)
+    }
+}
+
+pub fn hexadecimal_number(lexeme: &str) -> Result<Value, LiteralError> {
+    radix_number(lexeme, 16)
+}
+
+pub fn octal_number(lexeme: &str) -> Result<Value, LiteralError> {
+    radix_number(lexeme, 8)
+}
+
+pub fn binary_number(lexeme: &str) -> Result<Value, LiteralError> {
+    radix_number(lexeme, 2)
+}
+
+// A number after a two char prefix such as 0x
+fn radix_number(lexeme: &str, radix: u32) -> Result<Value, LiteralError> {
+    let digits: String = lexeme[2..].chars().filter(|c| *c != '_').collect();
+    if digits.is_empty() {
+        return Err(LiteralError::MalformedNumber);
+    }
+    i64::from_str_radix(&digits, radix)
+        .map(Value::Integer)
+        .map_err(|_| LiteralError::IntegerOverflow)
+}
+
+pub fn string_literal(lexeme: &str) -> Result<Value, LiteralError> {
+    unescape(&lexeme[1..lexeme.len() - 1]).map(Value::String)
+}
+
+pub fn char_literal(lexeme: &str) -> Result<Value, LiteralError> {
+    let value = unescape(&lexeme[1..lexeme.len() - 1])?;
+    let mut chars = value.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(Value::Char(c)),
+        _ => Err(LiteralError::MalformedChar),
+    }
+}
+
+// The escapes ex_2_6::Lexer accepts inside string and char literals, with
+// "\r\n" read as "\n"
+fn unescape(body: &str) -> Result<String, LiteralError> {
+    let mut value = String::new();
+    let mut error = None;
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == '\r' && chars.peek() == Some(&'\n') {
+            continue;
+        }
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let escaped = match chars.next() {
+            Some('n') => Some('\n'),
+            Some('t') => Some('\t'),
+            Some('r') => Some('\r'),
+            Some('0') => Some('\0'),
+            Some(escaped @ ('\\' | '"' | '\'')) => Some(escaped),
+            Some('u') if chars.peek() == Some(&'{') => {
+                chars.next();
+                let mut digits = String::new();
+                while let Some(digit) = chars.next_if(|c| c.is_ascii_hexdigit()) {
+                    digits.push(digit);
+                }
+                let closed = chars.next_if_eq(&'}').is_some();
+                u32::from_str_radix(&digits, 16)
+                    .ok()
+                    .filter(|_| closed && digits.len() <= 6)
+                    .and_then(char::from_u32)
+            }
+            _ => None,
+        };
+        match escaped {
+            Some(escaped) => value.push(escaped),
+            None => error = error.or(Some(LiteralError::InvalidEscape)),
+        }
+    }
+    match error {
+        Some(error) => Err(error),
+        None => Ok(value),
+    }
+}
+
+impl From<LiteralError> for TokenKind {
+    fn from(error: LiteralError) -> Self {
+        match error {
+            LiteralError::IntegerOverflow => TokenKind::IntegerOverflow,
+            LiteralError::FloatOverflow => TokenKind::FloatOverflow,
+            LiteralError::MalformedNumber => TokenKind::MalformedNumber,
+            LiteralError::InvalidEscape => TokenKind::InvalidEscape,
+            LiteralError::MalformedChar => TokenKind::MalformedChar,
+        }
+    }
+}
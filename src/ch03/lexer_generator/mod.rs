@@ -0,0 +1,7 @@
+pub mod emit;
+pub mod ex_2_6;
+pub mod ex_2_6_literals;
+#[rustfmt::skip]
+pub mod ex_2_6_scanner;
+pub mod scanner;
+pub mod spec;
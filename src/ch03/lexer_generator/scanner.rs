@@ -0,0 +1,264 @@
+use std::collections::BTreeSet;
+
+use super::spec::Action;
+use crate::ch02::lexical_analyzer::ex_2_6::{LexicalError, SpannedLexicalError};
+use crate::ch02::lexical_analyzer::span::{LineIndex, Span};
+use crate::ch02::recursive_descent_parser::diagnostic::Diagnostic;
+use crate::ch02::symbol_table::interner::Interner;
+use crate::ch03::automata::dfa::{determinize, label_ranges, Dfa};
+use crate::ch03::automata::nfa::{Nfa, StateId};
+use crate::ch03::regex::ast::Regex;
+use crate::ch03::regex::parser::RegexParser;
+
+// The DFA of section 3.8.3 for a list of patterns, along with the pattern
+// each of its accepting states announces
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ScannerTable {
+    dfa: Dfa,
+    rules: Vec<Option<usize>>,
+}
+
+impl ScannerTable {
+    // Figure 3.52: a new start state with ε edges to the NFA of each
+    // pattern, turned into a DFA by the subset construction. NFA states are
+    // numbered consecutively across patterns, so the start state is just the
+    // union of every NFA's start closure. A DFA state holding the accepting
+    // states of several patterns announces the one listed first.
+    pub fn from_patterns(patterns: &[Regex]) -> Self {
+        let nfas: Vec<Nfa> = patterns.iter().map(Nfa::from_regex).collect();
+        let mut offsets = Vec::with_capacity(nfas.len());
+        let mut state_count = 0;
+        for nfa in nfas.iter() {
+            offsets.push(state_count);
+            state_count += nfa.state_count();
+        }
+
+        let start = nfas
+            .iter()
+            .zip(offsets.iter())
+            .flat_map(|(nfa, offset)| nfa.start_closure().into_iter().map(move |s| s + offset))
+            .collect();
+        let ranges = nfas
+            .iter()
+            .flat_map(|nfa| (0..nfa.state_count()).flat_map(|state| nfa.transitions(state)))
+            .flat_map(|(label, _)| label_ranges(label));
+        let next = |states: &BTreeSet<StateId>, c: char| {
+            let mut next = BTreeSet::new();
+            for (nfa, offset) in nfas.iter().zip(offsets.iter()) {
+                let local: BTreeSet<StateId> = states
+                    .range(offset..&(offset + nfa.state_count()))
+                    .map(|state| state - offset)
+                    .collect();
+                if local.is_empty() {
+                    continue;
+                }
+                let moved = nfa.epsilon_closure(&nfa.move_on(&local, c));
+                next.extend(moved.into_iter().map(|state| state + offset));
+            }
+            next
+        };
+        let rule = |states: &BTreeSet<StateId>| {
+            (0..nfas.len()).find(|i| states.contains(&(offsets[*i] + nfas[*i].accept())))
+        };
+
+        let (dfa, dstates) = determinize(start, ranges, next, |states| rule(states).is_some());
+        let rules = dstates.iter().map(rule).collect();
+        ScannerTable { dfa, rules }
+    }
+
+    // Parses the patterns of a list of rules and builds their table. A
+    // pattern that does not parse is reported with the rule it belongs to.
+    pub fn parse<'p>(
+        patterns: impl IntoIterator<Item = &'p str>,
+    ) -> Result<Self, Diagnostic<char>> {
+        let mut regexes = Vec::new();
+        for (i, pattern) in patterns.into_iter().enumerate() {
+            let regex = RegexParser::new(pattern)
+                .parse()
+                .map_err(|error| error.with_note(&format!("in rule {} /{}/", i, pattern)))?;
+            regexes.push(regex);
+        }
+        Ok(ScannerTable::from_patterns(&regexes))
+    }
+
+    pub fn dfa(&self) -> &Dfa {
+        &self.dfa
+    }
+
+    // The pattern a lexeme ending in state is taken as, if any
+    pub fn rule(&self, state: StateId) -> Option<usize> {
+        self.rules[state]
+    }
+
+    // Runs the DFA from start until it dies or the input ends, remembering
+    // the last accepting state it went through. Returns where the longest
+    // nonempty lexeme ends and which pattern it matched.
+    pub fn longest_match(&self, input: &[char], start: usize) -> Option<(usize, usize)> {
+        let mut state = self.dfa.start();
+        let mut longest = None;
+        for (index, c) in input.iter().enumerate().skip(start) {
+            match self.dfa.next_state(state, *c) {
+                Some(next) => state = next,
+                None => break,
+            }
+            if let Some(rule) = self.rules[state] {
+                longest = Some((index + 1, rule));
+            }
+        }
+        longest
+    }
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub struct ScannedToken<T> {
+    pub token: T,
+    pub span: Span,
+}
+
+// Splits an input into lexemes with a ScannerTable and hands each one to
+// the action of the rule it matched. Lexemes whose rule has no action are
+// skipped. A char that starts no lexeme is reported as illegal and skipped.
+pub struct Scanner<'g, T> {
+    table: &'g ScannerTable,
+    actions: &'g [Option<Action<T>>],
+    input: Vec<char>,
+    line_index: LineIndex,
+    index: usize,
+    interner: Interner,
+    errors: Vec<SpannedLexicalError>,
+}
+
+impl<'g, T> Scanner<'g, T> {
+    pub fn new(table: &'g ScannerTable, actions: &'g [Option<Action<T>>], input: &str) -> Self {
+        let input: Vec<char> = input.chars().collect();
+        Scanner {
+            table,
+            actions,
+            line_index: LineIndex::new(&input),
+            input,
+            index: 0,
+            interner: Interner::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    pub fn tokenize(&mut self) -> Vec<ScannedToken<T>> {
+        self.by_ref().collect()
+    }
+
+    pub fn errors(&self) -> &[SpannedLexicalError] {
+        &self.errors
+    }
+
+    // The symbol table the actions intern words into
+    pub fn interner(&self) -> &Interner {
+        &self.interner
+    }
+
+    pub fn into_interner(self) -> Interner {
+        self.interner
+    }
+}
+
+impl<T> Iterator for Scanner<'_, T> {
+    type Item = ScannedToken<T>;
+
+    fn next(&mut self) -> Option<ScannedToken<T>> {
+        while self.index < self.input.len() {
+            let start = self.index;
+            let Some((end, rule)) = self.table.longest_match(&self.input, start) else {
+                self.errors.push(SpannedLexicalError {
+                    error: LexicalError::IllegalCharacter(self.input[start]),
+                    span: self.line_index.span(start, start + 1),
+                });
+                self.index += 1;
+                continue;
+            };
+            self.index = end;
+            if let Some(action) = &self.actions[rule] {
+                let lexeme: String = self.input[start..end].iter().collect();
+                return Some(ScannedToken {
+                    token: action(&lexeme, &mut self.interner),
+                    span: self.line_index.span(start, end),
+                });
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::spec::LexerSpec;
+    use super::*;
+    use crate::ch03::regex::parser::RegexParser;
+    use rstest::rstest;
+
+    // The patterns of figure 3.51
+    fn figure_3_51() -> ScannerTable {
+        let patterns: Vec<Regex> = ["a", "abb", "a*b+"]
+            .iter()
+            .map(|pattern| RegexParser::new(pattern).parse().unwrap())
+            .collect();
+        ScannerTable::from_patterns(&patterns)
+    }
+
+    #[rstest]
+    #[case("a", Some((1, 0)))]
+    #[case("abb", Some((3, 1)))]
+    #[case("abba", Some((3, 1)))]
+    #[case("aaba", Some((3, 2)))]
+    #[case("abbb", Some((4, 2)))]
+    #[case("b", Some((1, 2)))]
+    #[case("ca", None)]
+    #[case("", None)]
+    fn test_longest_match_figure_3_54(
+        #[case] input: &str,
+        #[case] expected: Option<(usize, usize)>,
+    ) {
+        let input: Vec<char> = input.chars().collect();
+
+        assert_eq!(figure_3_51().longest_match(&input, 0), expected);
+    }
+
+    #[rstest]
+    fn test_scanner_table_figure_3_54() {
+        let table = figure_3_51();
+
+        // Same states as figure 3.54 but numbered in the order they are found:
+        // 0137, 247, 8, 58, 68 and 7
+        let rules: Vec<Option<usize>> = (0..table.dfa().state_count())
+            .map(|state| table.rule(state))
+            .collect();
+        assert_eq!(rules, vec![None, Some(0), Some(2), None, Some(2), Some(1)]);
+    }
+
+    #[rstest]
+    fn test_scanner_skips_and_reports_illegal_characters() {
+        let lexer = LexerSpec::new()
+            .skip(" +")
+            .rule("[a-z]+", |lexeme, interner| interner.intern(lexeme))
+            .build()
+            .unwrap();
+        let mut scanner = lexer.scanner("ab ?c\n d");
+        let words: Vec<ScannedToken<_>> = scanner.tokenize();
+
+        let names: Vec<&str> = words
+            .iter()
+            .map(|word| scanner.interner().resolve(word.token))
+            .collect();
+        assert_eq!(names, vec!["ab", "c", "d"]);
+        assert_eq!(words[2].span.start.line, 2);
+        assert_eq!(
+            scanner
+                .errors()
+                .iter()
+                .map(|error| (error.error, error.span.start.column))
+                .collect::<Vec<_>>(),
+            vec![
+                (LexicalError::IllegalCharacter('?'), 4),
+                (LexicalError::IllegalCharacter('\n'), 6),
+            ]
+        );
+    }
+}
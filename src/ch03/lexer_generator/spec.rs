@@ -0,0 +1,142 @@
+use super::scanner::{Scanner, ScannerTable};
+use crate::ch02::recursive_descent_parser::diagnostic::Diagnostic;
+use crate::ch02::symbol_table::interner::Interner;
+use crate::ch03::regex::ast::Regex;
+
+// What a rule does with its lexeme, like the code in braces of a lex rule.
+// The interner plays the part of installID() in figure 3.23.
+pub type Action<T> = Box<dyn Fn(&str, &mut Interner) -> T>;
+
+pub struct Rule<T> {
+    pub pattern: String,
+    pub action: Option<Action<T>>,
+}
+
+// The translation rules of a lex program (section 3.5.2). The scanner built
+// from them takes the longest lexeme any rule matches; when several rules
+// match that same lexeme, the one added first wins.
+pub struct LexerSpec<T> {
+    rules: Vec<Rule<T>>,
+}
+
+impl<T> Default for LexerSpec<T> {
+    fn default() -> Self {
+        LexerSpec::new()
+    }
+}
+
+impl<T> LexerSpec<T> {
+    pub fn new() -> Self {
+        LexerSpec { rules: Vec::new() }
+    }
+
+    pub fn rule(
+        mut self,
+        pattern: &str,
+        action: impl Fn(&str, &mut Interner) -> T + 'static,
+    ) -> Self {
+        self.rules.push(Rule {
+            pattern: pattern.to_string(),
+            action: Some(Box::new(action)),
+        });
+        self
+    }
+
+    // A rule matching exactly lexeme, with any regex operators in it escaped
+    pub fn literal(
+        self,
+        lexeme: &str,
+        action: impl Fn(&str, &mut Interner) -> T + 'static,
+    ) -> Self {
+        self.rule(&Regex::literal(lexeme).to_string(), action)
+    }
+
+    // A rule whose lexemes produce no token, such as whitespace or comments
+    pub fn skip(mut self, pattern: &str) -> Self {
+        self.rules.push(Rule {
+            pattern: pattern.to_string(),
+            action: None,
+        });
+        self
+    }
+
+    pub fn rules(&self) -> &[Rule<T>] {
+        &self.rules
+    }
+
+    pub fn build(self) -> Result<GeneratedLexer<T>, Diagnostic<char>> {
+        let table = ScannerTable::parse(self.rules.iter().map(|rule| rule.pattern.as_str()))?;
+
+        Ok(GeneratedLexer {
+            table,
+            actions: self.rules.into_iter().map(|rule| rule.action).collect(),
+        })
+    }
+}
+
+pub struct GeneratedLexer<T> {
+    table: ScannerTable,
+    actions: Vec<Option<Action<T>>>,
+}
+
+impl<T> GeneratedLexer<T> {
+    pub fn table(&self) -> &ScannerTable {
+        &self.table
+    }
+
+    pub fn scanner(&self, input: &str) -> Scanner<'_, T> {
+        Scanner::new(&self.table, &self.actions, input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ch02::recursive_descent_parser::diagnostic::ErrorKind;
+    use rstest::rstest;
+
+    #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+    enum Kind {
+        If,
+        Identifier,
+        Plus,
+        PlusPlus,
+    }
+
+    fn kinds(input: &str) -> Vec<Kind> {
+        LexerSpec::new()
+            .skip("[ \\t\\n]+")
+            .rule("if", |_, _| Kind::If)
+            .rule("[a-z]+", |_, _| Kind::Identifier)
+            .literal("+", |_, _| Kind::Plus)
+            .literal("++", |_, _| Kind::PlusPlus)
+            .build()
+            .unwrap()
+            .scanner(input)
+            .map(|scanned| scanned.token)
+            .collect()
+    }
+
+    #[rstest]
+    #[case("if", vec![Kind::If])]
+    #[case("ifs", vec![Kind::Identifier])]
+    #[case("i f", vec![Kind::Identifier, Kind::Identifier])]
+    #[case("a+++b", vec![Kind::Identifier, Kind::PlusPlus, Kind::Plus, Kind::Identifier])]
+    #[case("", vec![])]
+    fn test_longest_match_and_rule_priority(#[case] input: &str, #[case] expected: Vec<Kind>) {
+        assert_eq!(kinds(input), expected);
+    }
+
+    #[rstest]
+    fn test_build_reports_invalid_pattern() {
+        let error = LexerSpec::new()
+            .rule("[a-z]+", |_, _| ())
+            .rule("(a", |_, _| ())
+            .build()
+            .err()
+            .unwrap();
+
+        assert_eq!(error.kind, ErrorKind::UnexpectedEndOfInput);
+        assert_eq!(error.notes, vec!["in rule 1 /(a/".to_string()]);
+    }
+}
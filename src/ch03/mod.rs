@@ -0,0 +1,3 @@
+pub mod automata;
+pub mod lexer_generator;
+pub mod regex;
@@ -0,0 +1,138 @@
+use std::fmt::{self, Display};
+
+// A set of chars given as inclusive ranges, e.g. [a-z_] or [^0-9]
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub struct CharClass {
+    pub negated: bool,
+    pub ranges: Vec<(char, char)>,
+}
+
+impl CharClass {
+    pub fn new(ranges: Vec<(char, char)>) -> Self {
+        CharClass {
+            negated: false,
+            ranges,
+        }
+    }
+
+    pub fn negated(ranges: Vec<(char, char)>) -> Self {
+        CharClass {
+            negated: true,
+            ranges,
+        }
+    }
+
+    // The class matched by ".": every char but a newline
+    pub fn any() -> Self {
+        CharClass::negated(vec![('\n', '\n')])
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        let in_ranges = self
+            .ranges
+            .iter()
+            .any(|(start, end)| *start <= c && c <= *end);
+        in_ranges != self.negated
+    }
+}
+
+impl Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if *self == CharClass::any() {
+            return write!(f, ".");
+        }
+        write!(f, "[{}", if self.negated { "^" } else { "" })?;
+        for (start, end) in self.ranges.iter() {
+            write!(f, "{}", escape(*start, true))?;
+            if start != end {
+                write!(f, "-{}", escape(*end, true))?;
+            }
+        }
+        write!(f, "]")
+    }
+}
+
+// Regular expressions over chars, as in section 3.3.3 plus the usual
+// shorthands: r+ is rr*, r? is r|ε and a char class is the union of its chars
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub enum Regex {
+    Empty,
+    Char(char),
+    Class(CharClass),
+    Concat(Box<Regex>, Box<Regex>),
+    Union(Box<Regex>, Box<Regex>),
+    Star(Box<Regex>),
+    Plus(Box<Regex>),
+    Optional(Box<Regex>),
+}
+
+impl Regex {
+    // The regex matching exactly the given string
+    pub fn literal(text: &str) -> Self {
+        text.chars()
+            .map(Regex::Char)
+            .reduce(|left, right| Regex::Concat(Box::new(left), Box::new(right)))
+            .unwrap_or(Regex::Empty)
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Regex::Union(_, _) => 0,
+            Regex::Concat(_, _) => 1,
+            Regex::Star(_) | Regex::Plus(_) | Regex::Optional(_) => 2,
+            Regex::Empty | Regex::Char(_) | Regex::Class(_) => 3,
+        }
+    }
+
+    fn fmt_operand(&self, f: &mut fmt::Formatter<'_>, min_precedence: u8) -> fmt::Result {
+        if self.precedence() < min_precedence {
+            write!(f, "({})", self)
+        } else {
+            write!(f, "{}", self)
+        }
+    }
+}
+
+// Prints the regex back in the syntax RegexParser reads, with only the
+// parentheses precedence requires
+impl Display for Regex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Regex::Empty => write!(f, "()"),
+            Regex::Char(c) => write!(f, "{}", escape(*c, false)),
+            Regex::Class(class) => write!(f, "{}", class),
+            Regex::Concat(left, right) => {
+                left.fmt_operand(f, 1)?;
+                right.fmt_operand(f, 2)
+            }
+            Regex::Union(left, right) => {
+                left.fmt_operand(f, 0)?;
+                write!(f, "|")?;
+                right.fmt_operand(f, 1)
+            }
+            Regex::Star(inner) => {
+                inner.fmt_operand(f, 3)?;
+                write!(f, "*")
+            }
+            Regex::Plus(inner) => {
+                inner.fmt_operand(f, 3)?;
+                write!(f, "+")
+            }
+            Regex::Optional(inner) => {
+                inner.fmt_operand(f, 3)?;
+                write!(f, "?")
+            }
+        }
+    }
+}
+
+fn escape(c: char, in_class: bool) -> String {
+    let special = if in_class { "\\]^-[" } else { "\\|*+?()[].^$" };
+    match c {
+        '\n' => "\\n".to_string(),
+        '\t' => "\\t".to_string(),
+        '\r' => "\\r".to_string(),
+        c if special.contains(c) => format!("\\{}", c),
+        c => c.to_string(),
+    }
+}
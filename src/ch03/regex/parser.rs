@@ -0,0 +1,305 @@
+use super::ast::{CharClass, Regex};
+use crate::ch02::lexical_analyzer::span::Span;
+use crate::ch02::recursive_descent_parser::diagnostic::{char_span, Diagnostic, ErrorKind};
+
+pub struct RegexParser {
+    input: Vec<char>,
+    lookahead_index: usize,
+}
+
+impl RegexParser {
+    // Recursive-descent parser for regular expressions, loosest binding first:
+    // regex  := term ('|' term)*
+    // term   := factor*
+    // factor := atom ('*' | '+' | '?')*
+    // atom   := char | '.' | '\' escape | '[' '^'? class_item+ ']' | '(' regex ')'
+    // An empty term, as in "a|" or "()", is ε. The escapes \d, \w and \s stand
+    // for [0-9], [A-Za-z0-9_] and ASCII whitespace; any other escaped
+    // punctuation char stands for itself.
+
+    pub fn new(input: &str) -> Self {
+        RegexParser {
+            input: input.chars().collect(),
+            lookahead_index: 0,
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<Regex, Diagnostic<char>> {
+        let regex = self.regex()?;
+
+        match self.peek() {
+            None => Ok(regex),
+            Some(c) => Err(Diagnostic::new(
+                ErrorKind::TrailingInput,
+                vec![],
+                Some(c),
+                self.span_here(),
+            )),
+        }
+    }
+
+    fn regex(&mut self) -> Result<Regex, Diagnostic<char>> {
+        let mut regex = self.term()?;
+        while self.peek() == Some('|') {
+            self.lookahead_index += 1;
+            let right = self.term()?;
+            regex = Regex::Union(Box::new(regex), Box::new(right));
+        }
+        Ok(regex)
+    }
+
+    fn term(&mut self) -> Result<Regex, Diagnostic<char>> {
+        let mut term: Option<Regex> = None;
+        while let Some(c) = self.peek() {
+            if c == '|' || c == ')' {
+                break;
+            }
+            let factor = self.factor()?;
+            term = Some(match term {
+                Some(left) => Regex::Concat(Box::new(left), Box::new(factor)),
+                None => factor,
+            });
+        }
+        Ok(term.unwrap_or(Regex::Empty))
+    }
+
+    fn factor(&mut self) -> Result<Regex, Diagnostic<char>> {
+        let mut factor = self.atom()?;
+        while let Some(c @ ('*' | '+' | '?')) = self.peek() {
+            self.lookahead_index += 1;
+            factor = match c {
+                '*' => Regex::Star(Box::new(factor)),
+                '+' => Regex::Plus(Box::new(factor)),
+                _ => Regex::Optional(Box::new(factor)),
+            };
+        }
+        Ok(factor)
+    }
+
+    fn atom(&mut self) -> Result<Regex, Diagnostic<char>> {
+        let c = self
+            .next_char()
+            .expect("term only calls factor before a char");
+        match c {
+            '(' => {
+                let inner = self.regex()?;
+                self.expect(')')?;
+                Ok(inner)
+            }
+            '[' => self.class(),
+            '.' => Ok(Regex::Class(CharClass::any())),
+            '\\' => match self.escape()? {
+                Escaped::Char(c) => Ok(Regex::Char(c)),
+                Escaped::Class(ranges) => Ok(Regex::Class(CharClass::new(ranges))),
+            },
+            '*' | '+' | '?' => Err(self
+                .unexpected_previous(c, vec![])
+                .with_note("nothing to repeat")),
+            c => Ok(Regex::Char(c)),
+        }
+    }
+
+    fn class(&mut self) -> Result<Regex, Diagnostic<char>> {
+        let negated = self.peek() == Some('^');
+        if negated {
+            self.lookahead_index += 1;
+        }
+
+        let mut ranges = Vec::new();
+        loop {
+            let start = match self.next_char() {
+                None => return Err(self.end_of_input(vec![']'])),
+                Some(']') if ranges.is_empty() => {
+                    return Err(self
+                        .unexpected_previous(']', vec![])
+                        .with_note("empty character class"));
+                }
+                Some(']') => break,
+                Some('\\') => match self.escape()? {
+                    Escaped::Char(c) => c,
+                    Escaped::Class(class_ranges) => {
+                        ranges.extend(class_ranges);
+                        continue;
+                    }
+                },
+                Some(c) => c,
+            };
+
+            let is_range = self.peek() == Some('-')
+                && self
+                    .input
+                    .get(self.lookahead_index + 1)
+                    .is_some_and(|c| *c != ']');
+            if !is_range {
+                ranges.push((start, start));
+                continue;
+            }
+            self.lookahead_index += 1;
+            let end = match self.next_char() {
+                Some('\\') => match self.escape()? {
+                    Escaped::Char(c) => c,
+                    Escaped::Class(_) => {
+                        return Err(self
+                            .unexpected_previous('\\', vec![])
+                            .with_note("a class escape cannot end a range"));
+                    }
+                },
+                Some(c) => c,
+                None => unreachable!("is_range checked there is a char after '-'"),
+            };
+            if end < start {
+                return Err(self
+                    .unexpected_previous(end, vec![])
+                    .with_note(&format!("range end {:?} comes before {:?}", end, start)));
+            }
+            ranges.push((start, end));
+        }
+
+        Ok(Regex::Class(CharClass { negated, ranges }))
+    }
+
+    fn escape(&mut self) -> Result<Escaped, Diagnostic<char>> {
+        let c = match self.next_char() {
+            Some(c) => c,
+            None => return Err(self.end_of_input(vec![])),
+        };
+        let escaped = match c {
+            'n' => Escaped::Char('\n'),
+            't' => Escaped::Char('\t'),
+            'r' => Escaped::Char('\r'),
+            'd' => Escaped::Class(vec![('0', '9')]),
+            'w' => Escaped::Class(vec![('A', 'Z'), ('a', 'z'), ('0', '9'), ('_', '_')]),
+            's' => Escaped::Class(vec![(' ', ' '), ('\t', '\r')]),
+            c if c.is_ascii_punctuation() => Escaped::Char(c),
+            c => {
+                return Err(self
+                    .unexpected_previous(c, vec![])
+                    .with_note("unknown escape sequence"));
+            }
+        };
+        Ok(escaped)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), Diagnostic<char>> {
+        match self.next_char() {
+            Some(c) if c == expected => Ok(()),
+            Some(c) => Err(self.unexpected_previous(c, vec![expected])),
+            None => Err(self.end_of_input(vec![expected])),
+        }
+    }
+
+    fn unexpected_previous(&self, found: char, expected: Vec<char>) -> Diagnostic<char> {
+        let index = self.lookahead_index - 1;
+        Diagnostic::new(
+            ErrorKind::UnexpectedToken,
+            expected,
+            Some(found),
+            char_span(&self.input, index, index + 1),
+        )
+    }
+
+    fn end_of_input(&self, expected: Vec<char>) -> Diagnostic<char> {
+        let end = self.input.len();
+        Diagnostic::new(
+            ErrorKind::UnexpectedEndOfInput,
+            expected,
+            None,
+            char_span(&self.input, end, end),
+        )
+    }
+
+    fn span_here(&self) -> Span {
+        char_span(&self.input, self.lookahead_index, self.lookahead_index + 1)
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.lookahead_index).copied()
+    }
+
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.lookahead_index += 1;
+        Some(c)
+    }
+}
+
+enum Escaped {
+    Char(char),
+    Class(Vec<(char, char)>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    fn concat(left: Regex, right: Regex) -> Regex {
+        Regex::Concat(Box::new(left), Box::new(right))
+    }
+
+    fn union(left: Regex, right: Regex) -> Regex {
+        Regex::Union(Box::new(left), Box::new(right))
+    }
+
+    #[rstest]
+    fn test_parse_precedence() -> Result<(), Diagnostic<char>> {
+        let regex = RegexParser::new("a|bc*").parse()?;
+
+        assert_eq!(
+            regex,
+            union(
+                Regex::Char('a'),
+                concat(Regex::Char('b'), Regex::Star(Box::new(Regex::Char('c'))))
+            )
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("(a|b)*abb")]
+    #[case("a+b?c*")]
+    #[case("[a-z_][a-z0-9_]*")]
+    #[case("[^0-9]")]
+    #[case("\\.\\*\\n")]
+    #[case("a|()")]
+    #[case("(ab)*|c+")]
+    #[case(".x")]
+    fn test_parse_display_round_trip(#[case] input: &str) -> Result<(), Diagnostic<char>> {
+        let regex = RegexParser::new(input).parse()?;
+
+        assert_eq!(regex.to_string(), input);
+        assert_eq!(RegexParser::new(&regex.to_string()).parse()?, regex);
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("[a-c\\d-]", vec![('a', 'c'), ('0', '9'), ('-', '-')], false)]
+    #[case("[^\\]x]", vec![(']', ']'), ('x', 'x')], true)]
+    fn test_parse_class(
+        #[case] input: &str,
+        #[case] ranges: Vec<(char, char)>,
+        #[case] negated: bool,
+    ) -> Result<(), Diagnostic<char>> {
+        let regex = RegexParser::new(input).parse()?;
+
+        assert_eq!(regex, Regex::Class(CharClass { negated, ranges }));
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("(ab", ErrorKind::UnexpectedEndOfInput, 3)]
+    #[case("ab)", ErrorKind::TrailingInput, 2)]
+    #[case("*a", ErrorKind::UnexpectedToken, 0)]
+    #[case("a|+", ErrorKind::UnexpectedToken, 2)]
+    #[case("[a-", ErrorKind::UnexpectedEndOfInput, 3)]
+    #[case("[z-a]", ErrorKind::UnexpectedToken, 3)]
+    #[case("[]", ErrorKind::UnexpectedToken, 1)]
+    #[case("a\\q", ErrorKind::UnexpectedToken, 2)]
+    #[case("a\\", ErrorKind::UnexpectedEndOfInput, 2)]
+    fn test_parse_invalid(#[case] input: &str, #[case] kind: ErrorKind, #[case] offset: usize) {
+        let error = RegexParser::new(input).parse().unwrap_err();
+
+        assert_eq!(error.kind, kind);
+        assert_eq!(error.span.start.offset, offset);
+    }
+}
@@ -0,0 +1,296 @@
+use super::grammar::{Grammar, Symbol};
+use crate::ch02::recursive_descent_parser::diagnostic::{char_span, Diagnostic, ErrorKind};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BnfToken {
+    Name,
+    Quoted,
+    Arrow,
+    Bar,
+    Newline,
+}
+
+pub struct BnfParser {
+    input: Vec<char>,
+    lookahead_index: usize,
+}
+
+impl BnfParser {
+    // Reads grammars written the way this repo's comments write them, one
+    // nonterminal per line:
+    // S := S ( S ) S | ε
+    // Symbols are separated by whitespace, and a line starting with '|'
+    // adds alternatives to the line before. "->" and "→" may stand for ":=".
+    // Nonterminals are the heads; every other symbol is a terminal, and a
+    // name in single quotes, e.g. '|', is a terminal whatever it spells.
+    // ε stands for the empty string. The first head is the start symbol.
+
+    pub fn new(input: &str) -> Self {
+        BnfParser {
+            input: input.chars().collect(),
+            lookahead_index: 0,
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<Grammar, Diagnostic<BnfToken>> {
+        let mut lines: Vec<Vec<(BnfToken, usize, usize)>> = vec![Vec::new()];
+        while let Some((token, start, end)) = self.next_token()? {
+            match token {
+                BnfToken::Newline => lines.push(Vec::new()),
+                token => lines.last_mut().unwrap().push((token, start, end)),
+            }
+        }
+        lines.retain(|line| !line.is_empty());
+
+        // Heads are numbered before any body is read, so that a nonterminal
+        // used before its line is not taken for a terminal
+        let mut grammar: Option<Grammar> = None;
+        for line in lines.iter() {
+            let (token, start, end) = line[0];
+            match token {
+                BnfToken::Name => {
+                    let head = self.text(start, end);
+                    match grammar.as_mut() {
+                        Some(grammar) => {
+                            grammar.add_nonterminal(&head);
+                        }
+                        None => grammar = Some(Grammar::new(&head)),
+                    }
+                }
+                BnfToken::Bar if grammar.is_some() => {}
+                token => return Err(self.unexpected(token, start, end, vec![BnfToken::Name])),
+            }
+        }
+        let Some(mut grammar) = grammar else {
+            let end = self.input.len();
+            return Err(self.unexpected(BnfToken::Newline, end, end, vec![BnfToken::Name]));
+        };
+
+        let mut head = 0;
+        for line in lines.iter() {
+            let alternatives = match line[0] {
+                (BnfToken::Bar, _, _) => &line[..],
+                (_, start, end) => {
+                    head = grammar.nonterminal_id(&self.text(start, end)).unwrap();
+                    match line.get(1) {
+                        Some((BnfToken::Arrow, _, _)) => &line[1..],
+                        Some((token, start, end)) => {
+                            return Err(self.unexpected(
+                                *token,
+                                *start,
+                                *end,
+                                vec![BnfToken::Arrow],
+                            ))
+                        }
+                        None => {
+                            return Err(self.unexpected(
+                                BnfToken::Newline,
+                                end,
+                                end,
+                                vec![BnfToken::Arrow],
+                            ))
+                        }
+                    }
+                }
+            };
+
+            // Each alternative starts at an Arrow or a Bar
+            let mut body = None;
+            for (token, start, end) in alternatives.iter().copied() {
+                let name = self.text(start, end);
+                match token {
+                    BnfToken::Arrow if body.is_some() => {
+                        return Err(self.unexpected(token, start, end, vec![]));
+                    }
+                    BnfToken::Arrow | BnfToken::Bar => {
+                        if let Some(body) = body.replace(Vec::new()) {
+                            grammar.add_production(head, body);
+                        }
+                    }
+                    BnfToken::Name if name == "ε" => {}
+                    // Quoted or not, "$" would be taken for Grammar::END
+                    BnfToken::Name | BnfToken::Quoted if name == "$" || name == "'$'" => {
+                        return Err(self
+                            .unexpected(token, start, end, vec![])
+                            .with_note("$ is reserved for the endmarker"));
+                    }
+                    BnfToken::Name => {
+                        let symbol = match grammar.nonterminal_id(&name) {
+                            Some(nonterminal) => Symbol::Nonterminal(nonterminal),
+                            None => Symbol::Terminal(grammar.add_terminal(&name)),
+                        };
+                        body.as_mut().unwrap().push(symbol);
+                    }
+                    BnfToken::Quoted => {
+                        let terminal = grammar.add_terminal(&name[1..name.len() - 1]);
+                        body.as_mut().unwrap().push(Symbol::Terminal(terminal));
+                    }
+                    BnfToken::Newline => unreachable!("lines are split at newlines"),
+                }
+            }
+            grammar.add_production(head, body.unwrap());
+        }
+
+        Ok(grammar)
+    }
+
+    fn next_token(&mut self) -> Result<Option<(BnfToken, usize, usize)>, Diagnostic<BnfToken>> {
+        while self.peek().is_some_and(|c| c.is_whitespace() && c != '\n') {
+            self.lookahead_index += 1;
+        }
+        let start = self.lookahead_index;
+        let token = match self.peek() {
+            None => return Ok(None),
+            Some('\n') => {
+                self.lookahead_index += 1;
+                BnfToken::Newline
+            }
+            Some('|') => {
+                self.lookahead_index += 1;
+                BnfToken::Bar
+            }
+            Some('\'') => {
+                self.lookahead_index += 1;
+                while self.peek().is_some_and(|c| c != '\'' && c != '\n') {
+                    self.lookahead_index += 1;
+                }
+                if self.peek() != Some('\'') {
+                    let end = self.lookahead_index;
+                    return Err(self
+                        .unexpected(BnfToken::Newline, start, end, vec![BnfToken::Quoted])
+                        .with_note("unterminated quoted terminal"));
+                }
+                self.lookahead_index += 1;
+                BnfToken::Quoted
+            }
+            Some(_) => {
+                while self.peek().is_some_and(|c| !c.is_whitespace() && c != '|') {
+                    self.lookahead_index += 1;
+                }
+                match self.text(start, self.lookahead_index).as_str() {
+                    ":=" | "->" | "→" => BnfToken::Arrow,
+                    _ => BnfToken::Name,
+                }
+            }
+        };
+        Ok(Some((token, start, self.lookahead_index)))
+    }
+
+    // A newline stands for the end of a line or of the input
+    fn unexpected(
+        &self,
+        found: BnfToken,
+        start: usize,
+        end: usize,
+        expected: Vec<BnfToken>,
+    ) -> Diagnostic<BnfToken> {
+        match found {
+            BnfToken::Newline => Diagnostic::new(
+                ErrorKind::UnexpectedEndOfInput,
+                expected,
+                None,
+                char_span(&self.input, start, end),
+            ),
+            found => Diagnostic::new(
+                ErrorKind::UnexpectedToken,
+                expected,
+                Some(found),
+                char_span(&self.input, start, end),
+            ),
+        }
+    }
+
+    fn text(&self, start: usize, end: usize) -> String {
+        self.input[start..end].iter().collect()
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input.get(self.lookahead_index).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rstest::rstest;
+
+    #[rstest]
+    fn test_parse_grammar() -> Result<(), Diagnostic<BnfToken>> {
+        let grammar = BnfParser::new("S := S ( S ) S | ε").parse()?;
+
+        let s = Symbol::Nonterminal(0);
+        let open = Symbol::Terminal(1);
+        let close = Symbol::Terminal(2);
+        assert_eq!(grammar.terminals(), ["$", "(", ")"]);
+        assert_eq!(grammar.nonterminals(), ["S"]);
+        assert_eq!(grammar.start(), 0);
+        assert_eq!(
+            grammar
+                .productions()
+                .iter()
+                .map(|p| p.body.clone())
+                .collect::<Vec<_>>(),
+            vec![vec![s, open, s, close, s], vec![]]
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    fn test_parse_nonterminal_used_before_its_line() -> Result<(), Diagnostic<BnfToken>> {
+        let grammar = BnfParser::new(
+            "E -> T E'\n\
+             E' → + T E' | ε\n\
+             T := id\n\
+             \x20  | '(' E ')'\n",
+        )
+        .parse()?;
+
+        assert_eq!(grammar.nonterminals(), ["E", "E'", "T"]);
+        assert_eq!(grammar.terminals(), ["$", "+", "id", "(", ")"]);
+        assert_eq!(grammar.describe(4), "T := ( E )");
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("S := S ( S ) S | ε\n")]
+    #[case("E := E + T | T\nT := T * F | F\nF := ( E ) | id\n")]
+    #[case("S := '|' S | 'S' | ':=' | a'b\n")]
+    fn test_display_round_trip(#[case] input: &str) -> Result<(), Diagnostic<BnfToken>> {
+        let grammar = BnfParser::new(input).parse()?;
+
+        assert_eq!(grammar.to_string(), input);
+        assert_eq!(BnfParser::new(&grammar.to_string()).parse()?, grammar);
+        Ok(())
+    }
+
+    #[rstest]
+    fn test_display_leaves_out_heads_without_productions() -> Result<(), Diagnostic<BnfToken>> {
+        let mut grammar = Grammar::new("S");
+        let a = grammar.add_nonterminal("A");
+        let b = grammar.add_terminal("b");
+        grammar.add_production(a, vec![Symbol::Terminal(b)]);
+
+        assert_eq!(grammar.to_string(), "A := b\n");
+        assert_eq!(
+            BnfParser::new(&grammar.to_string()).parse()?.nonterminals(),
+            ["A"]
+        );
+        Ok(())
+    }
+
+    #[rstest]
+    #[case("", ErrorKind::UnexpectedEndOfInput, 0)]
+    #[case("S a b", ErrorKind::UnexpectedToken, 2)]
+    #[case("S", ErrorKind::UnexpectedEndOfInput, 1)]
+    #[case("| a", ErrorKind::UnexpectedToken, 0)]
+    #[case("S := a := b", ErrorKind::UnexpectedToken, 7)]
+    #[case("S := a $", ErrorKind::UnexpectedToken, 7)]
+    #[case("S := a '$'", ErrorKind::UnexpectedToken, 7)]
+    #[case("S := 'a", ErrorKind::UnexpectedEndOfInput, 5)]
+    fn test_parse_invalid(#[case] input: &str, #[case] kind: ErrorKind, #[case] offset: usize) {
+        let error = BnfParser::new(input).parse().unwrap_err();
+
+        assert_eq!(error.kind, kind);
+        assert_eq!(error.span.start.offset, offset);
+    }
+}
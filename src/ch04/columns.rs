@@ -0,0 +1,27 @@
+use std::fmt;
+
+// Writes rows of cells as left-aligned columns two spaces apart, each as
+// wide as its widest cell, the way the tables and traces of this chapter
+// are laid out. Trailing spaces are trimmed.
+pub fn write_columns<R: AsRef<[String]>>(out: &mut impl fmt::Write, rows: &[R]) -> fmt::Result {
+    let columns = rows.iter().map(|row| row.as_ref().len()).max().unwrap_or(0);
+    let widths: Vec<usize> = (0..columns)
+        .map(|column| {
+            rows.iter()
+                .filter_map(|row| row.as_ref().get(column))
+                .map(|cell| cell.chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    for row in rows.iter() {
+        let cells: Vec<String> = row
+            .as_ref()
+            .iter()
+            .zip(widths.iter())
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect();
+        writeln!(out, "{}", cells.join("  ").trim_end())?;
+    }
+    Ok(())
+}
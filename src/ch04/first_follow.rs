@@ -0,0 +1,179 @@
+use std::collections::BTreeSet;
+
+use super::grammar::{Grammar, Symbol};
+
+// Whether each nonterminal derives ε, and its FIRST and FOLLOW sets as
+// terminal indexes (section 4.4.2). ε is kept out of FIRST; a nonterminal
+// that derives ε is nullable instead. FOLLOW sets may hold Grammar::END.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FirstFollow {
+    nullable: Vec<bool>,
+    first: Vec<BTreeSet<usize>>,
+    follow: Vec<BTreeSet<usize>>,
+}
+
+impl FirstFollow {
+    // Each set starts empty and the rules of section 4.4.2 are applied to
+    // every production until none of them adds anything
+    pub fn new(grammar: &Grammar) -> Self {
+        let nonterminals = grammar.nonterminals().len();
+        let mut sets = FirstFollow {
+            nullable: vec![false; nonterminals],
+            first: vec![BTreeSet::new(); nonterminals],
+            follow: vec![BTreeSet::new(); nonterminals],
+        };
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for production in grammar.productions() {
+                let (first, nullable) = sets.first_of(&production.body);
+                let head = production.head;
+                if nullable && !sets.nullable[head] {
+                    sets.nullable[head] = true;
+                    changed = true;
+                }
+                let size = sets.first[head].len();
+                sets.first[head].extend(first);
+                changed |= sets.first[head].len() != size;
+            }
+        }
+
+        sets.follow[grammar.start()].insert(Grammar::END);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for production in grammar.productions() {
+                for (i, symbol) in production.body.iter().enumerate() {
+                    let Symbol::Nonterminal(nonterminal) = symbol else {
+                        continue;
+                    };
+                    // A → αBβ: FIRST(β) is in FOLLOW(B), and so is FOLLOW(A)
+                    // if β derives ε
+                    let (mut follow, nullable) = sets.first_of(&production.body[i + 1..]);
+                    if nullable {
+                        follow.extend(sets.follow[production.head].iter().copied());
+                    }
+                    let size = sets.follow[*nonterminal].len();
+                    sets.follow[*nonterminal].extend(follow);
+                    changed |= sets.follow[*nonterminal].len() != size;
+                }
+            }
+        }
+
+        sets
+    }
+
+    pub fn is_nullable(&self, nonterminal: usize) -> bool {
+        self.nullable[nonterminal]
+    }
+
+    pub fn first(&self, nonterminal: usize) -> &BTreeSet<usize> {
+        &self.first[nonterminal]
+    }
+
+    pub fn follow(&self, nonterminal: usize) -> &BTreeSet<usize> {
+        &self.follow[nonterminal]
+    }
+
+    // FIRST of a string of grammar symbols, and whether the whole string
+    // derives ε
+    pub fn first_of(&self, symbols: &[Symbol]) -> (BTreeSet<usize>, bool) {
+        let mut first = BTreeSet::new();
+        for symbol in symbols.iter() {
+            match symbol {
+                Symbol::Terminal(terminal) => {
+                    first.insert(*terminal);
+                    return (first, false);
+                }
+                Symbol::Nonterminal(nonterminal) => {
+                    first.extend(self.first[*nonterminal].iter().copied());
+                    if !self.nullable[*nonterminal] {
+                        return (first, false);
+                    }
+                }
+            }
+        }
+        (first, true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bnf::BnfParser;
+    use super::*;
+    use rstest::rstest;
+
+    fn names(grammar: &Grammar, terminals: &BTreeSet<usize>) -> Vec<String> {
+        terminals
+            .iter()
+            .map(|terminal| grammar.terminals()[*terminal].clone())
+            .collect()
+    }
+
+    // The grammars of exercise 2.4.1, as the parsers in
+    // ch02::recursive_descent_parser document them
+    #[rstest]
+    #[case("S := + S S | - S S | a", false, vec!["+", "-", "a"], vec!["$", "+", "-", "a"])]
+    #[case("S := S ( S ) S | ε", true, vec!["("], vec!["$", "(", ")"])]
+    #[case("S := 0 S 1 | 0 1", false, vec!["0"], vec!["$", "1"])]
+    fn test_exercise_2_4_1_sets(
+        #[case] input: &str,
+        #[case] nullable: bool,
+        #[case] first: Vec<&str>,
+        #[case] follow: Vec<&str>,
+    ) {
+        let grammar = BnfParser::new(input).parse().unwrap();
+        let sets = FirstFollow::new(&grammar);
+
+        assert_eq!(sets.is_nullable(0), nullable);
+        assert_eq!(names(&grammar, sets.first(0)), first);
+        assert_eq!(names(&grammar, sets.follow(0)), follow);
+    }
+
+    // Example 4.30, for grammar 4.28
+    #[rstest]
+    #[case("E", false, vec!["(", "id"], vec!["$", ")"])]
+    #[case("E'", true, vec!["+"], vec!["$", ")"])]
+    #[case("T", false, vec!["(", "id"], vec!["$", "+", ")"])]
+    #[case("T'", true, vec!["*"], vec!["$", "+", ")"])]
+    #[case("F", false, vec!["(", "id"], vec!["$", "+", "*", ")"])]
+    fn test_example_4_30(
+        #[case] nonterminal: &str,
+        #[case] nullable: bool,
+        #[case] first: Vec<&str>,
+        #[case] follow: Vec<&str>,
+    ) {
+        let grammar = BnfParser::new(
+            "E := T E'\n\
+             E' := + T E' | ε\n\
+             T := F T'\n\
+             T' := * F T' | ε\n\
+             F := ( E ) | id",
+        )
+        .parse()
+        .unwrap();
+        let sets = FirstFollow::new(&grammar);
+        let nonterminal = grammar.nonterminal_id(nonterminal).unwrap();
+
+        assert_eq!(sets.is_nullable(nonterminal), nullable);
+        assert_eq!(names(&grammar, sets.first(nonterminal)), first);
+        assert_eq!(names(&grammar, sets.follow(nonterminal)), follow);
+    }
+
+    #[rstest]
+    fn test_first_of_string() {
+        let grammar = BnfParser::new("S := A B c\nA := a | ε\nB := b | ε")
+            .parse()
+            .unwrap();
+        let sets = FirstFollow::new(&grammar);
+        let a = Symbol::Nonterminal(1);
+        let b = Symbol::Nonterminal(2);
+
+        let (first, nullable) = sets.first_of(&[a, b]);
+        assert_eq!(names(&grammar, &first), vec!["a", "b"]);
+        assert!(nullable);
+        assert_eq!(names(&grammar, sets.first(0)), vec!["c", "a", "b"]);
+        assert_eq!(names(&grammar, sets.follow(1)), vec!["c", "b"]);
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt::{self, Debug, Display};
+
+use crate::ch02::lexical_analyzer::span::{LineIndex, Span};
+use crate::ch02::recursive_descent_parser::diagnostic::{Diagnostic, ErrorKind};
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Symbol {
+    Terminal(usize),
+    Nonterminal(usize),
+}
+
+// The name of a grammar symbol, as parse trees and diagnostics of parsers
+// generated from a grammar show it. It prints bare, e.g. S rather than "S".
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+pub struct Name<'g>(pub &'g str);
+
+impl Debug for Name<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// A terminal of the input of a parser with its span, or the error reading
+// it
+pub type SpannedTerminal<'g> = Result<(usize, Span), Diagnostic<Name<'g>>>;
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone)]
+pub struct Production {
+    pub head: usize,
+    pub body: Vec<Symbol>,
+}
+
+// A context-free grammar (section 4.2.1). Terminals and nonterminals are
+// numbered in the order they are added; terminal 0 is always the endmarker
+// $ that FOLLOW sets and parsing tables use for the end of the input.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Grammar {
+    terminals: Vec<String>,
+    nonterminals: Vec<String>,
+    productions: Vec<Production>,
+    start: usize,
+}
+
+impl Grammar {
+    pub const END: usize = 0;
+
+    // A grammar with no productions whose start symbol is start
+    pub fn new(start: &str) -> Self {
+        Grammar {
+            terminals: vec!["$".to_string()],
+            nonterminals: vec![start.to_string()],
+            productions: Vec::new(),
+            start: 0,
+        }
+    }
+
+    pub fn add_terminal(&mut self, name: &str) -> usize {
+        if let Some(terminal) = self.terminal_id(name) {
+            return terminal;
+        }
+        self.terminals.push(name.to_string());
+        self.terminals.len() - 1
+    }
+
+    pub fn add_nonterminal(&mut self, name: &str) -> usize {
+        if let Some(nonterminal) = self.nonterminal_id(name) {
+            return nonterminal;
+        }
+        self.nonterminals.push(name.to_string());
+        self.nonterminals.len() - 1
+    }
+
+    pub fn add_production(&mut self, head: usize, body: Vec<Symbol>) -> usize {
+        self.productions.push(Production { head, body });
+        self.productions.len() - 1
+    }
+
+    // The augmented grammar of section 4.5.3, with a new start symbol S' and
+    // S' := S as production 0. Symbols keep their order after S', so
+    // production i of this grammar is production i + 1 of the augmented one.
+    pub fn augmented(&self) -> Grammar {
+        let mut name = format!("{}'", self.nonterminals[self.start]);
+        while self.nonterminal_id(&name).is_some() || self.terminal_id(&name).is_some() {
+            name.push('\'');
+        }
+        let mut grammar = Grammar::new(&name);
+        for nonterminal in self.nonterminals.iter() {
+            grammar.add_nonterminal(nonterminal);
+        }
+        for terminal in self.terminals.iter() {
+            grammar.add_terminal(terminal);
+        }
+        grammar.add_production(0, vec![Symbol::Nonterminal(self.start + 1)]);
+        for production in self.productions.iter() {
+            let body = production
+                .body
+                .iter()
+                .map(|symbol| match symbol {
+                    Symbol::Terminal(terminal) => Symbol::Terminal(*terminal),
+                    Symbol::Nonterminal(nonterminal) => Symbol::Nonterminal(nonterminal + 1),
+                })
+                .collect();
+            grammar.add_production(production.head + 1, body);
+        }
+        grammar
+    }
+
+    pub fn start(&self) -> usize {
+        self.start
+    }
+
+    pub fn terminals(&self) -> &[String] {
+        &self.terminals
+    }
+
+    pub fn nonterminals(&self) -> &[String] {
+        &self.nonterminals
+    }
+
+    pub fn productions(&self) -> &[Production] {
+        &self.productions
+    }
+
+    pub fn production(&self, production: usize) -> &Production {
+        &self.productions[production]
+    }
+
+    // The indexes of the productions for head, in the order they were added
+    pub fn productions_of(&self, head: usize) -> Vec<usize> {
+        (0..self.productions.len())
+            .filter(|production| self.productions[*production].head == head)
+            .collect()
+    }
+
+    pub fn terminal_id(&self, name: &str) -> Option<usize> {
+        self.terminals.iter().position(|terminal| terminal == name)
+    }
+
+    pub fn nonterminal_id(&self, name: &str) -> Option<usize> {
+        self.nonterminals
+            .iter()
+            .position(|nonterminal| nonterminal == name)
+    }
+
+    pub fn name(&self, symbol: Symbol) -> &str {
+        match symbol {
+            Symbol::Terminal(terminal) => &self.terminals[terminal],
+            Symbol::Nonterminal(nonterminal) => &self.nonterminals[nonterminal],
+        }
+    }
+
+    // The terminals of a string whose every char is a terminal, the way the
+    // hand-written parsers of exercise 2.4.1 read their input, with their
+    // spans and the span of the end of the input. A char that is not a
+    // terminal is an error for the parser to report when it gets to it.
+    pub fn char_tokens(&self, input: &str) -> (Vec<SpannedTerminal<'_>>, Span) {
+        let input: Vec<char> = input.chars().collect();
+        let index = LineIndex::new(&input);
+        let tokens = input
+            .iter()
+            .enumerate()
+            .map(|(i, c)| {
+                let span = index.span(i, i + 1);
+                match self.terminal_id(&c.to_string()) {
+                    Some(terminal) if terminal != Grammar::END => Ok((terminal, span)),
+                    _ => Err(Diagnostic::new(
+                        ErrorKind::InvalidCharacter(*c),
+                        vec![],
+                        None,
+                        span,
+                    )),
+                }
+            })
+            .collect();
+        (tokens, index.span(input.len(), input.len()))
+    }
+
+    // A production as "head := body", with ε for an empty body
+    pub fn describe(&self, production: usize) -> String {
+        let production = &self.productions[production];
+        format!(
+            "{} := {}",
+            self.nonterminals[production.head],
+            self.describe_body(&production.body)
+        )
+    }
+
+    pub fn describe_body(&self, body: &[Symbol]) -> String {
+        if body.is_empty() {
+            return "ε".to_string();
+        }
+        let symbols: Vec<String> = body.iter().map(|symbol| self.quoted(*symbol)).collect();
+        symbols.join(" ")
+    }
+
+    // Terminals that would read back as something else are quoted
+    fn quoted(&self, symbol: Symbol) -> String {
+        let name = self.name(symbol);
+        let is_ambiguous = matches!(symbol, Symbol::Terminal(_))
+            && (self.nonterminal_id(name).is_some()
+                || matches!(name, "|" | ":=" | "->" | "→" | "ε")
+                || name.starts_with('\'')
+                || name.contains(|c: char| c.is_whitespace() || c == '|'));
+        if is_ambiguous {
+            format!("'{}'", name)
+        } else {
+            name.to_string()
+        }
+    }
+}
+
+// One line per nonterminal with its alternatives, in the BNF that
+// BnfParser reads. A nonterminal without productions has no line, since
+// that BNF cannot write one: "A :=" alone reads as A := ε. BnfParser never
+// makes such a nonterminal, but a grammar built or transformed in code may
+// have one, e.g. a start symbol that derives nothing, and then does not
+// round-trip.
+impl Display for Grammar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for head in 0..self.nonterminals.len() {
+            let productions = self.productions_of(head);
+            if productions.is_empty() {
+                continue;
+            }
+            let bodies: Vec<String> = productions
+                .iter()
+                .map(|production| self.describe_body(&self.productions[*production].body))
+                .collect();
+            writeln!(f, "{} := {}", self.nonterminals[head], bodies.join(" | "))?;
+        }
+        Ok(())
+    }
+}
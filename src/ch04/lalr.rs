@@ -0,0 +1,392 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+
+use super::first_follow::FirstFollow;
+use super::grammar::{Grammar, Symbol};
+use super::lr::LrTable;
+use super::lr0::{Item, Lr0Automaton};
+use super::lr1::{self, Lr1Automaton, Lr1Item};
+
+// The lookaheads of the kernel items of the LR(0) sets, as Algorithm 4.62
+// finds which of them are generated spontaneously and which propagate, and
+// Algorithm 4.63 passes them on until nothing changes (example 4.64)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LalrLookaheads {
+    automaton: Lr0Automaton,
+    // kernels[state]
+    kernels: Vec<Vec<Item>>,
+    // lookaheads[state][kernel item]
+    lookaheads: Vec<Vec<BTreeSet<usize>>>,
+    // propagation[state][kernel item]: the kernel items, as (state, kernel
+    // item), its lookaheads propagate to
+    propagation: Vec<Vec<Vec<(usize, usize)>>>,
+}
+
+impl LalrLookaheads {
+    pub fn new(grammar: &Grammar) -> Self {
+        let automaton = Lr0Automaton::new(grammar);
+        let grammar = automaton.grammar();
+        let sets = FirstFollow::new(grammar);
+        let kernels: Vec<Vec<Item>> = automaton
+            .states()
+            .iter()
+            .map(|items| {
+                items
+                    .iter()
+                    .copied()
+                    .filter(|item| item.is_kernel())
+                    .collect()
+            })
+            .collect();
+        let mut lookaheads: Vec<Vec<BTreeSet<usize>>> = kernels
+            .iter()
+            .map(|kernel| vec![BTreeSet::new(); kernel.len()])
+            .collect();
+        let mut propagation: Vec<Vec<Vec<(usize, usize)>>> = kernels
+            .iter()
+            .map(|kernel| vec![Vec::new(); kernel.len()])
+            .collect();
+
+        // The # of Algorithm 4.62, a lookahead that is no terminal
+        let marker = grammar.terminals().len();
+        lookaheads[0][0].insert(Grammar::END);
+        for (state, kernel) in kernels.iter().enumerate() {
+            for (k, item) in kernel.iter().enumerate() {
+                let start = BTreeSet::from([Lr1Item {
+                    core: *item,
+                    lookahead: marker,
+                }]);
+                for closed in lr1::closure(grammar, &sets, &start) {
+                    let Some(symbol) = closed.core.next_symbol(grammar) else {
+                        continue;
+                    };
+                    let target = automaton.goto(state, symbol).unwrap();
+                    let moved = Item {
+                        production: closed.core.production,
+                        dot: closed.core.dot + 1,
+                    };
+                    let index = kernels[target]
+                        .iter()
+                        .position(|kernel_item| *kernel_item == moved)
+                        .unwrap();
+                    if closed.lookahead == marker {
+                        if !propagation[state][k].contains(&(target, index)) {
+                            propagation[state][k].push((target, index));
+                        }
+                    } else {
+                        lookaheads[target][index].insert(closed.lookahead);
+                    }
+                }
+            }
+        }
+
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for state in 0..kernels.len() {
+                for k in 0..kernels[state].len() {
+                    for (target, index) in propagation[state][k].iter() {
+                        let passed = lookaheads[state][k].clone();
+                        let size = lookaheads[*target][*index].len();
+                        lookaheads[*target][*index].extend(passed);
+                        changed |= lookaheads[*target][*index].len() != size;
+                    }
+                }
+            }
+        }
+
+        LalrLookaheads {
+            automaton,
+            kernels,
+            lookaheads,
+            propagation,
+        }
+    }
+
+    pub fn automaton(&self) -> &Lr0Automaton {
+        &self.automaton
+    }
+
+    pub fn kernel(&self, state: usize) -> &[Item] {
+        &self.kernels[state]
+    }
+
+    pub fn lookaheads(&self, state: usize, kernel_item: usize) -> &BTreeSet<usize> {
+        &self.lookaheads[state][kernel_item]
+    }
+
+    pub fn propagates_to(&self, state: usize, kernel_item: usize) -> &[(usize, usize)] {
+        &self.propagation[state][kernel_item]
+    }
+
+    // The kernel of a set with its lookaheads, e.g. "L := * · R, =/$"
+    pub fn describe(&self, state: usize) -> Vec<String> {
+        let grammar = self.automaton.grammar();
+        self.kernels[state]
+            .iter()
+            .zip(self.lookaheads[state].iter())
+            .map(|(item, lookaheads)| {
+                let names: Vec<&str> = lookaheads
+                    .iter()
+                    .map(|terminal| grammar.terminals()[*terminal].as_str())
+                    .collect();
+                format!("{}, {}", item.describe(grammar), names.join("/"))
+            })
+            .collect()
+    }
+}
+
+impl LrTable {
+    // Algorithm 4.63: the states are the LR(0) sets, and the reductions
+    // come from closing their kernels with the lookaheads found for them
+    pub fn lalr(grammar: &Grammar) -> Self {
+        let lookaheads = LalrLookaheads::new(grammar);
+        let automaton = lookaheads.automaton();
+        let grammar = automaton.grammar();
+        let sets = FirstFollow::new(grammar);
+        let reductions: Vec<Vec<(usize, usize)>> = (0..automaton.states().len())
+            .map(|state| {
+                let mut kernel = BTreeSet::new();
+                for (k, item) in lookaheads.kernel(state).iter().enumerate() {
+                    for lookahead in lookaheads.lookaheads(state, k) {
+                        kernel.insert(Lr1Item {
+                            core: *item,
+                            lookahead: *lookahead,
+                        });
+                    }
+                }
+                lr1::reductions(grammar, &lr1::closure(grammar, &sets, &kernel))
+            })
+            .collect();
+
+        LrTable::new(grammar.clone(), automaton.transitions(), &reductions)
+    }
+
+    // Algorithm 4.59: the sets of LR(1) items with the same core are merged
+    // into one state. Merged states are numbered like the LR(0) set of
+    // their core, so the table comes out the same as LrTable::lalr's.
+    pub fn lalr_by_merging(grammar: &Grammar) -> Self {
+        let lr0 = Lr0Automaton::new(grammar);
+        let lr1 = Lr1Automaton::new(grammar);
+        let merged: Vec<usize> = (0..lr1.states().len())
+            .map(|state| {
+                let core = lr1.core(state);
+                lr0.states()
+                    .iter()
+                    .position(|items| *items == core)
+                    .unwrap()
+            })
+            .collect();
+
+        let mut transitions = vec![Vec::new(); lr0.states().len()];
+        let mut reductions = vec![Vec::new(); lr0.states().len()];
+        for (state, edges) in lr1.transitions().iter().enumerate() {
+            transitions[merged[state]] = edges
+                .iter()
+                .map(|(symbol, target)| (*symbol, merged[*target]))
+                .collect::<Vec<(Symbol, usize)>>();
+            reductions[merged[state]].extend(lr1::reductions(lr1.grammar(), &lr1.states()[state]));
+        }
+
+        LrTable::new(lr1.grammar().clone(), &transitions, &reductions)
+    }
+}
+
+// The SLR(1), LALR(1) and canonical LR(1) tables of a grammar, to compare
+// how many states each takes and how many conflicts each leaves
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TableComparison {
+    pub slr: LrTable,
+    pub lalr: LrTable,
+    pub canonical: LrTable,
+}
+
+impl TableComparison {
+    pub fn new(grammar: &Grammar) -> Self {
+        TableComparison {
+            slr: LrTable::slr(grammar),
+            lalr: LrTable::lalr(grammar),
+            canonical: LrTable::canonical_lr1(grammar),
+        }
+    }
+}
+
+// e.g. for grammar 4.49:
+//
+// TABLE    STATES  CONFLICTS
+// SLR(1)   10      1
+// LALR(1)  10      0
+// LR(1)    14      0
+impl Display for TableComparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "TABLE    STATES  CONFLICTS")?;
+        for (name, table) in [
+            ("SLR(1)", &self.slr),
+            ("LALR(1)", &self.lalr),
+            ("LR(1)", &self.canonical),
+        ] {
+            writeln!(
+                f,
+                "{:7}  {:<6}  {}",
+                name,
+                table.state_count(),
+                table.conflicts().len()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bnf::BnfParser;
+    use super::super::lr::LrParser;
+    use super::*;
+    use rstest::rstest;
+
+    // The grammars of exercise 2.4.1
+    const GRAMMAR_A: &str = "S := + S S | - S S | a";
+    const GRAMMAR_B: &str = "S := S ( S ) S | ε";
+    const GRAMMAR_C: &str = "S := 0 S 1 | 0 1";
+
+    // Grammar 4.49, assignments through pointers
+    const GRAMMAR_4_49: &str = "S := L = R | R\nL := * R | id\nR := L";
+
+    // Grammar 4.55
+    const GRAMMAR_4_55: &str = "S := C C\nC := c C | d";
+
+    // Example 4.58, LR(1) but not LALR(1)
+    const GRAMMAR_4_58: &str = "S := a A d | b B d | a B e | b A e\nA := c\nB := c";
+
+    const GRAMMAR_4_1: &str = "E := E + T | T\nT := T * F | F\nF := ( E ) | id";
+
+    fn grammar(input: &str) -> Grammar {
+        BnfParser::new(input).parse().unwrap()
+    }
+
+    // Figure 4.47
+    #[rstest]
+    #[case(0, vec!["S' := · S, $"])]
+    #[case(1, vec!["S' := S ·, $"])]
+    #[case(2, vec!["S := L · = R, $", "R := L ·, $"])]
+    #[case(3, vec!["S := R ·, $"])]
+    #[case(4, vec!["L := * · R, $/="])]
+    #[case(5, vec!["L := id ·, $/="])]
+    #[case(6, vec!["S := L = · R, $"])]
+    #[case(7, vec!["L := * R ·, $/="])]
+    #[case(8, vec!["R := L ·, $/="])]
+    #[case(9, vec!["S := L = R ·, $"])]
+    fn test_figure_4_47(#[case] state: usize, #[case] kernel: Vec<&str>) {
+        let lookaheads = LalrLookaheads::new(&grammar(GRAMMAR_4_49));
+
+        assert_eq!(lookaheads.describe(state), kernel);
+    }
+
+    // Figure 4.46: where the lookaheads of S' := · S in I0 and L := * · R
+    // in I4 propagate
+    #[rstest]
+    #[case(0, vec![(1, 0), (2, 0), (2, 1), (3, 0), (4, 0), (5, 0)])]
+    #[case(4, vec![(4, 0), (7, 0), (5, 0), (8, 0)])]
+    fn test_figure_4_46(#[case] state: usize, #[case] expected: Vec<(usize, usize)>) {
+        let lookaheads = LalrLookaheads::new(&grammar(GRAMMAR_4_49));
+
+        let mut propagates_to = lookaheads.propagates_to(state, 0).to_vec();
+        let mut expected = expected;
+        propagates_to.sort();
+        expected.sort();
+        assert_eq!(propagates_to, expected);
+    }
+
+    // Figure 4.43, with I36, I47 and I89 numbered 3, 4 and 6
+    #[rstest]
+    fn test_figure_4_43() {
+        let table = LrTable::lalr(&grammar(GRAMMAR_4_55));
+
+        assert_eq!(
+            table.to_string(),
+            [
+                "STATE  c   d   $    S  C",
+                "0      s3  s4       1  2",
+                "1              acc",
+                "2      s3  s4          5",
+                "3      s3  s4          6",
+                "4      r3  r3  r3",
+                "5              r1",
+                "6      r2  r2  r2",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[rstest]
+    #[case(GRAMMAR_A)]
+    #[case(GRAMMAR_B)]
+    #[case(GRAMMAR_C)]
+    #[case(GRAMMAR_4_1)]
+    #[case(GRAMMAR_4_49)]
+    #[case(GRAMMAR_4_55)]
+    #[case(GRAMMAR_4_58)]
+    fn test_propagation_matches_merging(#[case] input: &str) {
+        let grammar = grammar(input);
+
+        assert_eq!(LrTable::lalr(&grammar), LrTable::lalr_by_merging(&grammar));
+    }
+
+    #[rstest]
+    fn test_merging_introduces_reduce_reduce_conflict() {
+        let comparison = TableComparison::new(&grammar(GRAMMAR_4_58));
+        let table = &comparison.lalr;
+
+        assert!(comparison.canonical.conflicts().is_empty());
+        assert_eq!(
+            table
+                .conflicts()
+                .iter()
+                .map(|conflict| table.explain(conflict))
+                .collect::<Vec<_>>(),
+            vec![
+                "reduce/reduce conflict in state 6 on d: reduce by A := c or reduce by B := c; using reduce by A := c",
+                "reduce/reduce conflict in state 6 on e: reduce by A := c or reduce by B := c; using reduce by A := c",
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(
+        GRAMMAR_4_49,
+        "TABLE    STATES  CONFLICTS\n\
+         SLR(1)   10      1\n\
+         LALR(1)  10      0\n\
+         LR(1)    14      0\n"
+    )]
+    #[case(
+        GRAMMAR_4_55,
+        "TABLE    STATES  CONFLICTS\n\
+         SLR(1)   7       0\n\
+         LALR(1)  7       0\n\
+         LR(1)    10      0\n"
+    )]
+    #[case(
+        GRAMMAR_B,
+        "TABLE    STATES  CONFLICTS\n\
+         SLR(1)   6       1\n\
+         LALR(1)  6       1\n\
+         LR(1)    10      2\n"
+    )]
+    fn test_table_comparison(#[case] input: &str, #[case] expected: &str) {
+        assert_eq!(TableComparison::new(&grammar(input)).to_string(), expected);
+    }
+
+    #[rstest]
+    #[case(GRAMMAR_4_49, vec!["*", "id", "=", "id"], true)]
+    #[case(GRAMMAR_4_49, vec!["id", "=", "=", "id"], false)]
+    #[case(GRAMMAR_4_58, vec!["b", "c", "e"], true)]
+    #[case(GRAMMAR_B, vec!["(", ")", "(", "(", ")", ")"], true)]
+    fn test_parse(#[case] input: &str, #[case] tokens: Vec<&str>, #[case] accepted: bool) {
+        let table = LrTable::lalr(&grammar(input));
+
+        let trace = LrParser::new(&table).trace(&tokens).unwrap();
+        assert_eq!(trace.ends_with("accept\n"), accepted);
+    }
+}
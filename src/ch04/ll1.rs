@@ -0,0 +1,483 @@
+use std::fmt::{self, Display};
+
+use super::columns::write_columns;
+use super::first_follow::FirstFollow;
+use super::grammar::{Grammar, Name, Symbol};
+use crate::ch02::lexical_analyzer::span::Span;
+use crate::ch02::recursive_descent_parser::diagnostic::{Diagnostic, ErrorKind};
+use crate::ch02::recursive_descent_parser::parse_tree::ParseTree;
+
+// An entry M[nonterminal, terminal] holding more than one production, which
+// makes the grammar not LL(1)
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Ll1Conflict {
+    pub nonterminal: usize,
+    pub terminal: usize,
+    pub productions: Vec<usize>,
+}
+
+// The predictive parsing table M of section 4.4.3, built by Algorithm 4.31.
+// Every entry keeps all the productions the algorithm puts in it, so a
+// grammar that is not LL(1) still gets a table, whose conflicts say why.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Ll1Table {
+    grammar: Grammar,
+    // entries[nonterminal][terminal]
+    entries: Vec<Vec<Vec<usize>>>,
+}
+
+impl Ll1Table {
+    // For each production A → α, A → α goes in M[A, a] for each terminal a
+    // in FIRST(α), and if α derives ε, in M[A, b] for each b in FOLLOW(A)
+    pub fn new(grammar: &Grammar) -> Self {
+        let sets = FirstFollow::new(grammar);
+        let mut entries =
+            vec![vec![Vec::new(); grammar.terminals().len()]; grammar.nonterminals().len()];
+        for (i, production) in grammar.productions().iter().enumerate() {
+            let (mut lookaheads, nullable) = sets.first_of(&production.body);
+            if nullable {
+                lookaheads.extend(sets.follow(production.head).iter().copied());
+            }
+            for terminal in lookaheads {
+                entries[production.head][terminal].push(i);
+            }
+        }
+
+        Ll1Table {
+            grammar: grammar.clone(),
+            entries,
+        }
+    }
+
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    // The productions in M[nonterminal, terminal]; none means an error entry
+    pub fn entry(&self, nonterminal: usize, terminal: usize) -> &[usize] {
+        &self.entries[nonterminal][terminal]
+    }
+
+    pub fn conflicts(&self) -> Vec<Ll1Conflict> {
+        let mut conflicts = Vec::new();
+        for (nonterminal, row) in self.entries.iter().enumerate() {
+            for (terminal, productions) in row.iter().enumerate() {
+                if productions.len() > 1 {
+                    conflicts.push(Ll1Conflict {
+                        nonterminal,
+                        terminal,
+                        productions: productions.clone(),
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+
+    pub fn is_ll1(&self) -> bool {
+        self.conflicts().is_empty()
+    }
+
+    // e.g. "M[S, (] = S := S ( S ) S, S := ε"
+    pub fn explain(&self, conflict: &Ll1Conflict) -> String {
+        let productions: Vec<String> = conflict
+            .productions
+            .iter()
+            .map(|production| self.grammar.describe(*production))
+            .collect();
+        format!(
+            "M[{}, {}] = {}",
+            self.grammar.nonterminals()[conflict.nonterminal],
+            self.grammar.terminals()[conflict.terminal],
+            productions.join(", ")
+        )
+    }
+
+    // The terminals whose entries for nonterminal are not errors, i.e. the
+    // lookaheads a parser expanding it can accept
+    fn expected(&self, nonterminal: usize) -> Vec<usize> {
+        (0..self.grammar.terminals().len())
+            .filter(|terminal| !self.entries[nonterminal][*terminal].is_empty())
+            .collect()
+    }
+
+    // Terminals in the order they were added with $ last, as in figure 4.17
+    fn columns(&self) -> Vec<usize> {
+        let mut columns: Vec<usize> = (1..self.grammar.terminals().len()).collect();
+        columns.push(Grammar::END);
+        columns
+    }
+}
+
+// Laid out like figure 4.17, one row per nonterminal and one column per
+// terminal, with blank error entries:
+//
+// NONTERMINAL  +             *             (           )        id         $
+// E                                        E := T E'            E := T E'
+impl Display for Ll1Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let columns = self.columns();
+        let mut rows = vec![std::iter::once("NONTERMINAL".to_string())
+            .chain(
+                columns
+                    .iter()
+                    .map(|terminal| self.grammar.terminals()[*terminal].clone()),
+            )
+            .collect::<Vec<_>>()];
+        for (nonterminal, name) in self.grammar.nonterminals().iter().enumerate() {
+            let mut row = vec![name.clone()];
+            for terminal in columns.iter() {
+                let productions: Vec<String> = self.entries[nonterminal][*terminal]
+                    .iter()
+                    .map(|production| self.grammar.describe(*production))
+                    .collect();
+                row.push(productions.join(", "));
+            }
+            rows.push(row);
+        }
+        write_columns(f, &rows)
+    }
+}
+
+// A table-driven predictive parser (section 4.4.4), parsing with a stack of
+// grammar symbols instead of one procedure per nonterminal
+pub struct PredictiveParser<'t> {
+    table: &'t Ll1Table,
+}
+
+impl<'t> PredictiveParser<'t> {
+    // A table with conflicts does not say which production to use, so it
+    // cannot drive a parser
+    pub fn new(table: &'t Ll1Table) -> Result<Self, Vec<Ll1Conflict>> {
+        let conflicts = table.conflicts();
+        if !conflicts.is_empty() {
+            return Err(conflicts);
+        }
+        Ok(PredictiveParser { table })
+    }
+
+    // Parses a string whose every char is a terminal of the grammar, the way
+    // the hand-written parsers of exercise 2.4.1 read their input. A char
+    // that is not a terminal is reported when the parser gets to it.
+    pub fn parse_chars(
+        &self,
+        input: &str,
+    ) -> Result<ParseTree<Name<'t>, Name<'t>>, Diagnostic<Name<'t>>> {
+        let (tokens, end_of_input) = self.table.grammar().char_tokens(input);
+        self.parse(tokens, end_of_input)
+    }
+
+    // Algorithm 4.34 over a stream of terminals with their spans. Each stack
+    // entry is a node of the tree being built, so that expanding a
+    // nonterminal also fills in its children.
+    pub fn parse(
+        &self,
+        tokens: impl IntoIterator<Item = Result<(usize, Span), Diagnostic<Name<'t>>>>,
+        end_of_input: Span,
+    ) -> Result<ParseTree<Name<'t>, Name<'t>>, Diagnostic<Name<'t>>> {
+        let grammar = self.table.grammar();
+        let mut tokens = tokens.into_iter();
+        let mut next_token = || tokens.next().unwrap_or(Ok((Grammar::END, end_of_input)));
+
+        // None stands for an ε leaf
+        let mut nodes: Vec<(Option<Symbol>, Vec<usize>)> =
+            vec![(Some(Symbol::Nonterminal(grammar.start())), Vec::new())];
+        let mut stack = vec![0];
+        let (mut lookahead, mut span) = next_token()?;
+        while let Some(node) = stack.pop() {
+            match nodes[node].0 {
+                Some(Symbol::Terminal(terminal)) if terminal == lookahead => {
+                    (lookahead, span) = next_token()?;
+                }
+                Some(Symbol::Terminal(terminal)) => {
+                    return Err(self.unexpected(lookahead, span, vec![terminal]));
+                }
+                Some(Symbol::Nonterminal(nonterminal)) => {
+                    let Some(&production) = self.table.entry(nonterminal, lookahead).first() else {
+                        let expected = self.table.expected(nonterminal);
+                        return Err(self.unexpected(lookahead, span, expected));
+                    };
+                    let body = &grammar.production(production).body;
+                    let children: Vec<usize> = if body.is_empty() {
+                        nodes.push((None, Vec::new()));
+                        vec![nodes.len() - 1]
+                    } else {
+                        body.iter()
+                            .map(|symbol| {
+                                nodes.push((Some(*symbol), Vec::new()));
+                                nodes.len() - 1
+                            })
+                            .collect()
+                    };
+                    if !body.is_empty() {
+                        stack.extend(children.iter().rev());
+                    }
+                    nodes[node].1 = children;
+                }
+                None => unreachable!("ε leaves are never pushed"),
+            }
+        }
+
+        if lookahead != Grammar::END {
+            return Err(Diagnostic::new(
+                ErrorKind::TrailingInput,
+                vec![],
+                Some(self.name(Symbol::Terminal(lookahead))),
+                span,
+            ));
+        }
+        Ok(self.tree(&nodes, 0))
+    }
+
+    fn unexpected(
+        &self,
+        lookahead: usize,
+        span: Span,
+        expected: Vec<usize>,
+    ) -> Diagnostic<Name<'t>> {
+        let expected = expected
+            .into_iter()
+            .map(|terminal| self.name(Symbol::Terminal(terminal)))
+            .collect();
+        if lookahead == Grammar::END {
+            Diagnostic::new(ErrorKind::UnexpectedEndOfInput, expected, None, span)
+        } else {
+            let found = self.name(Symbol::Terminal(lookahead));
+            Diagnostic::new(ErrorKind::UnexpectedToken, expected, Some(found), span)
+        }
+    }
+
+    fn tree(
+        &self,
+        nodes: &[(Option<Symbol>, Vec<usize>)],
+        node: usize,
+    ) -> ParseTree<Name<'t>, Name<'t>> {
+        match nodes[node] {
+            (Some(Symbol::Nonterminal(nonterminal)), ref children) => ParseTree::Node(
+                self.name(Symbol::Nonterminal(nonterminal)),
+                children
+                    .iter()
+                    .map(|child| self.tree(nodes, *child))
+                    .collect(),
+            ),
+            (Some(terminal), _) => ParseTree::Leaf(self.name(terminal)),
+            (None, _) => ParseTree::Leaf(Name("ε")),
+        }
+    }
+
+    fn name(&self, symbol: Symbol) -> Name<'t> {
+        Name(self.table.grammar().name(symbol))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bnf::BnfParser;
+    use super::super::parser_tests::outcome;
+    use super::*;
+    use rstest::rstest;
+
+    // The grammars of exercise 2.4.1 as data. Grammar b is parsed through
+    // the same rewrite ParserB uses, and grammar c is left factored; the
+    // grammars as the exercise gives them are not LL(1).
+    const GRAMMAR_A: &str = "S := + S S | - S S | a";
+    const GRAMMAR_B: &str = "S := R\nR := ( S ) S | ε";
+    const GRAMMAR_C: &str = "S := 0 T\nT := S 1 | 1";
+
+    fn table(input: &str) -> Ll1Table {
+        Ll1Table::new(&BnfParser::new(input).parse().unwrap())
+    }
+
+    fn parse(grammar: &str, input: &str) -> Result<String, (ErrorKind, usize)> {
+        let table = table(grammar);
+        outcome(PredictiveParser::new(&table).unwrap().parse_chars(input))
+    }
+
+    #[rstest]
+    #[case(GRAMMAR_A, "a")]
+    #[case(GRAMMAR_A, "+aa")]
+    #[case(GRAMMAR_A, "++++aaaaa")]
+    #[case(GRAMMAR_A, "+-aaa")]
+    #[case(GRAMMAR_A, "+++--+-+-+-+-+++-a-aaaaaaaaaaaaaaaaaa")]
+    #[case(GRAMMAR_A, "-aa")]
+    #[case(GRAMMAR_B, "")]
+    #[case(GRAMMAR_B, "()")]
+    #[case(GRAMMAR_B, "()()()")]
+    #[case(GRAMMAR_B, "(()())()(())")]
+    #[case(GRAMMAR_B, "(())()")]
+    #[case(GRAMMAR_B, "(())(())(())")]
+    #[case(GRAMMAR_C, "01")]
+    #[case(GRAMMAR_C, "0011")]
+    #[case(GRAMMAR_C, "00000000001111111111")]
+    fn test_parse_valid(#[case] grammar: &str, #[case] input: &str) {
+        assert_eq!(parse(grammar, input), Ok(input.to_string()));
+    }
+
+    #[rstest]
+    #[case(GRAMMAR_A, "++aa", ErrorKind::UnexpectedEndOfInput, 4)]
+    #[case(GRAMMAR_A, "+a", ErrorKind::UnexpectedEndOfInput, 2)]
+    #[case(GRAMMAR_A, "--aa", ErrorKind::UnexpectedEndOfInput, 4)]
+    #[case(GRAMMAR_A, "-a", ErrorKind::UnexpectedEndOfInput, 2)]
+    #[case(GRAMMAR_A, "+ab", ErrorKind::InvalidCharacter('b'), 2)]
+    #[case(GRAMMAR_A, "+ba", ErrorKind::InvalidCharacter('b'), 1)]
+    #[case(GRAMMAR_A, "-ab", ErrorKind::InvalidCharacter('b'), 2)]
+    #[case(GRAMMAR_A, "-ba", ErrorKind::InvalidCharacter('b'), 1)]
+    #[case(GRAMMAR_A, "ab", ErrorKind::InvalidCharacter('b'), 1)]
+    #[case(GRAMMAR_A, "b", ErrorKind::InvalidCharacter('b'), 0)]
+    #[case(GRAMMAR_A, "aa", ErrorKind::TrailingInput, 1)]
+    #[case(GRAMMAR_B, "a", ErrorKind::InvalidCharacter('a'), 0)]
+    #[case(GRAMMAR_B, "(a)", ErrorKind::InvalidCharacter('a'), 1)]
+    #[case(GRAMMAR_B, "(", ErrorKind::UnexpectedEndOfInput, 1)]
+    #[case(GRAMMAR_B, "(()", ErrorKind::UnexpectedEndOfInput, 3)]
+    #[case(GRAMMAR_B, ")", ErrorKind::TrailingInput, 0)]
+    #[case(GRAMMAR_B, ")(", ErrorKind::TrailingInput, 0)]
+    #[case(GRAMMAR_B, "())", ErrorKind::TrailingInput, 2)]
+    #[case(GRAMMAR_B, "()())", ErrorKind::TrailingInput, 4)]
+    #[case(GRAMMAR_C, "0", ErrorKind::UnexpectedEndOfInput, 1)]
+    #[case(GRAMMAR_C, "", ErrorKind::UnexpectedEndOfInput, 0)]
+    #[case(GRAMMAR_C, "001", ErrorKind::UnexpectedEndOfInput, 3)]
+    #[case(GRAMMAR_C, "1", ErrorKind::UnexpectedToken, 0)]
+    #[case(GRAMMAR_C, "10", ErrorKind::UnexpectedToken, 0)]
+    #[case(GRAMMAR_C, "010", ErrorKind::TrailingInput, 2)]
+    #[case(GRAMMAR_C, "00110", ErrorKind::TrailingInput, 4)]
+    #[case(GRAMMAR_C, "a", ErrorKind::InvalidCharacter('a'), 0)]
+    #[case(GRAMMAR_C, "01a", ErrorKind::InvalidCharacter('a'), 2)]
+    fn test_parse_invalid(
+        #[case] grammar: &str,
+        #[case] input: &str,
+        #[case] kind: ErrorKind,
+        #[case] offset: usize,
+    ) {
+        assert_eq!(parse(grammar, input), Err((kind, offset)));
+    }
+
+    #[rstest]
+    #[case(GRAMMAR_A, "+a", vec!["+", "-", "a"], "expected one of +, -, a, found end of input")]
+    #[case(GRAMMAR_B, "(()", vec![")"], "expected ), found end of input")]
+    #[case(GRAMMAR_C, "1", vec!["0"], "expected 0, found 1")]
+    fn test_parse_expected(
+        #[case] grammar: &str,
+        #[case] input: &str,
+        #[case] expected: Vec<&str>,
+        #[case] message: &str,
+    ) {
+        let table = table(grammar);
+        let error = PredictiveParser::new(&table)
+            .unwrap()
+            .parse_chars(input)
+            .unwrap_err();
+
+        assert_eq!(
+            error.expected.iter().map(|name| name.0).collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(error.to_string(), message);
+    }
+
+    #[rstest]
+    fn test_parse_tree() {
+        let table = table(GRAMMAR_A);
+        let tree = PredictiveParser::new(&table)
+            .unwrap()
+            .parse_chars("+-aaa")
+            .unwrap();
+
+        assert_eq!(
+            tree.pretty_print(),
+            [
+                "S",
+                "├── +",
+                "├── S",
+                "│   ├── -",
+                "│   ├── S",
+                "│   │   └── a",
+                "│   └── S",
+                "│       └── a",
+                "└── S",
+                "    └── a",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[rstest]
+    fn test_parse_tree_with_epsilon() {
+        let table = table(GRAMMAR_B);
+        let tree = PredictiveParser::new(&table)
+            .unwrap()
+            .parse_chars("()")
+            .unwrap();
+
+        assert_eq!(
+            tree,
+            ParseTree::Node(
+                Name("S"),
+                vec![ParseTree::Node(
+                    Name("R"),
+                    vec![
+                        ParseTree::Leaf(Name("(")),
+                        ParseTree::Node(
+                            Name("S"),
+                            vec![ParseTree::Node(Name("R"), vec![ParseTree::Leaf(Name("ε"))])]
+                        ),
+                        ParseTree::Leaf(Name(")")),
+                        ParseTree::Node(
+                            Name("S"),
+                            vec![ParseTree::Node(Name("R"), vec![ParseTree::Leaf(Name("ε"))])]
+                        ),
+                    ]
+                )]
+            )
+        );
+    }
+
+    #[rstest]
+    #[case("S := S ( S ) S | ε", vec!["M[S, (] = S := S ( S ) S, S := ε"])]
+    #[case("S := 0 S 1 | 0 1", vec!["M[S, 0] = S := 0 S 1, S := 0 1"])]
+    #[case(
+        "S := i E t S S' | a\nS' := e S | ε\nE := b",
+        vec!["M[S', e] = S' := e S, S' := ε"]
+    )]
+    fn test_conflicts(#[case] grammar: &str, #[case] expected: Vec<&str>) {
+        let table = table(grammar);
+
+        let conflicts = table.conflicts();
+        assert!(!table.is_ll1());
+        assert_eq!(
+            conflicts
+                .iter()
+                .map(|conflict| table.explain(conflict))
+                .collect::<Vec<_>>(),
+            expected
+        );
+        assert_eq!(PredictiveParser::new(&table).err(), Some(conflicts));
+    }
+
+    // Figure 4.17, for grammar 4.28
+    #[rstest]
+    fn test_figure_4_17() {
+        let table = table(
+            "E := T E'\n\
+             E' := + T E' | ε\n\
+             T := F T'\n\
+             T' := * F T' | ε\n\
+             F := ( E ) | id",
+        );
+
+        assert!(table.is_ll1());
+        assert_eq!(
+            table.to_string(),
+            [
+                "NONTERMINAL  +             *             (           )        id         $",
+                "E                                        E := T E'            E := T E'",
+                "E'           E' := + T E'                            E' := ε             E' := ε",
+                "T                                        T := F T'            T := F T'",
+                "T'           T' := ε       T' := * F T'              T' := ε             T' := ε",
+                "F                                        F := ( E )           F := id",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
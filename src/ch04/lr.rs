@@ -0,0 +1,507 @@
+use std::fmt::{self, Display};
+
+use super::columns::write_columns;
+use super::grammar::{Grammar, Name, Symbol};
+use crate::ch02::lexical_analyzer::span::{LineIndex, Span};
+use crate::ch02::recursive_descent_parser::diagnostic::{Diagnostic, ErrorKind};
+use crate::ch02::recursive_descent_parser::parse_tree::ParseTree;
+
+// An ACTION entry; error entries are left empty. Productions are those of
+// the augmented grammar.
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Action {
+    Accept,
+    Shift(usize),
+    Reduce(usize),
+}
+
+// An ACTION entry the construction put more than one action in. The first
+// of actions is the one the table uses.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LrConflict {
+    pub state: usize,
+    pub terminal: usize,
+    pub actions: Vec<Action>,
+}
+
+// The ACTION and GOTO tables of an LR parser (section 4.6.3), over the
+// augmented grammar. How states and reductions are found is up to the
+// construction (SLR, canonical LR or LALR); conflicts are resolved the way
+// yacc resolves them, shifting rather than reducing and reducing by the
+// production that comes first, and kept for conflicts() to report.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LrTable {
+    grammar: Grammar,
+    // actions[state][terminal], sorted so that the action used comes first
+    actions: Vec<Vec<Vec<Action>>>,
+    // gotos[state][nonterminal]
+    gotos: Vec<Vec<Option<usize>>>,
+}
+
+impl LrTable {
+    // grammar is augmented, transitions are the GOTO function on the sets of
+    // items and reductions[state] pairs each lookahead with a production to
+    // reduce by. A reduction by S' := S becomes accept.
+    pub fn new(
+        grammar: Grammar,
+        transitions: &[Vec<(Symbol, usize)>],
+        reductions: &[Vec<(usize, usize)>],
+    ) -> Self {
+        let states = transitions.len();
+        let mut actions = vec![vec![Vec::new(); grammar.terminals().len()]; states];
+        let mut gotos = vec![vec![None; grammar.nonterminals().len()]; states];
+        for (state, edges) in transitions.iter().enumerate() {
+            for (symbol, target) in edges.iter() {
+                match symbol {
+                    Symbol::Terminal(terminal) => {
+                        actions[state][*terminal].push(Action::Shift(*target))
+                    }
+                    Symbol::Nonterminal(nonterminal) => gotos[state][*nonterminal] = Some(*target),
+                }
+            }
+        }
+        for (state, reductions) in reductions.iter().enumerate() {
+            for (terminal, production) in reductions.iter() {
+                let action = match production {
+                    0 => Action::Accept,
+                    production => Action::Reduce(*production),
+                };
+                if !actions[state][*terminal].contains(&action) {
+                    actions[state][*terminal].push(action);
+                }
+            }
+        }
+        for entry in actions.iter_mut().flatten() {
+            entry.sort();
+        }
+
+        LrTable {
+            grammar,
+            actions,
+            gotos,
+        }
+    }
+
+    // The augmented grammar
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.actions.len()
+    }
+
+    pub fn action(&self, state: usize, terminal: usize) -> Option<Action> {
+        self.actions[state][terminal].first().copied()
+    }
+
+    pub fn goto(&self, state: usize, nonterminal: usize) -> Option<usize> {
+        self.gotos[state][nonterminal]
+    }
+
+    pub fn conflicts(&self) -> Vec<LrConflict> {
+        let mut conflicts = Vec::new();
+        for (state, row) in self.actions.iter().enumerate() {
+            for (terminal, actions) in row.iter().enumerate() {
+                if actions.len() > 1 {
+                    conflicts.push(LrConflict {
+                        state,
+                        terminal,
+                        actions: actions.clone(),
+                    });
+                }
+            }
+        }
+        conflicts
+    }
+
+    // e.g. "shift/reduce conflict in state 5 on (: shift 2 or reduce by
+    // S := S ( S ) S; using shift 2"
+    pub fn explain(&self, conflict: &LrConflict) -> String {
+        let kind = if conflict
+            .actions
+            .iter()
+            .any(|action| matches!(action, Action::Shift(_)))
+        {
+            "shift/reduce"
+        } else {
+            "reduce/reduce"
+        };
+        let actions: Vec<String> = conflict
+            .actions
+            .iter()
+            .map(|action| self.describe(*action))
+            .collect();
+        format!(
+            "{} conflict in state {} on {}: {}; using {}",
+            kind,
+            conflict.state,
+            self.grammar.terminals()[conflict.terminal],
+            actions.join(" or "),
+            actions[0]
+        )
+    }
+
+    fn describe(&self, action: Action) -> String {
+        match action {
+            Action::Accept => "accept".to_string(),
+            Action::Shift(state) => format!("shift {}", state),
+            Action::Reduce(production) => {
+                format!("reduce by {}", self.grammar.describe(production))
+            }
+        }
+    }
+
+    // The terminals a state has an action for
+    fn expected(&self, state: usize) -> Vec<usize> {
+        (0..self.grammar.terminals().len())
+            .filter(|terminal| !self.actions[state][*terminal].is_empty())
+            .collect()
+    }
+}
+
+// Laid out like figure 4.37, with productions numbered as in the augmented
+// grammar, so that production i of the grammar is reduced by ri+1:
+//
+// STATE  +   *   (   )    id  $    E  T  F
+// 0              s4       s5       1  2  3
+// 1      s6                   acc
+//
+// An entry with a conflict lists all its actions, e.g. s2/r1.
+impl Display for LrTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let terminals: Vec<usize> = (1..self.grammar.terminals().len())
+            .chain([Grammar::END])
+            .collect();
+        let nonterminals = 1..self.grammar.nonterminals().len();
+
+        let mut header = vec!["STATE".to_string()];
+        header.extend(
+            terminals
+                .iter()
+                .map(|terminal| self.grammar.terminals()[*terminal].clone()),
+        );
+        header.extend(
+            nonterminals
+                .clone()
+                .map(|nonterminal| self.grammar.nonterminals()[nonterminal].clone()),
+        );
+        let mut rows = vec![header];
+        for state in 0..self.state_count() {
+            let mut row = vec![state.to_string()];
+            for terminal in terminals.iter() {
+                let actions: Vec<String> = self.actions[state][*terminal]
+                    .iter()
+                    .map(|action| match action {
+                        Action::Accept => "acc".to_string(),
+                        Action::Shift(state) => format!("s{}", state),
+                        Action::Reduce(production) => format!("r{}", production),
+                    })
+                    .collect();
+                row.push(actions.join("/"));
+            }
+            for nonterminal in nonterminals.clone() {
+                row.push(
+                    self.gotos[state][nonterminal]
+                        .map(|target| target.to_string())
+                        .unwrap_or_default(),
+                );
+            }
+            rows.push(row);
+        }
+        write_columns(f, &rows)
+    }
+}
+
+// The LR-parsing algorithm (Algorithm 4.44), driven by any LrTable
+pub struct LrParser<'t> {
+    table: &'t LrTable,
+}
+
+impl<'t> LrParser<'t> {
+    pub fn new(table: &'t LrTable) -> Self {
+        LrParser { table }
+    }
+
+    // Parses a string whose every char is a terminal of the grammar, like
+    // PredictiveParser::parse_chars
+    pub fn parse_chars(
+        &self,
+        input: &str,
+    ) -> Result<ParseTree<Name<'t>, Name<'t>>, Diagnostic<Name<'t>>> {
+        let (tokens, end_of_input) = self.table.grammar().char_tokens(input);
+        self.parse(tokens, end_of_input)
+    }
+
+    pub fn parse(
+        &self,
+        tokens: impl IntoIterator<Item = Result<(usize, Span), Diagnostic<Name<'t>>>>,
+        end_of_input: Span,
+    ) -> Result<ParseTree<Name<'t>, Name<'t>>, Diagnostic<Name<'t>>> {
+        self.run(tokens, end_of_input, |_, _, _, _| {})
+    }
+
+    // The moves of the parser on a string of terminal names as rows of
+    // figure 4.38. A name that is not a terminal is an error up front; a
+    // syntax error ends the trace with an error row.
+    //
+    // LINE  STACK  SYMBOLS  INPUT           ACTION
+    // (1)   0               id * id + id $  shift
+    // (2)   0 5    id       * id + id $     reduce by F := id
+    pub fn trace(&self, input: &[&'t str]) -> Result<String, Diagnostic<Name<'t>>> {
+        let grammar = self.table.grammar();
+        // Spans are those of the names joined with spaces
+        let text: Vec<char> = input.join(" ").chars().collect();
+        let index = LineIndex::new(&text);
+        let mut tokens = Vec::with_capacity(input.len());
+        let mut start = 0;
+        for name in input.iter() {
+            let end = start + name.chars().count();
+            let span = index.span(start, end);
+            match grammar.terminal_id(name) {
+                Some(terminal) if terminal != Grammar::END => tokens.push((terminal, span)),
+                _ => {
+                    let terminals = grammar.terminals().iter().skip(1);
+                    return Err(Diagnostic::new(
+                        ErrorKind::UnexpectedToken,
+                        terminals.map(|terminal| Name(terminal)).collect(),
+                        Some(Name(name)),
+                        span,
+                    )
+                    .with_note(&format!("{} is not a terminal", name)));
+                }
+            }
+            start = end + 1;
+        }
+
+        let mut rows = vec![[
+            "LINE".to_string(),
+            "STACK".to_string(),
+            "SYMBOLS".to_string(),
+            "INPUT".to_string(),
+            "ACTION".to_string(),
+        ]];
+        let result = self.run(
+            tokens.into_iter().map(Ok),
+            index.span(text.len(), text.len()),
+            |states, symbols, consumed, action| {
+                let states: Vec<String> = states.iter().map(|state| state.to_string()).collect();
+                let symbols: Vec<&str> =
+                    symbols.iter().map(|symbol| grammar.name(*symbol)).collect();
+                let mut remaining: Vec<&str> = input[consumed..].to_vec();
+                remaining.push("$");
+                rows.push([
+                    format!("({})", rows.len()),
+                    states.join(" "),
+                    symbols.join(" "),
+                    remaining.join(" "),
+                    match action {
+                        Some(action) => self.table.describe(action),
+                        None => "error".to_string(),
+                    },
+                ]);
+            },
+        );
+        if let Err(error) = result {
+            rows.last_mut().unwrap()[4] = format!("error: {}", error);
+        }
+
+        let mut trace = String::new();
+        write_columns(&mut trace, &rows).unwrap();
+        Ok(trace)
+    }
+
+    // The stack holds states, and alongside it the grammar symbols and the
+    // parse trees they stand for. observe sees the stack, the symbols and
+    // how many tokens were consumed before each move, with None for an
+    // error entry.
+    fn run(
+        &self,
+        tokens: impl IntoIterator<Item = Result<(usize, Span), Diagnostic<Name<'t>>>>,
+        end_of_input: Span,
+        mut observe: impl FnMut(&[usize], &[Symbol], usize, Option<Action>),
+    ) -> Result<ParseTree<Name<'t>, Name<'t>>, Diagnostic<Name<'t>>> {
+        let grammar = self.table.grammar();
+        let mut tokens = tokens.into_iter();
+        let mut next_token = || tokens.next().unwrap_or(Ok((Grammar::END, end_of_input)));
+
+        let mut states = vec![0];
+        let mut symbols = Vec::new();
+        let mut trees: Vec<ParseTree<Name<'t>, Name<'t>>> = Vec::new();
+        let mut consumed = 0;
+        let (mut lookahead, mut span) = next_token()?;
+        loop {
+            let state = *states.last().unwrap();
+            let action = self.table.action(state, lookahead);
+            observe(&states, &symbols, consumed, action);
+            match action {
+                Some(Action::Shift(target)) => {
+                    states.push(target);
+                    symbols.push(Symbol::Terminal(lookahead));
+                    trees.push(ParseTree::Leaf(self.name(Symbol::Terminal(lookahead))));
+                    (lookahead, span) = next_token()?;
+                    consumed += 1;
+                }
+                Some(Action::Reduce(production)) => {
+                    let production = grammar.production(production);
+                    let length = production.body.len();
+                    states.truncate(states.len() - length);
+                    symbols.truncate(symbols.len() - length);
+                    let mut children = trees.split_off(trees.len() - length);
+                    if children.is_empty() {
+                        children.push(ParseTree::Leaf(Name("ε")));
+                    }
+                    let state = *states.last().unwrap();
+                    let target = self
+                        .table
+                        .goto(state, production.head)
+                        .expect("every reduction has a GOTO entry");
+                    states.push(target);
+                    symbols.push(Symbol::Nonterminal(production.head));
+                    trees.push(ParseTree::Node(
+                        self.name(Symbol::Nonterminal(production.head)),
+                        children,
+                    ));
+                }
+                Some(Action::Accept) => return Ok(trees.pop().unwrap()),
+                None => return Err(self.error(&states, lookahead, span)),
+            }
+        }
+    }
+
+    // When the input read so far is already a sentence, i.e. the parser
+    // would reduce all the way to accept on $, what follows is trailing input
+    fn error(&self, states: &[usize], lookahead: usize, span: Span) -> Diagnostic<Name<'t>> {
+        let state = *states.last().unwrap();
+        let expected = self
+            .table
+            .expected(state)
+            .into_iter()
+            .filter(|terminal| *terminal != Grammar::END)
+            .map(|terminal| self.name(Symbol::Terminal(terminal)))
+            .collect();
+        if lookahead == Grammar::END {
+            return Diagnostic::new(ErrorKind::UnexpectedEndOfInput, expected, None, span);
+        }
+        let found = Some(self.name(Symbol::Terminal(lookahead)));
+        if self.accepts_at_end(states.to_vec()) {
+            Diagnostic::new(ErrorKind::TrailingInput, vec![], found, span)
+        } else {
+            Diagnostic::new(ErrorKind::UnexpectedToken, expected, found, span)
+        }
+    }
+
+    fn accepts_at_end(&self, mut states: Vec<usize>) -> bool {
+        let grammar = self.table.grammar();
+        loop {
+            let state = *states.last().unwrap();
+            match self.table.action(state, Grammar::END) {
+                Some(Action::Accept) => return true,
+                Some(Action::Reduce(production)) => {
+                    let production = grammar.production(production);
+                    states.truncate(states.len() - production.body.len());
+                    let state = *states.last().unwrap();
+                    match self.table.goto(state, production.head) {
+                        Some(target) => states.push(target),
+                        None => return false,
+                    }
+                }
+                _ => return false,
+            }
+        }
+    }
+
+    fn name(&self, symbol: Symbol) -> Name<'t> {
+        Name(self.table.grammar().name(symbol))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bnf::BnfParser;
+    use super::*;
+    use rstest::rstest;
+
+    fn expression_table() -> LrTable {
+        LrTable::slr(
+            &BnfParser::new("E := E + T | T\nT := T * F | F\nF := ( E ) | id")
+                .parse()
+                .unwrap(),
+        )
+    }
+
+    // Figure 4.38
+    #[rstest]
+    fn test_figure_4_38() {
+        let table = expression_table();
+        let trace = LrParser::new(&table)
+            .trace(&["id", "*", "id", "+", "id"])
+            .unwrap();
+
+        assert_eq!(
+            trace,
+            [
+                "LINE  STACK     SYMBOLS  INPUT           ACTION",
+                "(1)   0                  id * id + id $  shift 5",
+                "(2)   0 5       id       * id + id $     reduce by F := id",
+                "(3)   0 3       F        * id + id $     reduce by T := F",
+                "(4)   0 2       T        * id + id $     shift 7",
+                "(5)   0 2 7     T *      id + id $       shift 5",
+                "(6)   0 2 7 5   T * id   + id $          reduce by F := id",
+                "(7)   0 2 7 10  T * F    + id $          reduce by T := T * F",
+                "(8)   0 2       T        + id $          reduce by E := T",
+                "(9)   0 1       E        + id $          shift 6",
+                "(10)  0 1 6     E +      id $            shift 5",
+                "(11)  0 1 6 5   E + id   $               reduce by F := id",
+                "(12)  0 1 6 3   E + F    $               reduce by T := F",
+                "(13)  0 1 6 9   E + T    $               reduce by E := E + T",
+                "(14)  0 1       E        $               accept",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    #[rstest]
+    fn test_trace_error() {
+        let table = expression_table();
+        let trace = LrParser::new(&table).trace(&["id", "+", ")"]).unwrap();
+
+        assert_eq!(
+            trace.lines().last().unwrap(),
+            "(6)   0 1 6  E +      ) $       error: expected one of (, id, found )"
+        );
+
+        let error = LrParser::new(&table).trace(&["id", "+", "x"]).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnexpectedToken);
+        assert_eq!(error.found, Some(Name("x")));
+        assert_eq!(error.span.start.offset, 5);
+        assert_eq!(error.to_string(), "expected one of +, *, (, ), id, found x");
+    }
+
+    #[rstest]
+    fn test_parse_tree() {
+        let table = expression_table();
+        let tokens = [5, 1, 5].map(|terminal| Ok((terminal, Span::default())));
+        let tree = LrParser::new(&table)
+            .parse(tokens, Span::default())
+            .unwrap();
+
+        assert_eq!(
+            tree.pretty_print(),
+            [
+                "E",
+                "├── E",
+                "│   └── T",
+                "│       └── F",
+                "│           └── id",
+                "├── +",
+                "└── T",
+                "    └── F",
+                "        └── id",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display};
+
+use super::grammar::{Grammar, Symbol};
+
+// An LR(0) item A := α · β: a production and how much of its body has been
+// seen (section 4.6.2)
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub struct Item {
+    pub production: usize,
+    pub dot: usize,
+}
+
+impl Item {
+    // The symbol right after the dot, if the dot is not at the end
+    pub fn next_symbol(&self, grammar: &Grammar) -> Option<Symbol> {
+        grammar
+            .production(self.production)
+            .body
+            .get(self.dot)
+            .copied()
+    }
+
+    // S' := · S and the items whose dot is not at the left end
+    pub fn is_kernel(&self) -> bool {
+        self.production == 0 || self.dot > 0
+    }
+
+    pub fn describe(&self, grammar: &Grammar) -> String {
+        let production = grammar.production(self.production);
+        let mut symbols: Vec<&str> = production
+            .body
+            .iter()
+            .map(|symbol| grammar.name(*symbol))
+            .collect();
+        symbols.insert(self.dot, "·");
+        format!(
+            "{} := {}",
+            grammar.nonterminals()[production.head],
+            symbols.join(" ")
+        )
+    }
+}
+
+// What the sets-of-items construction needs of an item, LR(0) or LR(1):
+// the LR(0) item it extends
+pub trait LrItem: Ord + Clone {
+    fn core(&self) -> Item;
+}
+
+impl LrItem for Item {
+    fn core(&self) -> Item {
+        *self
+    }
+}
+
+// CLOSURE(I) of figure 4.32: for each item A := α · B β, the items B := · γ
+pub fn closure(grammar: &Grammar, items: &BTreeSet<Item>) -> BTreeSet<Item> {
+    let mut closure = items.clone();
+    let mut pending: Vec<Item> = items.iter().copied().collect();
+    while let Some(item) = pending.pop() {
+        let Some(Symbol::Nonterminal(nonterminal)) = item.next_symbol(grammar) else {
+            continue;
+        };
+        for production in grammar.productions_of(nonterminal) {
+            let item = Item { production, dot: 0 };
+            if closure.insert(item) {
+                pending.push(item);
+            }
+        }
+    }
+    closure
+}
+
+// GOTO(I, X): the closure of the items of I with the dot moved over X
+pub fn goto(grammar: &Grammar, items: &BTreeSet<Item>, symbol: Symbol) -> BTreeSet<Item> {
+    let moved = items
+        .iter()
+        .filter(|item| item.next_symbol(grammar) == Some(symbol))
+        .map(|item| Item {
+            production: item.production,
+            dot: item.dot + 1,
+        })
+        .collect();
+    closure(grammar, &moved)
+}
+
+// The symbols after the dots of items, in the order the items come in and
+// without repeats, which is the order figure 4.31 numbers the states in
+pub fn next_symbols<'a>(grammar: &Grammar, items: impl Iterator<Item = &'a Item>) -> Vec<Symbol> {
+    let mut symbols = Vec::new();
+    for symbol in items.filter_map(|item| item.next_symbol(grammar)) {
+        if !symbols.contains(&symbol) {
+            symbols.push(symbol);
+        }
+    }
+    symbols
+}
+
+// transitions[state] lists the symbols with a GOTO from state and where
+// each one leads
+pub type Transitions = Vec<Vec<(Symbol, usize)>>;
+
+// The procedure items(G') of figure 4.33 for any kind of item: the sets
+// reachable from start through goto, numbered in the order they are found,
+// and the GOTO transitions between them. Sets found so far are kept in a
+// map, so telling whether a set is new does not go through all of them.
+pub fn canonical_collection<I: LrItem>(
+    grammar: &Grammar,
+    start: BTreeSet<I>,
+    goto: impl Fn(&BTreeSet<I>, Symbol) -> BTreeSet<I>,
+) -> (Vec<BTreeSet<I>>, Transitions) {
+    let mut ids = BTreeMap::from([(start.clone(), 0)]);
+    let mut states = vec![start];
+    let mut transitions = Vec::new();
+    let mut state = 0;
+    while state < states.len() {
+        let mut edges = Vec::new();
+        let cores: Vec<Item> = states[state].iter().map(LrItem::core).collect();
+        for symbol in next_symbols(grammar, cores.iter()) {
+            let items = goto(&states[state], symbol);
+            let target = *ids.entry(items).or_insert_with_key(|items| {
+                states.push(items.clone());
+                states.len() - 1
+            });
+            edges.push((symbol, target));
+        }
+        transitions.push(edges);
+        state += 1;
+    }
+    (states, transitions)
+}
+
+// The canonical collection of sets of LR(0) items for the augmented grammar
+// (figure 4.33), numbered in the order they are found, and the GOTO
+// transitions between them
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Lr0Automaton {
+    grammar: Grammar,
+    states: Vec<BTreeSet<Item>>,
+    transitions: Transitions,
+}
+
+impl Lr0Automaton {
+    pub fn new(grammar: &Grammar) -> Self {
+        let grammar = grammar.augmented();
+        let start = BTreeSet::from([Item {
+            production: 0,
+            dot: 0,
+        }]);
+        let (states, transitions) =
+            canonical_collection(&grammar, closure(&grammar, &start), |items, symbol| {
+                goto(&grammar, items, symbol)
+            });
+
+        Lr0Automaton {
+            grammar,
+            states,
+            transitions,
+        }
+    }
+
+    // The augmented grammar the items refer to
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    pub fn states(&self) -> &[BTreeSet<Item>] {
+        &self.states
+    }
+
+    pub fn transitions(&self) -> &[Vec<(Symbol, usize)>] {
+        &self.transitions
+    }
+
+    pub fn goto(&self, state: usize, symbol: Symbol) -> Option<usize> {
+        self.transitions[state]
+            .iter()
+            .find(|(label, _)| *label == symbol)
+            .map(|(_, target)| *target)
+    }
+}
+
+// Each set of items with its transitions, e.g. for grammar 4.1:
+//
+// I0
+//   E' := · E
+//   E := · E + T
+//   ...
+//   on E goto I1
+impl Display for Lr0Automaton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (state, items) in self.states.iter().enumerate() {
+            writeln!(f, "I{}", state)?;
+            for item in items.iter() {
+                writeln!(f, "  {}", item.describe(&self.grammar))?;
+            }
+            for (symbol, target) in self.transitions[state].iter() {
+                writeln!(f, "  on {} goto I{}", self.grammar.name(*symbol), target)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bnf::BnfParser;
+    use super::*;
+    use rstest::rstest;
+
+    fn expression_grammar() -> Grammar {
+        BnfParser::new("E := E + T | T\nT := T * F | F\nF := ( E ) | id")
+            .parse()
+            .unwrap()
+    }
+
+    fn describe(automaton: &Lr0Automaton, state: usize) -> Vec<String> {
+        automaton.states()[state]
+            .iter()
+            .map(|item| item.describe(automaton.grammar()))
+            .collect()
+    }
+
+    #[rstest]
+    fn test_closure_of_start_item() {
+        let automaton = Lr0Automaton::new(&expression_grammar());
+
+        assert_eq!(
+            describe(&automaton, 0),
+            vec![
+                "E' := · E",
+                "E := · E + T",
+                "E := · T",
+                "T := · T * F",
+                "T := · F",
+                "F := · ( E )",
+                "F := · id",
+            ]
+        );
+    }
+
+    // Figure 4.31
+    #[rstest]
+    #[case(1, vec!["E' := E ·", "E := E · + T"])]
+    #[case(2, vec!["E := T ·", "T := T · * F"])]
+    #[case(5, vec!["F := id ·"])]
+    #[case(7, vec!["T := T * · F", "F := · ( E )", "F := · id"])]
+    #[case(9, vec!["E := E + T ·", "T := T · * F"])]
+    #[case(11, vec!["F := ( E ) ·"])]
+    fn test_figure_4_31(#[case] state: usize, #[case] items: Vec<&str>) {
+        let automaton = Lr0Automaton::new(&expression_grammar());
+
+        assert_eq!(automaton.states().len(), 12);
+        assert_eq!(describe(&automaton, state), items);
+    }
+
+    #[rstest]
+    #[case(0, "E", Some(1))]
+    #[case(0, "id", Some(5))]
+    #[case(4, "E", Some(8))]
+    #[case(4, "(", Some(4))]
+    #[case(6, "T", Some(9))]
+    #[case(8, ")", Some(11))]
+    #[case(5, "+", None)]
+    fn test_goto(#[case] state: usize, #[case] name: &str, #[case] expected: Option<usize>) {
+        let automaton = Lr0Automaton::new(&expression_grammar());
+        let grammar = automaton.grammar();
+        let symbol = match grammar.nonterminal_id(name) {
+            Some(nonterminal) => Symbol::Nonterminal(nonterminal),
+            None => Symbol::Terminal(grammar.terminal_id(name).unwrap()),
+        };
+
+        assert_eq!(automaton.goto(state, symbol), expected);
+    }
+
+    #[rstest]
+    fn test_display() {
+        let automaton = Lr0Automaton::new(&BnfParser::new("S := ( S ) | a").parse().unwrap());
+
+        assert_eq!(
+            automaton.to_string(),
+            [
+                "I0",
+                "  S' := · S",
+                "  S := · ( S )",
+                "  S := · a",
+                "  on S goto I1",
+                "  on ( goto I2",
+                "  on a goto I3",
+                "I1",
+                "  S' := S ·",
+                "I2",
+                "  S := · ( S )",
+                "  S := ( · S )",
+                "  S := · a",
+                "  on ( goto I2",
+                "  on S goto I4",
+                "  on a goto I3",
+                "I3",
+                "  S := a ·",
+                "I4",
+                "  S := ( S · )",
+                "  on ) goto I5",
+                "I5",
+                "  S := ( S ) ·",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
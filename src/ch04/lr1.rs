@@ -0,0 +1,276 @@
+use std::collections::BTreeSet;
+use std::fmt::{self, Display};
+
+use super::first_follow::FirstFollow;
+use super::grammar::{Grammar, Symbol};
+use super::lr::LrTable;
+use super::lr0::{self, Item, LrItem, Transitions};
+
+// An LR(1) item [A := α · β, a]: an LR(0) item, its core, with a lookahead
+// terminal (section 4.7.2)
+#[derive(Debug, Hash, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub struct Lr1Item {
+    pub core: Item,
+    pub lookahead: usize,
+}
+
+impl LrItem for Lr1Item {
+    fn core(&self) -> Item {
+        self.core
+    }
+}
+
+// CLOSURE(I) of figure 4.40: for each item [A := α · B β, a], the items
+// [B := · γ, b] for every b in FIRST(β a)
+pub fn closure(
+    grammar: &Grammar,
+    sets: &FirstFollow,
+    items: &BTreeSet<Lr1Item>,
+) -> BTreeSet<Lr1Item> {
+    let mut closure = items.clone();
+    let mut pending: Vec<Lr1Item> = items.iter().copied().collect();
+    while let Some(item) = pending.pop() {
+        let Some(Symbol::Nonterminal(nonterminal)) = item.core.next_symbol(grammar) else {
+            continue;
+        };
+        let beta = &grammar.production(item.core.production).body[item.core.dot + 1..];
+        let (mut lookaheads, nullable) = sets.first_of(beta);
+        if nullable {
+            lookaheads.insert(item.lookahead);
+        }
+        for production in grammar.productions_of(nonterminal) {
+            for lookahead in lookaheads.iter() {
+                let item = Lr1Item {
+                    core: Item { production, dot: 0 },
+                    lookahead: *lookahead,
+                };
+                if closure.insert(item) {
+                    pending.push(item);
+                }
+            }
+        }
+    }
+    closure
+}
+
+pub fn goto(
+    grammar: &Grammar,
+    sets: &FirstFollow,
+    items: &BTreeSet<Lr1Item>,
+    symbol: Symbol,
+) -> BTreeSet<Lr1Item> {
+    let moved = items
+        .iter()
+        .filter(|item| item.core.next_symbol(grammar) == Some(symbol))
+        .map(|item| Lr1Item {
+            core: Item {
+                production: item.core.production,
+                dot: item.core.dot + 1,
+            },
+            lookahead: item.lookahead,
+        })
+        .collect();
+    closure(grammar, sets, &moved)
+}
+
+// The canonical collection of sets of LR(1) items (figure 4.40), numbered
+// the way Lr0Automaton numbers its sets
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Lr1Automaton {
+    grammar: Grammar,
+    states: Vec<BTreeSet<Lr1Item>>,
+    transitions: Transitions,
+}
+
+impl Lr1Automaton {
+    pub fn new(grammar: &Grammar) -> Self {
+        let grammar = grammar.augmented();
+        let sets = FirstFollow::new(&grammar);
+        let start = BTreeSet::from([Lr1Item {
+            core: Item {
+                production: 0,
+                dot: 0,
+            },
+            lookahead: Grammar::END,
+        }]);
+        let (states, transitions) = lr0::canonical_collection(
+            &grammar,
+            closure(&grammar, &sets, &start),
+            |items, symbol| goto(&grammar, &sets, items, symbol),
+        );
+
+        Lr1Automaton {
+            grammar,
+            states,
+            transitions,
+        }
+    }
+
+    // The augmented grammar the items refer to
+    pub fn grammar(&self) -> &Grammar {
+        &self.grammar
+    }
+
+    pub fn states(&self) -> &[BTreeSet<Lr1Item>] {
+        &self.states
+    }
+
+    pub fn transitions(&self) -> &[Vec<(Symbol, usize)>] {
+        &self.transitions
+    }
+
+    // The LR(0) items of a set without their lookaheads
+    pub fn core(&self, state: usize) -> BTreeSet<Item> {
+        self.states[state].iter().map(|item| item.core).collect()
+    }
+
+    // The items of a set with the lookaheads of each core together, as
+    // figure 4.41 writes them, e.g. "C := · c C, c/d"
+    pub fn describe(&self, state: usize) -> Vec<String> {
+        self.core(state)
+            .into_iter()
+            .map(|core| {
+                let lookaheads: Vec<&str> = self.states[state]
+                    .iter()
+                    .filter(|item| item.core == core)
+                    .map(|item| self.grammar.terminals()[item.lookahead].as_str())
+                    .collect();
+                format!("{}, {}", core.describe(&self.grammar), lookaheads.join("/"))
+            })
+            .collect()
+    }
+}
+
+// Laid out like the Display of Lr0Automaton
+impl Display for Lr1Automaton {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for state in 0..self.states.len() {
+            writeln!(f, "I{}", state)?;
+            for item in self.describe(state) {
+                writeln!(f, "  {}", item)?;
+            }
+            for (symbol, target) in self.transitions[state].iter() {
+                writeln!(f, "  on {} goto I{}", self.grammar.name(*symbol), target)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LrTable {
+    // Algorithm 4.56: the states are the canonical LR(1) collection, and a
+    // set containing [A := α ·, a] reduces by A := α on a alone
+    pub fn canonical_lr1(grammar: &Grammar) -> Self {
+        let automaton = Lr1Automaton::new(grammar);
+        let reductions: Vec<Vec<(usize, usize)>> = automaton
+            .states()
+            .iter()
+            .map(|items| reductions(automaton.grammar(), items))
+            .collect();
+
+        LrTable::new(
+            automaton.grammar().clone(),
+            automaton.transitions(),
+            &reductions,
+        )
+    }
+}
+
+// The lookahead and production of each item of items with the dot at the end
+pub fn reductions(grammar: &Grammar, items: &BTreeSet<Lr1Item>) -> Vec<(usize, usize)> {
+    items
+        .iter()
+        .filter(|item| item.core.next_symbol(grammar).is_none())
+        .map(|item| (item.lookahead, item.core.production))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bnf::BnfParser;
+    use super::super::lr::LrParser;
+    use super::*;
+    use rstest::rstest;
+
+    // The grammars of exercise 2.4.1
+    const GRAMMAR_A: &str = "S := + S S | - S S | a";
+    const GRAMMAR_B: &str = "S := S ( S ) S | ε";
+    const GRAMMAR_C: &str = "S := 0 S 1 | 0 1";
+
+    // Grammar 4.55
+    const GRAMMAR_4_55: &str = "S := C C\nC := c C | d";
+
+    // Grammar 4.49, assignments through pointers
+    const GRAMMAR_4_49: &str = "S := L = R | R\nL := * R | id\nR := L";
+
+    fn grammar(input: &str) -> Grammar {
+        BnfParser::new(input).parse().unwrap()
+    }
+
+    // Figure 4.41
+    #[rstest]
+    #[case(0, vec!["S' := · S, $", "S := · C C, $", "C := · c C, c/d", "C := · d, c/d"])]
+    #[case(2, vec!["S := C · C, $", "C := · c C, $", "C := · d, $"])]
+    #[case(3, vec!["C := · c C, c/d", "C := c · C, c/d", "C := · d, c/d"])]
+    #[case(4, vec!["C := d ·, c/d"])]
+    #[case(6, vec!["C := · c C, $", "C := c · C, $", "C := · d, $"])]
+    #[case(7, vec!["C := d ·, $"])]
+    #[case(8, vec!["C := c C ·, c/d"])]
+    #[case(9, vec!["C := c C ·, $"])]
+    fn test_figure_4_41(#[case] state: usize, #[case] items: Vec<&str>) {
+        let automaton = Lr1Automaton::new(&grammar(GRAMMAR_4_55));
+
+        assert_eq!(automaton.states().len(), 10);
+        assert_eq!(automaton.describe(state), items);
+    }
+
+    // Figure 4.42
+    #[rstest]
+    fn test_figure_4_42() {
+        let table = LrTable::canonical_lr1(&grammar(GRAMMAR_4_55));
+
+        assert_eq!(
+            table.to_string(),
+            [
+                "STATE  c   d   $    S  C",
+                "0      s3  s4       1  2",
+                "1              acc",
+                "2      s6  s7          5",
+                "3      s3  s4          8",
+                "4      r3  r3",
+                "5              r1",
+                "6      s6  s7          9",
+                "7              r3",
+                "8      r2  r2",
+                "9              r2",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+
+    // The grammars of exercise 2.4.1 are LR(1) but for the ambiguous b, and
+    // grammar 4.49, which is not SLR(1), is
+    #[rstest]
+    #[case(GRAMMAR_A, 0)]
+    #[case(GRAMMAR_B, 2)]
+    #[case(GRAMMAR_C, 0)]
+    #[case(GRAMMAR_4_49, 0)]
+    fn test_conflicts(#[case] input: &str, #[case] conflicts: usize) {
+        let table = LrTable::canonical_lr1(&grammar(input));
+
+        assert_eq!(table.conflicts().len(), conflicts);
+    }
+
+    #[rstest]
+    #[case(GRAMMAR_A, vec!["+", "-", "a", "a", "a"])]
+    #[case(GRAMMAR_B, vec!["(", "(", ")", ")", "(", ")"])]
+    #[case(GRAMMAR_C, vec!["0", "0", "1", "1"])]
+    #[case(GRAMMAR_4_49, vec!["*", "*", "id", "=", "*", "id"])]
+    fn test_parse(#[case] input: &str, #[case] tokens: Vec<&str>) {
+        let table = LrTable::canonical_lr1(&grammar(input));
+
+        let trace = LrParser::new(&table).trace(&tokens).unwrap();
+        assert!(trace.ends_with("accept\n"));
+    }
+}
@@ -0,0 +1,13 @@
+pub mod bnf;
+pub mod columns;
+pub mod first_follow;
+pub mod grammar;
+pub mod lalr;
+pub mod ll1;
+pub mod lr;
+pub mod lr0;
+pub mod lr1;
+#[cfg(test)]
+mod parser_tests;
+pub mod slr;
+pub mod transform;
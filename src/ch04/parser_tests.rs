@@ -0,0 +1,22 @@
+// Helpers the tests of the parsers of this chapter share on exercise 2.4.1,
+// which check what ch02's hand-written parsers check: the leaves of an
+// accepted input, or the kind and offset of the error.
+use super::grammar::Name;
+use crate::ch02::recursive_descent_parser::diagnostic::{Diagnostic, ErrorKind};
+use crate::ch02::recursive_descent_parser::parse_tree::ParseTree;
+
+// The leaves of the parse tree without ε, or the kind and offset of the
+// error, as the tests of the hand-written parsers check them
+pub fn outcome(
+    result: Result<ParseTree<Name, Name>, Diagnostic<Name>>,
+) -> Result<String, (ErrorKind, usize)> {
+    match result {
+        Ok(tree) => Ok(tree
+            .leaves()
+            .into_iter()
+            .filter(|leaf| leaf.0 != "ε")
+            .map(|leaf| leaf.0)
+            .collect()),
+        Err(error) => Err((error.kind, error.span.start.offset)),
+    }
+}
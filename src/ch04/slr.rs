@@ -0,0 +1,177 @@
+use super::first_follow::FirstFollow;
+use super::grammar::Grammar;
+use super::lr::LrTable;
+use super::lr0::Lr0Automaton;
+
+impl LrTable {
+    // Algorithm 4.46: the states are the canonical LR(0) collection, and a
+    // set of items containing A := α · reduces by A := α on every terminal
+    // in FOLLOW(A)
+    pub fn slr(grammar: &Grammar) -> Self {
+        let automaton = Lr0Automaton::new(grammar);
+        let grammar = automaton.grammar();
+        let sets = FirstFollow::new(grammar);
+        let reductions: Vec<Vec<(usize, usize)>> = automaton
+            .states()
+            .iter()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter(|item| item.next_symbol(grammar).is_none())
+                    .flat_map(|item| {
+                        let head = grammar.production(item.production).head;
+                        sets.follow(head)
+                            .iter()
+                            .map(move |terminal| (*terminal, item.production))
+                    })
+                    .collect()
+            })
+            .collect();
+
+        LrTable::new(grammar.clone(), automaton.transitions(), &reductions)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bnf::BnfParser;
+    use super::super::lr::{Action, LrParser};
+    use super::super::parser_tests::outcome;
+    use super::*;
+    use crate::ch02::recursive_descent_parser::diagnostic::ErrorKind;
+    use rstest::rstest;
+
+    // The grammars of exercise 2.4.1 as the exercise gives them; b is
+    // ambiguous and left recursive, and c needs two tokens of lookahead
+    // top-down
+    const GRAMMAR_A: &str = "S := + S S | - S S | a";
+    const GRAMMAR_B: &str = "S := S ( S ) S | ε";
+    const GRAMMAR_C: &str = "S := 0 S 1 | 0 1";
+
+    fn table(input: &str) -> LrTable {
+        LrTable::slr(&BnfParser::new(input).parse().unwrap())
+    }
+
+    fn parse(grammar: &str, input: &str) -> Result<String, (ErrorKind, usize)> {
+        let table = table(grammar);
+        outcome(LrParser::new(&table).parse_chars(input))
+    }
+
+    #[rstest]
+    #[case(GRAMMAR_A, "a")]
+    #[case(GRAMMAR_A, "+aa")]
+    #[case(GRAMMAR_A, "++++aaaaa")]
+    #[case(GRAMMAR_A, "+-aaa")]
+    #[case(GRAMMAR_A, "+++--+-+-+-+-+++-a-aaaaaaaaaaaaaaaaaa")]
+    #[case(GRAMMAR_A, "-aa")]
+    #[case(GRAMMAR_B, "")]
+    #[case(GRAMMAR_B, "()")]
+    #[case(GRAMMAR_B, "()()()")]
+    #[case(GRAMMAR_B, "(()())()(())")]
+    #[case(GRAMMAR_B, "(())()")]
+    #[case(GRAMMAR_B, "(())(())(())")]
+    #[case(GRAMMAR_C, "01")]
+    #[case(GRAMMAR_C, "0011")]
+    #[case(GRAMMAR_C, "00000000001111111111")]
+    fn test_parse_valid(#[case] grammar: &str, #[case] input: &str) {
+        assert_eq!(parse(grammar, input), Ok(input.to_string()));
+    }
+
+    #[rstest]
+    #[case(GRAMMAR_A, "++aa", ErrorKind::UnexpectedEndOfInput, 4)]
+    #[case(GRAMMAR_A, "+a", ErrorKind::UnexpectedEndOfInput, 2)]
+    #[case(GRAMMAR_A, "--aa", ErrorKind::UnexpectedEndOfInput, 4)]
+    #[case(GRAMMAR_A, "-a", ErrorKind::UnexpectedEndOfInput, 2)]
+    #[case(GRAMMAR_A, "+ab", ErrorKind::InvalidCharacter('b'), 2)]
+    #[case(GRAMMAR_A, "+ba", ErrorKind::InvalidCharacter('b'), 1)]
+    #[case(GRAMMAR_A, "-ab", ErrorKind::InvalidCharacter('b'), 2)]
+    #[case(GRAMMAR_A, "-ba", ErrorKind::InvalidCharacter('b'), 1)]
+    #[case(GRAMMAR_A, "ab", ErrorKind::InvalidCharacter('b'), 1)]
+    #[case(GRAMMAR_A, "b", ErrorKind::InvalidCharacter('b'), 0)]
+    #[case(GRAMMAR_A, "aa", ErrorKind::TrailingInput, 1)]
+    #[case(GRAMMAR_B, "a", ErrorKind::InvalidCharacter('a'), 0)]
+    #[case(GRAMMAR_B, "(a)", ErrorKind::InvalidCharacter('a'), 1)]
+    #[case(GRAMMAR_B, "(", ErrorKind::UnexpectedEndOfInput, 1)]
+    #[case(GRAMMAR_B, "(()", ErrorKind::UnexpectedEndOfInput, 3)]
+    #[case(GRAMMAR_B, ")", ErrorKind::TrailingInput, 0)]
+    #[case(GRAMMAR_B, ")(", ErrorKind::TrailingInput, 0)]
+    #[case(GRAMMAR_B, "())", ErrorKind::TrailingInput, 2)]
+    #[case(GRAMMAR_B, "()())", ErrorKind::TrailingInput, 4)]
+    #[case(GRAMMAR_C, "0", ErrorKind::UnexpectedEndOfInput, 1)]
+    #[case(GRAMMAR_C, "", ErrorKind::UnexpectedEndOfInput, 0)]
+    #[case(GRAMMAR_C, "001", ErrorKind::UnexpectedEndOfInput, 3)]
+    #[case(GRAMMAR_C, "1", ErrorKind::UnexpectedToken, 0)]
+    #[case(GRAMMAR_C, "10", ErrorKind::UnexpectedToken, 0)]
+    #[case(GRAMMAR_C, "010", ErrorKind::TrailingInput, 2)]
+    #[case(GRAMMAR_C, "00110", ErrorKind::TrailingInput, 4)]
+    #[case(GRAMMAR_C, "a", ErrorKind::InvalidCharacter('a'), 0)]
+    #[case(GRAMMAR_C, "01a", ErrorKind::InvalidCharacter('a'), 2)]
+    fn test_parse_invalid(
+        #[case] grammar: &str,
+        #[case] input: &str,
+        #[case] kind: ErrorKind,
+        #[case] offset: usize,
+    ) {
+        assert_eq!(parse(grammar, input), Err((kind, offset)));
+    }
+
+    #[rstest]
+    #[case(GRAMMAR_A, vec![])]
+    #[case(
+        GRAMMAR_B,
+        vec!["shift/reduce conflict in state 5 on (: shift 2 or reduce by S := S ( S ) S; using shift 2"]
+    )]
+    #[case(GRAMMAR_C, vec![])]
+    // Example 4.48: L = R is not SLR(1)
+    #[case(
+        "S := L = R | R\nL := * R | id\nR := L",
+        vec!["shift/reduce conflict in state 2 on =: shift 6 or reduce by R := L; using shift 6"]
+    )]
+    #[case(
+        "S := A | B\nA := a\nB := a",
+        vec![
+            "reduce/reduce conflict in state 4 on $: reduce by A := a or reduce by B := a; using reduce by A := a",
+        ]
+    )]
+    fn test_conflicts(#[case] grammar: &str, #[case] expected: Vec<&str>) {
+        let table = table(grammar);
+
+        assert_eq!(
+            table
+                .conflicts()
+                .iter()
+                .map(|conflict| table.explain(conflict))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    // Figure 4.37, with the terminals in the order grammar 4.1 uses them
+    #[rstest]
+    fn test_figure_4_37() {
+        let table = table("E := E + T | T\nT := T * F | F\nF := ( E ) | id");
+
+        assert_eq!(table.action(0, 5), Some(Action::Shift(5)));
+        assert_eq!(table.action(1, Grammar::END), Some(Action::Accept));
+        assert_eq!(
+            table.to_string(),
+            [
+                "STATE  +   *   (   )    id  $    E  T  F",
+                "0              s4       s5       1  2  3",
+                "1      s6                   acc",
+                "2      r2  s7      r2       r2",
+                "3      r4  r4      r4       r4",
+                "4              s4       s5       8  2  3",
+                "5      r6  r6      r6       r6",
+                "6              s4       s5          9  3",
+                "7              s4       s5             10",
+                "8      s6          s11",
+                "9      r1  s7      r1       r1",
+                "10     r3  r3      r3       r3",
+                "11     r5  r5      r5       r5",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,770 @@
+use std::mem;
+
+use super::first_follow::FirstFollow;
+use super::grammar::{Grammar, Symbol};
+
+// A grammar produced by a transformation, with one line per rewriting step
+// that got there
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Transformed {
+    pub grammar: Grammar,
+    pub trace: Vec<String>,
+}
+
+impl Transformed {
+    // Applies another transformation to the result, keeping both traces
+    pub fn then(mut self, transform: impl Fn(&Grammar) -> Transformed) -> Transformed {
+        let next = transform(&self.grammar);
+        self.trace.extend(next.trace);
+        Transformed {
+            grammar: next.grammar,
+            trace: self.trace,
+        }
+    }
+}
+
+// Algorithm 4.19. Nonterminals are taken in the order of the grammar; the
+// productions of each one that start with an earlier nonterminal get that
+// nonterminal's bodies substituted in, and its immediate left recursion
+// A := A α | β is then replaced by A := β A'; A' := α A' | ε.
+// Like the book's, the result may still be left recursive when the grammar
+// has ε-productions.
+pub fn eliminate_left_recursion(grammar: &Grammar) -> Transformed {
+    let mut rules = Rules::new(grammar);
+    let mut trace = Vec::new();
+    for i in 0..grammar.nonterminals().len() {
+        for j in 0..i {
+            let earlier = Symbol::Nonterminal(j);
+            if !rules.bodies[i]
+                .iter()
+                .any(|body| body.first() == Some(&earlier))
+            {
+                continue;
+            }
+            let deltas = rules.bodies[j].clone();
+            let mut bodies = Vec::new();
+            for body in mem::take(&mut rules.bodies[i]) {
+                if body.first() == Some(&earlier) {
+                    for delta in deltas.iter() {
+                        bodies.push([&delta[..], &body[1..]].concat());
+                    }
+                } else {
+                    bodies.push(body);
+                }
+            }
+            rules.set_bodies(i, bodies);
+            trace.push(format!(
+                "substitute {} in the productions of {} that start with it: {}",
+                rules.nonterminals[j],
+                rules.nonterminals[i],
+                rules.describe(i)
+            ));
+        }
+        eliminate_immediate_left_recursion(&mut rules, i, &mut trace);
+    }
+
+    Transformed {
+        grammar: rules.build(),
+        trace,
+    }
+}
+
+fn eliminate_immediate_left_recursion(rules: &mut Rules, head: usize, trace: &mut Vec<String>) {
+    let (recursive, others): (Vec<_>, Vec<_>) = mem::take(&mut rules.bodies[head])
+        .into_iter()
+        .partition(|body| body.first() == Some(&Symbol::Nonterminal(head)));
+    if recursive.iter().any(|body| body.len() == 1) {
+        trace.push(format!(
+            "drop the cycle {0} := {0}",
+            rules.nonterminals[head]
+        ));
+    }
+    let alphas: Vec<Vec<Symbol>> = recursive
+        .into_iter()
+        .filter(|body| body.len() > 1)
+        .map(|body| body[1..].to_vec())
+        .collect();
+    if alphas.is_empty() {
+        rules.set_bodies(head, others);
+        return;
+    }
+
+    let prime = rules.fresh(head);
+    let tail = Symbol::Nonterminal(prime);
+    rules.set_bodies(
+        head,
+        others
+            .into_iter()
+            .map(|beta| [&beta[..], &[tail]].concat())
+            .collect(),
+    );
+    rules.set_bodies(
+        prime,
+        alphas
+            .into_iter()
+            .map(|alpha| [&alpha[..], &[tail]].concat())
+            .chain([Vec::new()])
+            .collect(),
+    );
+    trace.push(format!(
+        "eliminate the immediate left recursion of {}: {}; {}",
+        rules.nonterminals[head],
+        rules.describe(head),
+        rules.describe(prime)
+    ));
+}
+
+// Algorithm 4.21. While two alternatives of some A start with the same
+// symbol, their longest common prefix α is factored out:
+// A := α β1 | α β2 | γ becomes A := α A' | γ; A' := β1 | β2
+pub fn left_factor(grammar: &Grammar) -> Transformed {
+    let mut rules = Rules::new(grammar);
+    let mut trace = Vec::new();
+    let mut head = 0;
+    while head < rules.nonterminals.len() {
+        let Some((prefix, group)) = common_prefix(&rules.bodies[head]) else {
+            head += 1;
+            continue;
+        };
+        let prime = rules.fresh(head);
+        let mut factored = Vec::new();
+        let mut suffixes = Vec::new();
+        for (i, body) in mem::take(&mut rules.bodies[head]).into_iter().enumerate() {
+            if !group.contains(&i) {
+                factored.push(body);
+                continue;
+            }
+            if suffixes.is_empty() {
+                factored.push([&prefix[..], &[Symbol::Nonterminal(prime)]].concat());
+            }
+            suffixes.push(body[prefix.len()..].to_vec());
+        }
+        rules.set_bodies(head, factored);
+        rules.set_bodies(prime, suffixes);
+        trace.push(format!(
+            "left factor {} out of {}: {}; {}",
+            rules.describe_body(&prefix),
+            rules.nonterminals[head],
+            rules.describe(head),
+            rules.describe(prime)
+        ));
+    }
+
+    Transformed {
+        grammar: rules.build(),
+        trace,
+    }
+}
+
+// The alternatives that start with the same symbol as the first one that
+// shares its first symbol with another, and the longest prefix they share
+fn common_prefix(bodies: &[Vec<Symbol>]) -> Option<(Vec<Symbol>, Vec<usize>)> {
+    for (i, body) in bodies.iter().enumerate() {
+        let Some(first) = body.first() else {
+            continue;
+        };
+        let group: Vec<usize> = (i..bodies.len())
+            .filter(|j| bodies[*j].first() == Some(first))
+            .collect();
+        if group.len() < 2 {
+            continue;
+        }
+        let mut length = 1;
+        while body.get(length).is_some()
+            && group
+                .iter()
+                .all(|j| bodies[*j].get(length) == body.get(length))
+        {
+            length += 1;
+        }
+        return Some((body[..length].to_vec(), group));
+    }
+    None
+}
+
+// Removes the nonterminals that derive no terminal string, with every
+// production using them, and then whatever the start symbol no longer
+// reaches
+pub fn remove_useless_symbols(grammar: &Grammar) -> Transformed {
+    let mut rules = Rules::new(grammar);
+    let mut trace = Vec::new();
+    let used_by_grammar = rules.used_terminals();
+
+    let mut generating = vec![false; rules.nonterminals.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for head in 0..rules.nonterminals.len() {
+            if !generating[head]
+                && rules.bodies[head].iter().any(|body| {
+                    body.iter().all(|symbol| match symbol {
+                        Symbol::Terminal(_) => true,
+                        Symbol::Nonterminal(nonterminal) => generating[*nonterminal],
+                    })
+                })
+            {
+                generating[head] = true;
+                changed = true;
+            }
+        }
+    }
+    for (head, is_generating) in generating.iter().enumerate() {
+        if !is_generating {
+            trace.push(format!(
+                "remove {}, which derives no terminal string",
+                rules.nonterminals[head]
+            ));
+            rules.bodies[head].clear();
+        }
+    }
+    for bodies in rules.bodies.iter_mut() {
+        bodies.retain(|body| {
+            body.iter().all(|symbol| match symbol {
+                Symbol::Terminal(_) => true,
+                Symbol::Nonterminal(nonterminal) => generating[*nonterminal],
+            })
+        });
+    }
+    let used_by_generating = rules.used_terminals();
+    for (terminal, name) in rules.terminals.iter().enumerate().skip(1) {
+        if used_by_grammar[terminal] && !used_by_generating[terminal] {
+            trace.push(format!(
+                "remove {}, which only appears in productions using a nonterminal that derives \
+                 no terminal string",
+                name
+            ));
+        }
+    }
+
+    let mut reachable = vec![false; rules.nonterminals.len()];
+    let mut pending = vec![0];
+    reachable[0] = true;
+    while let Some(head) = pending.pop() {
+        for symbol in rules.bodies[head].iter().flatten() {
+            if let Symbol::Nonterminal(nonterminal) = symbol {
+                if !reachable[*nonterminal] {
+                    reachable[*nonterminal] = true;
+                    pending.push(*nonterminal);
+                }
+            }
+        }
+    }
+    for (head, is_reachable) in reachable.iter().enumerate() {
+        if !is_reachable && !rules.bodies[head].is_empty() {
+            trace.push(format!(
+                "remove {}, which is unreachable from {}",
+                rules.nonterminals[head], rules.nonterminals[0]
+            ));
+            rules.bodies[head].clear();
+        }
+    }
+    let used_by_reachable = rules.used_terminals();
+    for (terminal, name) in rules.terminals.iter().enumerate().skip(1) {
+        if used_by_generating[terminal] && !used_by_reachable[terminal] {
+            trace.push(format!(
+                "remove {}, which is unreachable from {}",
+                name, rules.nonterminals[0]
+            ));
+        }
+    }
+
+    Transformed {
+        grammar: rules.build(),
+        trace,
+    }
+}
+
+// Replaces each production by the versions of it with every combination of
+// its nullable nonterminals left out, and drops the ε-productions. If the
+// start symbol S derives ε, the language keeps ε through S := ε when S
+// appears in no body, or else through a new start symbol S' := S | ε.
+pub fn remove_epsilon_productions(grammar: &Grammar) -> Transformed {
+    let sets = FirstFollow::new(grammar);
+    let mut rules = Rules::new(grammar);
+    let mut trace = Vec::new();
+    let nullable: Vec<usize> = (0..rules.nonterminals.len())
+        .filter(|nonterminal| sets.is_nullable(*nonterminal))
+        .collect();
+    if nullable.is_empty() {
+        return Transformed {
+            grammar: grammar.clone(),
+            trace,
+        };
+    }
+
+    for head in 0..rules.nonterminals.len() {
+        let mut bodies = Vec::new();
+        for body in rules.bodies[head].iter() {
+            let mut expansions = vec![Vec::new()];
+            for symbol in body.iter() {
+                let is_nullable = matches!(symbol, Symbol::Nonterminal(nonterminal) if nullable.contains(nonterminal));
+                let mut with_symbol: Vec<Vec<Symbol>> = expansions
+                    .iter()
+                    .map(|expansion| [&expansion[..], &[*symbol]].concat())
+                    .collect();
+                if is_nullable {
+                    expansions.append(&mut with_symbol);
+                } else {
+                    expansions = with_symbol;
+                }
+            }
+            bodies.extend(expansions.into_iter().filter(|expansion| {
+                !expansion.is_empty() && expansion[..] != [Symbol::Nonterminal(head)]
+            }));
+        }
+        rules.set_bodies(head, bodies);
+    }
+    let names: Vec<&str> = nullable
+        .iter()
+        .map(|nonterminal| rules.nonterminals[*nonterminal].as_str())
+        .collect();
+    trace.push(format!(
+        "remove the ε-productions, leaving out the nullable {} in every combination",
+        names.join(", ")
+    ));
+
+    if sets.is_nullable(0) {
+        if rules.is_used(Symbol::Nonterminal(0)) {
+            rules.insert_start();
+            rules.set_bodies(0, vec![vec![Symbol::Nonterminal(1)], Vec::new()]);
+            trace.push(format!(
+                "add {}, since {} derives ε",
+                rules.describe(0),
+                rules.nonterminals[1]
+            ));
+        } else {
+            rules.bodies[0].push(Vec::new());
+            trace.push(format!(
+                "keep {0} := ε, since {0} derives ε and appears in no body",
+                rules.nonterminals[0]
+            ));
+        }
+    }
+
+    Transformed {
+        grammar: rules.build(),
+        trace,
+    }
+}
+
+// When A := B is the only production of A, A and B derive the same strings,
+// so B is renamed to A everywhere and its productions become A's. This
+// undoes the S := S' that eliminating left recursion leaves behind when S
+// has an ε-production.
+pub fn merge_aliases(grammar: &Grammar) -> Transformed {
+    let mut rules = Rules::new(grammar);
+    let mut trace = Vec::new();
+    while let Some((head, alias)) =
+        (0..rules.nonterminals.len()).find_map(|head| match rules.bodies[head][..] {
+            [ref body] => match body[..] {
+                [Symbol::Nonterminal(alias)] if alias != head => Some((head, alias)),
+                _ => None,
+            },
+            _ => None,
+        })
+    {
+        // The start symbol is never merged away: when it is the alias, A is
+        // merged into it instead and A := S is dropped
+        let (kept, merged) = if alias == 0 {
+            (alias, head)
+        } else {
+            (head, alias)
+        };
+        let bodies = mem::take(&mut rules.bodies[merged]);
+        if kept == head {
+            rules.set_bodies(head, bodies);
+        }
+        for symbol in rules.bodies.iter_mut().flatten().flatten() {
+            if *symbol == Symbol::Nonterminal(merged) {
+                *symbol = Symbol::Nonterminal(kept);
+            }
+        }
+        trace.push(format!(
+            "merge {} into {}, its only production being {} := {}: {}",
+            rules.nonterminals[merged],
+            rules.nonterminals[kept],
+            rules.nonterminals[head],
+            rules.nonterminals[alias],
+            rules.describe(kept)
+        ));
+    }
+
+    Transformed {
+        grammar: rules.build(),
+        trace,
+    }
+}
+
+// A nonterminal A whose productions are ε and bodies ending in A derives a
+// closure: any two strings it derives concatenate into one it derives. A A
+// can then be written A anywhere, e.g. S := ( S ) S S | ε becomes
+// S := ( S ) S | ε, which no longer has two ways of splitting "()()".
+pub fn merge_repeated_closures(grammar: &Grammar) -> Transformed {
+    let mut rules = Rules::new(grammar);
+    let mut trace = Vec::new();
+    for head in 0..rules.nonterminals.len() {
+        let closure = Symbol::Nonterminal(head);
+        let is_closure = rules.bodies[head].iter().any(|body| body.is_empty())
+            && rules.bodies[head]
+                .iter()
+                .all(|body| body.is_empty() || body.last() == Some(&closure));
+        if !is_closure {
+            continue;
+        }
+
+        let mut changed = false;
+        for body in rules.bodies.iter_mut().flatten() {
+            let length = body.len();
+            body.dedup_by(|a, b| *a == closure && *b == closure);
+            changed |= body.len() != length;
+        }
+        if changed {
+            for bodies in 0..rules.bodies.len() {
+                let deduplicated = mem::take(&mut rules.bodies[bodies]);
+                rules.set_bodies(bodies, deduplicated);
+            }
+            trace.push(format!(
+                "write {0} {0} as {0}, since {0} derives a closure: {1}",
+                rules.nonterminals[head],
+                rules.describe(head)
+            ));
+        }
+    }
+
+    Transformed {
+        grammar: rules.build(),
+        trace,
+    }
+}
+
+// The productions of a grammar grouped by head, for the transformations to
+// edit before building a new Grammar. Nonterminal 0 is the start symbol, as
+// in Grammar.
+struct Rules {
+    terminals: Vec<String>,
+    nonterminals: Vec<String>,
+    bodies: Vec<Vec<Vec<Symbol>>>,
+}
+
+impl Rules {
+    fn new(grammar: &Grammar) -> Self {
+        let mut bodies = vec![Vec::new(); grammar.nonterminals().len()];
+        for production in grammar.productions() {
+            bodies[production.head].push(production.body.clone());
+        }
+        Rules {
+            terminals: grammar.terminals().to_vec(),
+            nonterminals: grammar.nonterminals().to_vec(),
+            bodies,
+        }
+    }
+
+    // Sets the bodies of head, dropping repeated ones
+    fn set_bodies(&mut self, head: usize, bodies: Vec<Vec<Symbol>>) {
+        let mut unique: Vec<Vec<Symbol>> = Vec::new();
+        for body in bodies {
+            if !unique.contains(&body) {
+                unique.push(body);
+            }
+        }
+        self.bodies[head] = unique;
+    }
+
+    // A new nonterminal named after base with primes appended, e.g. A' for A
+    fn fresh(&mut self, base: usize) -> usize {
+        let name = self.fresh_name(base);
+        self.nonterminals.push(name);
+        self.bodies.push(Vec::new());
+        self.nonterminals.len() - 1
+    }
+
+    // A new start symbol named after the old one, which becomes nonterminal 1
+    fn insert_start(&mut self) {
+        let name = self.fresh_name(0);
+        for symbol in self.bodies.iter_mut().flatten().flatten() {
+            if let Symbol::Nonterminal(nonterminal) = symbol {
+                *nonterminal += 1;
+            }
+        }
+        self.nonterminals.insert(0, name);
+        self.bodies.insert(0, Vec::new());
+    }
+
+    fn fresh_name(&self, base: usize) -> String {
+        let mut name = format!("{}'", self.nonterminals[base]);
+        while self.nonterminals.contains(&name) || self.terminals.contains(&name) {
+            name.push('\'');
+        }
+        name
+    }
+
+    fn is_used(&self, symbol: Symbol) -> bool {
+        self.bodies
+            .iter()
+            .flatten()
+            .any(|body| body.contains(&symbol))
+    }
+
+    // used_terminals()[terminal] tells whether any body uses terminal
+    fn used_terminals(&self) -> Vec<bool> {
+        (0..self.terminals.len())
+            .map(|terminal| self.is_used(Symbol::Terminal(terminal)))
+            .collect()
+    }
+
+    fn describe(&self, head: usize) -> String {
+        let bodies: Vec<String> = self.bodies[head]
+            .iter()
+            .map(|body| self.describe_body(body))
+            .collect();
+        format!("{} := {}", self.nonterminals[head], bodies.join(" | "))
+    }
+
+    fn describe_body(&self, body: &[Symbol]) -> String {
+        self.symbols().describe_body(body)
+    }
+
+    // The symbols as a Grammar without productions, numbered the same, so
+    // that bodies are described with Grammar's quoting
+    fn symbols(&self) -> Grammar {
+        let mut grammar = Grammar::new(&self.nonterminals[0]);
+        for name in self.nonterminals.iter().skip(1) {
+            grammar.add_nonterminal(name);
+        }
+        for name in self.terminals.iter().skip(1) {
+            grammar.add_terminal(name);
+        }
+        grammar
+    }
+
+    // Symbols that neither have productions nor appear in any body are
+    // dropped and the rest renumbered, keeping their order
+    fn build(&self) -> Grammar {
+        let mut grammar = Grammar::new(&self.nonterminals[0]);
+        let mut nonterminal_ids = vec![0; self.nonterminals.len()];
+        for (nonterminal, name) in self.nonterminals.iter().enumerate().skip(1) {
+            if !self.bodies[nonterminal].is_empty()
+                || self.is_used(Symbol::Nonterminal(nonterminal))
+            {
+                nonterminal_ids[nonterminal] = grammar.add_nonterminal(name);
+            }
+        }
+        let mut terminal_ids = vec![Grammar::END; self.terminals.len()];
+        for (terminal, name) in self.terminals.iter().enumerate().skip(1) {
+            if self.is_used(Symbol::Terminal(terminal)) {
+                terminal_ids[terminal] = grammar.add_terminal(name);
+            }
+        }
+        for (head, bodies) in self.bodies.iter().enumerate() {
+            for body in bodies.iter() {
+                let body = body
+                    .iter()
+                    .map(|symbol| match symbol {
+                        Symbol::Terminal(terminal) => Symbol::Terminal(terminal_ids[*terminal]),
+                        Symbol::Nonterminal(nonterminal) => {
+                            Symbol::Nonterminal(nonterminal_ids[*nonterminal])
+                        }
+                    })
+                    .collect();
+                grammar.add_production(nonterminal_ids[head], body);
+            }
+        }
+        grammar
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::bnf::BnfParser;
+    use super::super::ll1::{Ll1Table, PredictiveParser};
+    use super::*;
+    use crate::ch02::recursive_descent_parser::ex2_4_1_b::ParserB;
+    use rstest::rstest;
+
+    fn grammar(input: &str) -> Grammar {
+        BnfParser::new(input).parse().unwrap()
+    }
+
+    // Example 4.20
+    #[rstest]
+    fn test_eliminate_indirect_left_recursion() {
+        let transformed = eliminate_left_recursion(&grammar("S := A a | b\nA := A c | S d | ε"));
+
+        assert_eq!(
+            transformed.grammar.to_string(),
+            "S := A a | b\nA := b d A' | A'\nA' := c A' | a d A' | ε\n"
+        );
+        assert_eq!(
+            transformed.trace,
+            vec![
+                "substitute S in the productions of A that start with it: A := A c | A a d | b d | ε",
+                "eliminate the immediate left recursion of A: A := b d A' | A'; A' := c A' | a d A' | ε",
+            ]
+        );
+    }
+
+    // Grammar 4.1 becomes grammar 4.2
+    #[rstest]
+    fn test_eliminate_immediate_left_recursion() {
+        let transformed =
+            eliminate_left_recursion(&grammar("E := E + T | T\nT := T * F | F\nF := ( E ) | id"));
+
+        assert_eq!(
+            transformed.grammar.to_string(),
+            "E := T E'\nT := F T'\nF := ( E ) | id\nE' := + T E' | ε\nT' := * F T' | ε\n"
+        );
+        assert!(Ll1Table::new(&transformed.grammar).is_ll1());
+    }
+
+    #[rstest]
+    fn test_eliminate_cycle() {
+        let transformed = eliminate_left_recursion(&grammar("S := S | S a | b"));
+
+        assert_eq!(
+            transformed.grammar.to_string(),
+            "S := b S'\nS' := a S' | ε\n"
+        );
+        assert_eq!(transformed.trace[0], "drop the cycle S := S");
+    }
+
+    // Example 4.22
+    #[rstest]
+    fn test_left_factor() {
+        let transformed = left_factor(&grammar("S := i E t S | i E t S e S | a\nE := b"));
+
+        assert_eq!(
+            transformed.grammar.to_string(),
+            "S := i E t S S' | a\nE := b\nS' := ε | e S\n"
+        );
+        assert_eq!(
+            transformed.trace,
+            vec!["left factor i E t S out of S: S := i E t S S' | a; S' := ε | e S"]
+        );
+    }
+
+    #[rstest]
+    fn test_left_factor_repeatedly() {
+        let transformed = left_factor(&grammar("S := a b c | a b d | a e | f"));
+
+        assert_eq!(
+            transformed.grammar.to_string(),
+            "S := a S' | f\nS' := b S'' | e\nS'' := c | d\n"
+        );
+        assert_eq!(transformed.trace.len(), 2);
+    }
+
+    #[rstest]
+    fn test_left_factor_trace_quotes_like_display() {
+        let transformed = left_factor(&grammar("S := '|' a | '|' b"));
+
+        assert_eq!(
+            transformed.trace,
+            vec!["left factor '|' out of S: S := '|' S'; S' := a | b"]
+        );
+    }
+
+    #[rstest]
+    fn test_remove_useless_symbols() {
+        let transformed = remove_useless_symbols(&grammar("S := A B | a\nA := a | c\nB := B b"));
+
+        assert_eq!(transformed.grammar.to_string(), "S := a\n");
+        assert_eq!(transformed.grammar.terminals(), ["$", "a"]);
+        assert_eq!(
+            transformed.trace,
+            vec![
+                "remove B, which derives no terminal string",
+                "remove b, which only appears in productions using a nonterminal that derives \
+                 no terminal string",
+                "remove A, which is unreachable from S",
+                "remove c, which is unreachable from S",
+            ]
+        );
+    }
+
+    #[rstest]
+    #[case(
+        "S := A B\nA := a A | ε\nB := b B | ε",
+        "S := A | B | A B | ε\nA := a | a A\nB := b | b B\n"
+    )]
+    #[case(
+        "S := S ( S ) S | ε",
+        "S' := S | ε\nS := ( ) | S ( ) | ( S ) | S ( S ) | ( ) S | S ( ) S | ( S ) S | S ( S ) S\n"
+    )]
+    #[case("S := a S | b", "S := a S | b\n")]
+    fn test_remove_epsilon_productions(#[case] input: &str, #[case] expected: &str) {
+        let transformed = remove_epsilon_productions(&grammar(input));
+
+        assert_eq!(transformed.grammar.to_string(), expected);
+    }
+
+    #[rstest]
+    fn test_merge_aliases() {
+        let transformed = merge_aliases(&grammar("S := A\nA := B\nB := a B | ε"));
+
+        assert_eq!(transformed.grammar.to_string(), "S := a S | ε\n");
+        assert_eq!(transformed.trace.len(), 2);
+    }
+
+    #[rstest]
+    fn test_merge_aliases_of_start_symbol() {
+        let transformed = merge_aliases(&grammar("S := a A | b\nA := S"));
+
+        assert_eq!(transformed.grammar.to_string(), "S := a S | b\n");
+        assert_eq!(
+            transformed.trace,
+            vec!["merge A into S, its only production being A := S: S := a S | b"]
+        );
+    }
+
+    // ParserB's rewrite of S := S ( S ) S | ε, done by the transformations
+    fn parser_b_grammar() -> Transformed {
+        eliminate_left_recursion(&grammar("S := S ( S ) S | ε"))
+            .then(merge_aliases)
+            .then(merge_repeated_closures)
+            .then(left_factor)
+    }
+
+    #[rstest]
+    fn test_parser_b_grammar() {
+        let transformed = parser_b_grammar();
+
+        assert_eq!(transformed.grammar.to_string(), "S := ( S ) S | ε\n");
+        assert_eq!(
+            transformed.trace,
+            vec![
+                "eliminate the immediate left recursion of S: S := S'; S' := ( S ) S S' | ε",
+                "merge S' into S, its only production being S := S': S := ( S ) S S | ε",
+                "write S S as S, since S derives a closure: S := ( S ) S | ε",
+            ]
+        );
+        assert!(Ll1Table::new(&transformed.grammar).is_ll1());
+    }
+
+    #[rstest]
+    #[case("")]
+    #[case("()")]
+    #[case("()()()")]
+    #[case("(()())()(())")]
+    #[case("(())()")]
+    #[case("(())(())(())")]
+    #[case("a")]
+    #[case("(a)")]
+    #[case("(")]
+    #[case("(()")]
+    #[case(")")]
+    #[case(")(")]
+    #[case("())")]
+    #[case("()())")]
+    fn test_parser_b_grammar_accepts_what_parser_b_does(#[case] input: &str) {
+        let table = Ll1Table::new(&parser_b_grammar().grammar);
+        let parser = PredictiveParser::new(&table).unwrap();
+
+        let result = parser.parse_chars(input);
+        let expected = ParserB::new(input).parse();
+        assert_eq!(result.is_ok(), expected.is_ok());
+        if let (Err(error), Err(expected)) = (result, expected) {
+            assert_eq!(error.kind, expected.kind);
+            assert_eq!(error.span, expected.span);
+        }
+    }
+}
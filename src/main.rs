@@ -1,27 +1,30 @@
-use ch02::lexical_analyzer::ex_2_6::{Lexer, Token};
+use ch02::lexical_analyzer::ex_2_6::{Lexer, SpannedToken, Token};
 use ch02::recursive_descent_parser::ex2_4_1_a::ParserA;
 use ch02::recursive_descent_parser::ex2_4_1_b::ParserB;
 use ch02::recursive_descent_parser::ex2_4_1_c::ParserC;
+use ch02::symbol_table::interner::Interner;
 
 pub mod ch02;
+pub mod ch03;
+pub mod ch04;
 
 fn main() {
     match ParserA::new("+aa").parse() {
-        Ok(_) => println!("Success!"),
-        Err(err) => println!("{}", err),
+        Ok(tree) => println!("{}", tree),
+        Err(err) => println!("{}", err.render("+aa")),
     }
 
     match ParserB::new("()()()").parse() {
-        Ok(_) => println!("Success!"),
-        Err(err) => println!("{}", err),
+        Ok(tree) => println!("{}", tree),
+        Err(err) => println!("{}", err.render("()()()")),
     }
 
     match ParserC::new("01").parse() {
-        Ok(_) => println!("Success!"),
-        Err(err) => println!("{}", err),
+        Ok(tree) => println!("{}", tree),
+        Err(err) => println!("{}", err.render("01")),
     }
 
-    let tokens = Lexer::new(
+    let mut lexer = Lexer::new(
         "// random  comment \n\
         hello = 12    * 5\t + 3\n\
         boolean_variable_=true | false //comment at the /* end\n\
@@ -29,17 +32,31 @@ fn main() {
         commented_variable = 3 */\n
         > >= < <= == !=\n
         2. 3.14 .5",
-    )
-    .tokenize();
-    print_tokens(tokens);
+    );
+    let tokens = lexer.tokenize();
+    print_tokens(tokens, lexer.interner());
+    for error in lexer.errors() {
+        println!("{}", error);
+    }
 }
 
-fn print_tokens(tokens: Vec<Token>) {
-    for token in tokens.iter() {
+fn print_tokens(tokens: Vec<SpannedToken>, interner: &Interner) {
+    for SpannedToken { token, span } in tokens.iter() {
+        print!("{}:{} ", span.start.line, span.start.column);
         match token {
-            Token::Word(tag, lexeme) => println!("{:?} - {}", tag, lexeme),
-            Token::Number(integer, decimal) => println!("Number - {}.{}", integer, decimal),
+            Token::Word(tag, symbol) => println!("{:?} - {}", tag, interner.resolve(*symbol)),
+            Token::Number(value, lexeme) => println!("{:?} - {}", value, lexeme),
+            Token::StringLiteral(value, lexeme) => println!("String {:?} - {}", value, lexeme),
+            Token::CharLiteral(value, lexeme) => println!("Char {:?} - {}", value, lexeme),
             Token::LogicalOperator(tag, lexeme) => println!("{:?} - {}", tag, lexeme),
+            Token::ArithmeticOperator(tag, lexeme) => println!("{:?} - {}", tag, lexeme),
+            Token::LogicalConnective(tag, lexeme) => println!("{:?} - {}", tag, lexeme),
+            Token::BitwiseOperator(tag, lexeme) => println!("{:?} - {}", tag, lexeme),
+            Token::Assign(lexeme) => println!("Assign - {}", lexeme),
+            Token::Punctuation(tag, lexeme) => println!("{:?} - {}", tag, lexeme),
+            Token::Comment(lexeme) => println!("Comment - {}", lexeme),
+            Token::DocComment(text, lexeme) => println!("DocComment {:?} - {}", text, lexeme),
+            Token::Error(error, lexeme) => println!("Error - {}: {}", error, lexeme),
             Token::Unknown(lexeme) => println!("{}", lexeme),
             Token::Epsilon => println!("Epsilon"),
         }